@@ -1,14 +1,21 @@
 // Day 1: Trebuchet?!
 
-use regex::Regex;
+use std::cmp::Reverse;
 
+use crate::framework::aho_corasick::AhoCorasick;
 use crate::framework::Day;
 use crate::prelude::*;
 
 pub struct Day1;
 
+const ENGLISH_DIGIT_WORDS: [&str; 9] = [
+    "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+];
+
 lazy_static! {
     static ref PUZZLE_INPUT: String = include_str!("./day01_input.txt").trim().to_string();
+    static ref SPELLED_OUT_CALIBRATOR: Calibrator =
+        Calibrator::new(&TokenTable::digits().with_words(&ENGLISH_DIGIT_WORDS, 1)).unwrap();
 }
 
 impl Day for Day1 {
@@ -25,6 +32,21 @@ impl Day for Day1 {
         let result = sum_of_calibration_values_mk2(&PUZZLE_INPUT);
         Some(result.map(|it| it.to_string()))
     }
+
+    fn run_script(&self, name: &str) -> Result<bool> {
+        match name {
+            "calibration_report" => {
+                for line in PUZZLE_INPUT.lines() {
+                    match SPELLED_OUT_CALIBRATOR.find_tokens(line) {
+                        Some(tokens) => println!("{}: {}", line, tokens.describe(line)),
+                        None => println!("{}: no digits found", line),
+                    }
+                }
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
 }
 
 fn get_calibration_value(line: &str) -> Result<u32> {
@@ -42,40 +64,118 @@ fn sum_of_calibration_values(input: &str) -> Result<u32> {
         .sum::<Result<u32>>()
 }
 
-fn get_calibration_value_mk2(line: &str) -> Result<u32> {
-    let digit_regex_template = r"([0-9]|one|two|three|four|five|six|seven|eight|nine)";
-    let first_digit_regex = Regex::new(digit_regex_template).unwrap();
-    let last_digit_regex = Regex::new(&format!(".*+{}", digit_regex_template)).unwrap();
-    let first_digit_capture = first_digit_regex
-        .captures(line)
-        .ok_or(anyhow!("No digits found"))?
-        .get(1)
-        .unwrap()
-        .as_str();
-    let last_digit_capture = last_digit_regex
-        .captures(line)
-        .ok_or(anyhow!("No digits found"))?
-        .get(1)
-        .unwrap()
-        .as_str();
-    let first = digit(first_digit_capture)?;
-    let last = digit(last_digit_capture)?;
-    Ok(first * 10 + last)
+/// The strings that count as digits, and the digit each one stands for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct TokenTable(Vec<(String, u32)>);
+
+impl TokenTable {
+    /// Just "0" through "9".
+    fn digits() -> Self {
+        TokenTable((0..10).map(|digit| (digit.to_string(), digit)).collect())
+    }
+
+    fn with_token(mut self, token: &str, value: u32) -> Self {
+        self.0.push((token.to_string(), value));
+        self
+    }
+
+    /// Adds `words` as consecutive digits, starting from `first_value`, so a language's words for
+    /// one to nine would start at 1.
+    fn with_words(self, words: &[&str], first_value: u32) -> Self {
+        words
+            .iter()
+            .zip(first_value..)
+            .fold(self, |table, (word, value)| table.with_token(word, value))
+    }
+}
+
+/// Where a token was found in a line, in bytes, and the digit it stands for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct TokenMatch {
+    start: usize,
+    end: usize,
+    value: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct CalibrationTokens {
+    first: TokenMatch,
+    last: TokenMatch,
+}
+
+impl CalibrationTokens {
+    fn calibration_value(&self) -> u32 {
+        self.first.value * 10 + self.last.value
+    }
+
+    /// Shows which parts of `line` were picked, for tracking down lines that score wrong.
+    fn describe(&self, line: &str) -> String {
+        let describe_token = |token: &TokenMatch| {
+            format!(
+                "{:?} at {}..{}",
+                &line[token.start..token.end],
+                token.start,
+                token.end
+            )
+        };
+        format!(
+            "first {}, last {} => {}",
+            describe_token(&self.first),
+            describe_token(&self.last),
+            self.calibration_value()
+        )
+    }
 }
 
-fn digit(input: &str) -> Result<u32> {
-    match input {
-        "one" => Ok(1),
-        "two" => Ok(2),
-        "three" => Ok(3),
-        "four" => Ok(4),
-        "five" => Ok(5),
-        "six" => Ok(6),
-        "seven" => Ok(7),
-        "eight" => Ok(8),
-        "nine" => Ok(9),
-        numeric => numeric.parse::<u32>().map_err(Into::into),
+/// Finds the first and last digit tokens in a line, scanning it once. Tokens are allowed to
+/// overlap, so "twone" starts with a 2 and ends with a 1.
+struct Calibrator(AhoCorasick<u32>);
+
+impl Calibrator {
+    fn new(table: &TokenTable) -> Result<Self> {
+        if let Some((token, value)) = table.0.iter().find(|(_, value)| *value > 9) {
+            return Err(anyhow!(
+                "{:?} stands for {}, which isn't a digit",
+                token,
+                value
+            ));
+        }
+        AhoCorasick::new(table.0.iter().map(|(token, value)| (token, *value))).map(Calibrator)
+    }
+
+    /// When two tokens start at the same place, the longer one wins.
+    fn find_tokens(&self, line: &str) -> Option<CalibrationTokens> {
+        let mut matches = self.0.find_overlapping(line).map(|it| TokenMatch {
+            start: it.start,
+            end: it.end,
+            value: *it.value,
+        });
+        let first_match = matches.next()?;
+        let (first, last) = matches.fold((first_match, first_match), |(first, last), token| {
+            let first = if (token.start, Reverse(token.end)) < (first.start, Reverse(first.end)) {
+                token
+            } else {
+                first
+            };
+            let last = if (token.start, token.end) > (last.start, last.end) {
+                token
+            } else {
+                last
+            };
+            (first, last)
+        });
+        Some(CalibrationTokens { first, last })
     }
+
+    fn calibration_value(&self, line: &str) -> Result<u32> {
+        self.find_tokens(line)
+            .map(|tokens| tokens.calibration_value())
+            .ok_or_else(|| anyhow!("No digits found in {:?}", line))
+    }
+}
+
+fn get_calibration_value_mk2(line: &str) -> Result<u32> {
+    SPELLED_OUT_CALIBRATOR.calibration_value(line)
 }
 
 fn sum_of_calibration_values_mk2(input: &str) -> Result<u32> {
@@ -144,4 +244,57 @@ mod test {
         "};
         assert_eq!(sum_of_calibration_values_mk2(input).unwrap(), 281);
     }
+
+    #[test]
+    fn test_overlapping_tokens() {
+        let tokens = SPELLED_OUT_CALIBRATOR.find_tokens("xtwone").unwrap();
+        assert_eq!(
+            tokens,
+            CalibrationTokens {
+                first: TokenMatch {
+                    start: 1,
+                    end: 4,
+                    value: 2
+                },
+                last: TokenMatch {
+                    start: 3,
+                    end: 6,
+                    value: 1
+                },
+            }
+        );
+        assert_eq!(
+            tokens.describe("xtwone"),
+            "first \"two\" at 1..4, last \"one\" at 3..6 => 21"
+        );
+        assert_eq!(get_calibration_value_mk2("oneight").unwrap(), 18);
+        assert_eq!(get_calibration_value_mk2("eightwo").unwrap(), 82);
+        assert!(SPELLED_OUT_CALIBRATOR.find_tokens("abcdef").is_none());
+        assert!(get_calibration_value_mk2("").is_err());
+    }
+
+    #[test]
+    fn test_custom_token_tables() {
+        let german = TokenTable::digits()
+            .with_words(&["null", "eins", "zwei", "drei", "vier"], 0)
+            .pipe(|table| Calibrator::new(&table))
+            .unwrap();
+        assert_eq!(german.calibration_value("zweinsx").unwrap(), 21);
+        assert_eq!(german.calibration_value("nullvier").unwrap(), 4);
+
+        let with_aliases = TokenTable::digits()
+            .with_words(&ENGLISH_DIGIT_WORDS, 1)
+            .with_token("zero", 0)
+            .with_token("eleventy", 9)
+            .with_token("eleven", 1)
+            .pipe(|table| Calibrator::new(&table))
+            .unwrap();
+        assert_eq!(with_aliases.calibration_value("zeroeleventy").unwrap(), 9);
+        assert_eq!(with_aliases.calibration_value("xelevenx").unwrap(), 11);
+
+        let not_a_digit = TokenTable::digits().with_token("ten", 10);
+        assert!(Calibrator::new(&not_a_digit).is_err());
+        let repeated = TokenTable::digits().with_token("1", 1);
+        assert!(Calibrator::new(&repeated).is_err());
+    }
 }
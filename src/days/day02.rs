@@ -1,5 +1,7 @@
 // Day 2: Cube Conundrum
 
+use std::collections::BTreeMap;
+use std::fmt::Display;
 use std::ops::Add;
 use std::str::FromStr;
 
@@ -16,6 +18,11 @@ fn puzzle_input() -> Result<Vec<Game>> {
         .collect()
 }
 
+/// The bag part 1 asks about.
+fn puzzle_bag() -> Inventory {
+    PUZZLE_COLORS.into_iter().zip([12, 13, 14]).collect()
+}
+
 impl Day for Day2 {
     fn day_number(&self) -> u8 {
         2
@@ -23,11 +30,7 @@ impl Day for Day2 {
 
     fn part1(&self) -> Option<Result<String>> {
         let games = puzzle_input();
-        let inventory = Inventory {
-            red: 12,
-            green: 13,
-            blue: 14,
-        };
+        let inventory = puzzle_bag();
         let possible_game_ids = games.map(|it| inventory.possible_game_ids(&it));
         let sum = possible_game_ids.map(|it| it.iter().sum::<u32>());
         Some(sum.map(|it| it.to_string()))
@@ -42,15 +45,37 @@ impl Day for Day2 {
         let sum = powers.sum::<u32>();
         Some(Ok(sum.to_string()))
     }
-}
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum Color {
-    RED,
-    GREEN,
-    BLUE,
+    fn run_script(&self, name: &str) -> Result<bool> {
+        match name {
+            "bag_queries" => {
+                let games = puzzle_input()?;
+                let bag = puzzle_bag();
+                let possible = bag.possible_game_ids(&games);
+                println!("Possible with {}: {} games", bag, possible.len());
+                println!(
+                    "Smallest bag for every game: {}",
+                    smallest_inventory_for(&games)
+                );
+                match bag.best_extra_cube(&games) {
+                    Some((color, ids)) => println!(
+                        "Best extra cube: 1 {}, making {} more games possible ({})",
+                        color,
+                        ids.len(),
+                        ids.iter().join(", ")
+                    ),
+                    None => println!("No single extra cube makes another game possible"),
+                }
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
 }
 
+/// The colors in the puzzle's bag, which is all that `Game::power` multiplies together.
+const PUZZLE_COLORS: [&str; 3] = ["red", "green", "blue"];
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 struct Game {
     id: u32,
@@ -62,86 +87,136 @@ impl Game {
         self.pulls.iter().all(|pull| pull.fits_in(inventory))
     }
 
+    /// The fewest cubes of each color that could have produced every pull.
+    fn minimum_inventory(&self) -> Inventory {
+        self.pulls
+            .iter()
+            .fold(Inventory::default(), |minimum, pull| minimum.max(pull))
+    }
+
     fn power(&self) -> u32 {
-        let mut minimum_inventory = Inventory::default();
-        for pull in &self.pulls {
-            minimum_inventory.red = minimum_inventory.red.max(pull.red);
-            minimum_inventory.green = minimum_inventory.green.max(pull.green);
-            minimum_inventory.blue = minimum_inventory.blue.max(pull.blue);
-        }
-        minimum_inventory.red * minimum_inventory.green * minimum_inventory.blue
+        let minimum_inventory = self.minimum_inventory();
+        PUZZLE_COLORS
+            .iter()
+            .map(|color| minimum_inventory.count(color))
+            .product()
     }
 }
 
+lazy_static! {
+    static ref GAME_REGEX: regex::Regex = regex::Regex::new(r"^Game (\d+): (.*)$").unwrap();
+    static ref QUANTITY_REGEX: regex::Regex = regex::Regex::new(r"^(\d+) (\S+)$").unwrap();
+}
+
 impl FromStr for Game {
     type Err = Error;
 
     fn from_str(s: &str) -> std::prelude::v1::Result<Self, Self::Err> {
         let error = || anyhow!("Invalid game string: {}", s);
-        let game_match = regex::Regex::new(r"Game (\d+): (.*)")
-            .unwrap()
-            .captures(s)
-            .ok_or_else(error)?;
+        let game_match = GAME_REGEX.captures(s).ok_or_else(error)?;
         let id = game_match.get(1).unwrap().as_str().parse::<u32>()?;
-        let pulls = {
-            let pulls_strings = game_match
-                .get(2)
-                .unwrap()
-                .as_str()
-                .split(";")
-                .map(str::trim);
-            pulls_strings
-                .map(|it| {
-                    let qty_strings = it.split(",").map(str::trim);
-                    let mut inventory = Inventory::default();
-                    for qty_string in qty_strings {
-                        let qty_match = regex::Regex::new(r"(\d+) (red|green|blue)")
-                            .unwrap()
-                            .captures(qty_string)
-                            .ok_or_else(error)?;
-                        let qty = qty_match.get(1).unwrap().as_str().parse::<u32>()?;
-                        let color = match qty_match.get(2).unwrap().as_str() {
-                            "red" => Color::RED,
-                            "green" => Color::GREEN,
-                            "blue" => Color::BLUE,
-                            _ => return Err(error()),
-                        };
-                        match color {
-                            Color::RED => inventory.red += qty,
-                            Color::GREEN => inventory.green += qty,
-                            Color::BLUE => inventory.blue += qty,
-                        }
-                    }
-                    Ok(inventory)
-                })
-                .collect::<Result<Vec<_>>>()
-        }?;
+        let pulls = game_match
+            .get(2)
+            .unwrap()
+            .as_str()
+            .split(";")
+            .map(Inventory::from_str)
+            .collect::<Result<Vec<_>>>()
+            .map_err(|err| err.context(error()))?;
         Ok(Game { id, pulls })
     }
 }
 
+/// Cube counts keyed by color name. Colors with no cubes aren't stored, so inventories compare
+/// equal no matter how they were built.
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
-struct Inventory {
-    red: u32,
-    green: u32,
-    blue: u32,
+struct Inventory(BTreeMap<String, u32>);
+
+impl<'a> FromIterator<(&'a str, u32)> for Inventory {
+    fn from_iter<T: IntoIterator<Item = (&'a str, u32)>>(iter: T) -> Self {
+        iter.into_iter()
+            .fold(Inventory::default(), |inventory, (color, count)| {
+                inventory + Inventory::single(color, count)
+            })
+    }
+}
+
+impl FromStr for Inventory {
+    type Err = Error;
+
+    /// Parses a single pull, like `3 blue, 4 red`.
+    fn from_str(s: &str) -> Result<Self> {
+        let mut inventory = Inventory::default();
+        for quantity in s.split(",").map(str::trim) {
+            let quantity_match = QUANTITY_REGEX
+                .captures(quantity)
+                .ok_or_else(|| anyhow!("Invalid quantity: {:?}", quantity))?;
+            let count = quantity_match.get(1).unwrap().as_str().parse::<u32>()?;
+            let color = quantity_match.get(2).unwrap().as_str();
+            inventory = inventory
+                .checked_add(&Inventory::single(color, count))
+                .ok_or_else(|| anyhow!("Too many {} cubes in {:?}", color, s))?;
+        }
+        Ok(inventory)
+    }
+}
+
+impl Display for Inventory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let quantities = self
+            .0
+            .iter()
+            .map(|(color, count)| format!("{} {}", count, color))
+            .join(", ");
+        f.write_str(&quantities)
+    }
 }
 
 impl Add for Inventory {
     type Output = Inventory;
 
+    /// Panics on overflow, like adding numbers does.
     fn add(self, other: Inventory) -> Inventory {
-        Inventory {
-            red: self.red + other.red,
-            green: self.green + other.green,
-            blue: self.blue + other.blue,
-        }
+        self.checked_add(&other).expect("cube count overflowed")
     }
 }
 
 impl Inventory {
+    fn single(color: &str, count: u32) -> Self {
+        let mut inventory = Inventory::default();
+        if count > 0 {
+            inventory.0.insert(color.to_string(), count);
+        }
+        inventory
+    }
+
+    fn count(&self, color: &str) -> u32 {
+        self.0.get(color).copied().unwrap_or(0)
+    }
+
+    fn checked_add(&self, other: &Inventory) -> Option<Inventory> {
+        let mut result = self.clone();
+        for (color, count) in &other.0 {
+            let total = result.0.entry(color.clone()).or_default();
+            *total = total.checked_add(*count)?;
+        }
+        Some(result)
+    }
+
+    /// The larger count of each color.
+    fn max(&self, other: &Inventory) -> Inventory {
+        let mut result = self.clone();
+        for (color, count) in &other.0 {
+            let larger = result.0.entry(color.clone()).or_default();
+            *larger = (*larger).max(*count);
+        }
+        result
+    }
+
     fn fits_in(&self, other: &Inventory) -> bool {
-        self.red <= other.red && self.green <= other.green && self.blue <= other.blue
+        self.0
+            .iter()
+            .all(|(color, count)| *count <= other.count(color))
     }
 
     fn possible_game_ids<'a>(&self, games: impl IntoIterator<Item = &'a Game>) -> Vec<u32> {
@@ -151,6 +226,45 @@ impl Inventory {
             .map(|game| game.id)
             .collect()
     }
+
+    /// The color to add one more cube of to make the most impossible games possible, along with
+    /// the games it would make possible. Ties go to the color that sorts first. Returns `None` if
+    /// no single cube would make any difference.
+    fn best_extra_cube<'a>(
+        &self,
+        games: impl IntoIterator<Item = &'a Game>,
+    ) -> Option<(String, Vec<u32>)> {
+        let mut newly_possible = BTreeMap::<&str, Vec<u32>>::new();
+        let minimum_inventories = games
+            .into_iter()
+            .map(|game| (game.id, game.minimum_inventory()))
+            .collect_vec();
+        for (id, minimum_inventory) in &minimum_inventories {
+            let shortfalls = minimum_inventory
+                .0
+                .iter()
+                .filter(|(color, count)| **count > self.count(color))
+                .map(|(color, count)| (color.as_str(), count - self.count(color)))
+                .collect_vec();
+            if let [(color, 1)] = shortfalls[..] {
+                newly_possible.entry(color).or_default().push(*id);
+            }
+        }
+        newly_possible
+            .into_iter()
+            .rev()
+            .max_by_key(|(_, ids)| ids.len())
+            .map(|(color, ids)| (color.to_string(), ids))
+    }
+}
+
+/// The smallest bag that makes every game possible.
+fn smallest_inventory_for<'a>(games: impl IntoIterator<Item = &'a Game>) -> Inventory {
+    games
+        .into_iter()
+        .fold(Inventory::default(), |inventory, game| {
+            inventory.max(&game.minimum_inventory())
+        })
 }
 
 #[cfg(test)]
@@ -158,19 +272,22 @@ mod test {
     use std::ops::Not;
 
     use super::*;
+    use crate::framework::fuzz;
+
+    const SAMPLE_INPUT: &str = indoc! {"
+        Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green
+        Game 2: 1 blue, 2 green; 3 green, 4 blue, 1 red; 1 green, 1 blue
+        Game 3: 8 green, 6 blue, 20 red; 5 blue, 4 red, 13 green; 5 green, 1 red
+        Game 4: 1 green, 3 red, 6 blue; 3 green, 6 red; 3 green, 15 blue, 14 red
+        Game 5: 6 red, 1 blue, 3 green; 2 blue, 1 red, 2 green
+    "};
 
     fn test_input() -> Vec<Game> {
-        indoc! {"
-            Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green
-            Game 2: 1 blue, 2 green; 3 green, 4 blue, 1 red; 1 green, 1 blue
-            Game 3: 8 green, 6 blue, 20 red; 5 blue, 4 red, 13 green; 5 green, 1 red
-            Game 4: 1 green, 3 red, 6 blue; 3 green, 6 red; 3 green, 15 blue, 14 red
-            Game 5: 6 red, 1 blue, 3 green; 2 blue, 1 red, 2 green
-        "}
-        .lines()
-        .map(Game::from_str)
-        .collect::<Result<Vec<_>>>()
-        .unwrap()
+        SAMPLE_INPUT
+            .lines()
+            .map(Game::from_str)
+            .collect::<Result<Vec<_>>>()
+            .unwrap()
     }
 
     #[test]
@@ -189,60 +306,41 @@ mod test {
         let expected_game = Game {
             id: 1,
             pulls: vec![
-                Inventory {
-                    red: 4,
-                    green: 0,
-                    blue: 3,
-                },
-                Inventory {
-                    red: 1,
-                    green: 2,
-                    blue: 6,
-                },
-                Inventory {
-                    red: 0,
-                    green: 2,
-                    blue: 0,
-                },
+                Inventory::from_iter([("red", 4), ("green", 0), ("blue", 3)]),
+                Inventory::from_iter([("red", 1), ("green", 2), ("blue", 6)]),
+                Inventory::from_iter([("red", 0), ("green", 2), ("blue", 0)]),
             ],
         };
         let result = Game::from_str(input_string).unwrap();
         assert_eq!(expected_game, result);
     }
 
+    #[test]
+    fn test_parser_never_panics() {
+        let corpus = SAMPLE_INPUT.lines().collect_vec();
+        fuzz::check_parser("Game", &corpus, Game::from_str);
+        assert!(Game::from_str("Game 1: 4294967295 red, 1 red").is_err());
+    }
+
     #[test]
     fn test_inventory_fits_in() {
-        assert!(Inventory {
-            red: 1,
-            green: 1,
-            blue: 1,
-        }
-        .fits_in(&Inventory {
-            red: 1,
-            green: 2,
-            blue: 1,
-        }));
-        assert!((Inventory {
-            red: 1,
-            green: 1,
-            blue: 4,
-        }
-        .fits_in(&Inventory {
-            red: 5,
-            green: 1,
-            blue: 1,
-        }))
-        .not());
+        assert!(
+            Inventory::from_iter([("red", 1), ("green", 1), ("blue", 1)]).fits_in(
+                &Inventory::from_iter([("red", 1), ("green", 2), ("blue", 1)])
+            )
+        );
+        assert!(
+            (Inventory::from_iter([("red", 1), ("green", 1), ("blue", 4)]).fits_in(
+                &Inventory::from_iter([("red", 5), ("green", 1), ("blue", 1)])
+            ))
+            .not()
+        );
     }
 
     #[test]
     fn test_possible_game_ids() {
         let games = test_input();
-        let inventory = Inventory {
-            red: 12,
-            green: 13,
-            blue: 14,
-        };
+        let inventory = Inventory::from_iter([("red", 12), ("green", 13), ("blue", 14)]);
         assert_eq!(inventory.possible_game_ids(&games), vec![1, 2, 5]);
     }
 
@@ -252,4 +350,35 @@ mod test {
         let powers: Vec<u32> = games.iter().map(|game| game.power()).collect();
         assert_eq!(powers, vec![48, 12, 1560, 630, 36]);
     }
+
+    #[test]
+    fn test_any_colors() {
+        let game = Game::from_str("Game 7: 2 teal, 1 red; 3 teal, 0 mauve").unwrap();
+        let minimum_inventory = game.minimum_inventory();
+        assert_eq!(minimum_inventory.to_string(), "1 red, 3 teal");
+        assert_eq!(minimum_inventory.count("mauve"), 0);
+        assert_eq!(game.power(), 0);
+        assert!(!game.is_possible_with_inventory(&puzzle_bag()));
+        assert!(Game::from_str("Game 7: 2 teal red").is_err());
+    }
+
+    #[test]
+    fn test_bag_queries() {
+        let games = test_input();
+        assert_eq!(
+            smallest_inventory_for(&games),
+            Inventory::from_iter([("red", 20), ("green", 13), ("blue", 15)])
+        );
+        assert_eq!(puzzle_bag().best_extra_cube(&games), None);
+        let bag = Inventory::from_iter([("red", 14), ("green", 13), ("blue", 14)]);
+        assert_eq!(
+            bag.best_extra_cube(&games),
+            Some(("blue".to_string(), vec![4]))
+        );
+        let tied = Inventory::from_iter([("red", 19), ("green", 13), ("blue", 14)]);
+        assert_eq!(
+            tied.best_extra_cube(&games),
+            Some(("blue".to_string(), vec![4]))
+        );
+    }
 }
@@ -1,8 +1,12 @@
 // Day 3: Gear Ratios
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::iter;
 use std::str::FromStr;
 
+use clap::{Parser, ValueEnum};
+
+use crate::framework::grid::{GridShape, IntVector};
 use crate::framework::Day;
 use crate::prelude::*;
 
@@ -22,7 +26,7 @@ impl Day for Day3 {
             Result::Ok(grid) => grid,
             Err(e) => return Some(Err(e)),
         };
-        let numbers = grid.find_numbers_adjacent_to_symbols();
+        let numbers = grid.find_numbers_adjacent_to_symbols(&SchematicRules::default());
         let sum = numbers.iter().sum::<u32>();
         Some(Ok(sum.to_string()))
     }
@@ -32,34 +36,103 @@ impl Day for Day3 {
             Result::Ok(grid) => grid,
             Err(e) => return Some(Err(e)),
         };
-        let ratios = grid.gear_ratios();
-        let sum = ratios.into_iter().sum::<u32>();
+        let ratios = grid.gear_ratios(&SchematicRules::default());
+        let sum = ratios.into_iter().sum::<u64>();
         Some(Ok(sum.to_string()))
     }
+
+    fn run_script_with_args(&self, name: &str, args: &[String]) -> Result<bool> {
+        match name {
+            "annotated_schematic" => {
+                let rules = SchematicArgs::try_parse_from(
+                    iter::once(name).chain(args.iter().map(String::as_str)),
+                )?
+                .rules()?;
+                let grid = grid()?;
+                print!("{}", grid.annotate(&rules));
+                println!(
+                    "Part numbers: {}, gear ratios: {}",
+                    grid.find_numbers_adjacent_to_symbols(&rules)
+                        .iter()
+                        .sum::<u32>(),
+                    grid.gear_ratios(&rules).iter().sum::<u64>()
+                );
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
-struct Coordinate {
-    x: i32,
-    y: i32,
+/// Picks the rules from the command line, e.g.
+/// `--script annotated_schematic -- --part-symbols '#*' --gear-size 3 --gear-ratio sum`.
+#[derive(Debug, Parser)]
+struct SchematicArgs {
+    /// The symbols that mark part numbers; every symbol counts if this is left out
+    #[arg(long)]
+    part_symbols: Option<String>,
+    #[arg(long, default_value_t = '*')]
+    gear_symbol: char,
+    /// How many numbers have to be next to a gear symbol for it to be a gear
+    #[arg(long, default_value_t = 2)]
+    gear_size: usize,
+    #[arg(long, value_enum, default_value_t = GearRatio::Product)]
+    gear_ratio: GearRatio,
 }
 
-impl Coordinate {
-    fn new(x: i32, y: i32) -> Self {
-        Coordinate { x, y }
+impl SchematicArgs {
+    fn rules(&self) -> Result<SchematicRules> {
+        if self.gear_size == 0 {
+            return Err(anyhow!("a gear needs at least one number next to it"));
+        }
+        Ok(SchematicRules {
+            part_symbols: self
+                .part_symbols
+                .as_ref()
+                .map(|symbols| symbols.chars().collect()),
+            gear_symbol: self.gear_symbol,
+            gear_size: self.gear_size,
+            gear_ratio: self.gear_ratio,
+        })
     }
+}
+
+/// How a gear's numbers combine into its ratio.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum GearRatio {
+    Product,
+    Sum,
+}
 
-    fn adjacent_coordinates(&self) -> Vec<Coordinate> {
-        vec![
-            Coordinate::new(self.x - 1, self.y - 1),
-            Coordinate::new(self.x, self.y - 1),
-            Coordinate::new(self.x + 1, self.y - 1),
-            Coordinate::new(self.x - 1, self.y),
-            Coordinate::new(self.x + 1, self.y),
-            Coordinate::new(self.x - 1, self.y + 1),
-            Coordinate::new(self.x, self.y + 1),
-            Coordinate::new(self.x + 1, self.y + 1),
-        ]
+/// What makes a number a part number, and what makes a symbol a gear. The default is the
+/// puzzle's: any symbol marks part numbers, and a `*` next to exactly two numbers is a gear whose
+/// ratio is their product.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SchematicRules {
+    /// `None` means every symbol counts.
+    part_symbols: Option<HashSet<char>>,
+    gear_symbol: char,
+    /// How many numbers have to be next to a gear symbol for it to be a gear
+    gear_size: usize,
+    gear_ratio: GearRatio,
+}
+
+impl Default for SchematicRules {
+    fn default() -> Self {
+        SchematicRules {
+            part_symbols: None,
+            gear_symbol: '*',
+            gear_size: 2,
+            gear_ratio: GearRatio::Product,
+        }
+    }
+}
+
+impl SchematicRules {
+    fn is_part_symbol(&self, symbol: char) -> bool {
+        self.part_symbols
+            .as_ref()
+            .is_none_or(|symbols| symbols.contains(&symbol))
     }
 }
 
@@ -71,32 +144,24 @@ enum GridCell {
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 struct Grid {
-    width: usize,
+    shape: GridShape,
     cells: Vec<Option<GridCell>>,
 }
 
 impl Grid {
-    fn get(&self, coord: Coordinate) -> Option<GridCell> {
-        let index = self.index(coord);
-        self.cells.get(index).copied().flatten()
-    }
-
-    fn index(&self, coord: Coordinate) -> usize {
-        (coord.y * self.width as i32 + coord.x) as usize
-    }
-
-    fn coordinate_for_index(&self, index: usize) -> Coordinate {
-        let x = index % self.width;
-        let y = index / self.width;
-        Coordinate::new(x as i32, y as i32)
+    fn get(&self, coord: IntVector) -> Option<GridCell> {
+        if !self.shape.in_bounds(coord) {
+            return None;
+        }
+        self.cells[self.shape.arr_index(coord)]
     }
 
-    fn find_all_symbols(&self) -> Vec<(Coordinate, char)> {
+    fn find_all_symbols(&self) -> Vec<(IntVector, char)> {
         self.cells
             .iter()
             .enumerate()
             .filter_map(|(index, cell)| {
-                let coordinate = self.coordinate_for_index(index);
+                let coordinate = self.shape.coordinate_for_index(index);
                 match cell {
                     Some(GridCell::Symbol(c)) => Some((coordinate, *c)),
                     _ => None,
@@ -105,14 +170,10 @@ impl Grid {
             .collect()
     }
 
-    fn height(&self) -> usize {
-        self.cells.len() / self.width
-    }
-
     fn find_all_numbers(&self) -> Vec<Number> {
         let mut result = Vec::new();
-        for y in 0..self.height() {
-            type CurrentNumber = (Vec<u8>, Coordinate);
+        for y in 0..self.shape.height {
+            type CurrentNumber = (Vec<u8>, IntVector);
             fn current_number_to_number(current_number: &CurrentNumber) -> Number {
                 let (digits, left) = current_number;
                 Number {
@@ -124,8 +185,8 @@ impl Grid {
                 }
             }
             let mut current_number: Option<CurrentNumber> = None;
-            for x in 0..self.width {
-                let coordinate = Coordinate::new(x as i32, y as i32);
+            for x in 0..self.shape.width {
+                let coordinate = IntVector::new(x as isize, y as isize);
                 let cell = self.get(coordinate);
                 match &mut current_number {
                     Some(current_number_found) => match cell {
@@ -153,26 +214,28 @@ impl Grid {
         result
     }
 
-    fn find_numbers_adjacent_to_symbols(&self) -> Vec<u32> {
+    fn is_part_number(&self, number: &Number, rules: &SchematicRules) -> bool {
+        number
+            .adjacent_coordinates()
+            .iter()
+            .any(|coord| match self.get(*coord) {
+                Some(GridCell::Symbol(symbol)) => rules.is_part_symbol(symbol),
+                _ => false,
+            })
+    }
+
+    fn find_numbers_adjacent_to_symbols(&self, rules: &SchematicRules) -> Vec<u32> {
         let numbers = self.find_all_numbers();
         numbers
             .into_iter()
-            .filter(|it| {
-                let adjacent_coordinates = it.adjacent_coordinates();
-                adjacent_coordinates
-                    .iter()
-                    .any(|coord| match self.get(*coord) {
-                        Some(GridCell::Symbol(_)) => true,
-                        _ => false,
-                    })
-            })
+            .filter(|it| self.is_part_number(it, rules))
             .map(|it| it.value)
             .collect()
     }
 
-    fn find_gears(&self) -> Vec<Gear> {
+    fn find_gears(&self, rules: &SchematicRules) -> Vec<Gear> {
         let numbers = self.find_all_numbers();
-        let numbers_with_coordinates: Vec<(Number, HashSet<Coordinate>)> = numbers
+        let numbers_with_coordinates: Vec<(Number, HashSet<IntVector>)> = numbers
             .iter()
             .map(|number| {
                 let digit_coordinates = number.digit_coordinates();
@@ -182,18 +245,18 @@ impl Grid {
                 )
             })
             .collect();
-        let star_symbols: Vec<Coordinate> = self
+        let gear_symbols: Vec<IntVector> = self
             .find_all_symbols()
             .into_iter()
-            .filter(|(_, symbol)| *symbol == '*')
+            .filter(|(_, symbol)| *symbol == rules.gear_symbol)
             .map(|(coord, _)| coord)
             .collect();
 
-        let gears = star_symbols
+        let gears = gear_symbols
             .iter()
-            .filter_map(|star_symbol| {
-                let adjacent_coordinates: HashSet<Coordinate> =
-                    star_symbol.adjacent_coordinates().iter().copied().collect();
+            .filter_map(|gear_symbol| {
+                let adjacent_coordinates: HashSet<IntVector> =
+                    gear_symbol.all_neighbors().into_iter().collect();
 
                 let adjacent_numbers = numbers_with_coordinates
                     .iter()
@@ -205,10 +268,10 @@ impl Grid {
                     .map(|(number, _)| number.clone())
                     .collect::<Vec<_>>();
 
-                if adjacent_numbers.len() == 2 {
+                if adjacent_numbers.len() == rules.gear_size {
                     Some(Gear {
-                        coordinate: *star_symbol,
-                        numbers: [adjacent_numbers[0], adjacent_numbers[1]],
+                        coordinate: *gear_symbol,
+                        numbers: adjacent_numbers,
                     })
                 } else {
                     None
@@ -218,67 +281,95 @@ impl Grid {
         gears
     }
 
-    fn gear_ratios(&self) -> Vec<u32> {
-        let gears = self.find_gears();
-        gears.iter().map(|it| it.ratio()).collect()
+    fn gear_ratios(&self, rules: &SchematicRules) -> Vec<u64> {
+        let gears = self.find_gears(rules);
+        gears.iter().map(|it| it.ratio(rules.gear_ratio)).collect()
+    }
+
+    /// The schematic with part numbers in green, numbers that don't count in red, and gears in
+    /// yellow, using ANSI escape codes.
+    fn annotate(&self, rules: &SchematicRules) -> String {
+        let mut styles = HashMap::new();
+        for number in self.find_all_numbers() {
+            let style = if self.is_part_number(&number, rules) {
+                PART_NUMBER_STYLE
+            } else {
+                IGNORED_NUMBER_STYLE
+            };
+            for coord in number.digit_coordinates() {
+                styles.insert(coord, style);
+            }
+        }
+        for gear in self.find_gears(rules) {
+            styles.insert(gear.coordinate, GEAR_STYLE);
+        }
+
+        let mut result = String::new();
+        for y in 0..self.shape.height {
+            let mut current_style = None;
+            for x in 0..self.shape.width {
+                let coord = IntVector::new(x as isize, y as isize);
+                let style = styles.get(&coord).copied();
+                if style != current_style {
+                    result.push_str(style.unwrap_or(RESET_STYLE));
+                    current_style = style;
+                }
+                result.push(match self.get(coord) {
+                    Some(GridCell::Digit(digit)) => (b'0' + digit) as char,
+                    Some(GridCell::Symbol(symbol)) => symbol,
+                    None => '.',
+                });
+            }
+            if current_style.is_some() {
+                result.push_str(RESET_STYLE);
+            }
+            result.push('\n');
+        }
+        result
     }
 }
 
+const PART_NUMBER_STYLE: &str = "\x1b[32m";
+const IGNORED_NUMBER_STYLE: &str = "\x1b[31m";
+const GEAR_STYLE: &str = "\x1b[1;33m";
+const RESET_STYLE: &str = "\x1b[0m";
+
 impl FromStr for Grid {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self> {
-        let lines = s.lines().collect::<Vec<_>>();
-        let width = lines.first().ok_or(anyhow!("empty grid"))?.chars().count();
-        let chars: Vec<char> = lines
-            .iter()
-            .map(|line| {
-                let chars = line.chars().collect::<Vec<char>>();
-                if chars.len() != width {
-                    return Err(anyhow!(
-                        "inconsistent line width - expected {}, got {} ({})",
-                        width,
-                        chars.len(),
-                        line
-                    ));
-                }
-                Ok(chars)
-            })
-            .collect::<Result<Vec<Vec<char>>>>()?
-            .into_iter()
-            .flatten()
-            .collect();
+        let (shape, chars) = GridShape::parse_char_grid(s)?;
         let grid: Vec<Option<GridCell>> = chars
-            .into_iter()
-            .map(|c| match c {
+            .iter()
+            .map(|&c| match c {
                 '.' => None,
                 '0'..='9' => Some(GridCell::Digit(c.to_digit(10).unwrap() as u8)),
                 c => Some(GridCell::Symbol(c)),
             })
             .collect();
-        Ok(Grid { cells: grid, width })
+        Ok(Grid { cells: grid, shape })
     }
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 struct Number {
     value: u32,
-    left: Coordinate,
+    left: IntVector,
     width: u8,
 }
 
 impl Number {
-    fn digit_coordinates(&self) -> Vec<Coordinate> {
+    fn digit_coordinates(&self) -> Vec<IntVector> {
         (0..self.width)
-            .map(|x| Coordinate::new(self.left.x + x as i32, self.left.y))
+            .map(|x| IntVector::new(self.left.x + x as isize, self.left.y))
             .collect()
     }
 
-    fn adjacent_coordinates(&self) -> Vec<Coordinate> {
+    fn adjacent_coordinates(&self) -> Vec<IntVector> {
         let digit_coordinates = self.digit_coordinates();
-        let adjacent_coordinates_set: HashSet<Coordinate> = digit_coordinates
+        let adjacent_coordinates_set: HashSet<IntVector> = digit_coordinates
             .iter()
-            .flat_map(|coord| coord.adjacent_coordinates())
+            .flat_map(|coord| coord.all_neighbors())
             .collect();
         adjacent_coordinates_set
             .difference(&digit_coordinates.iter().copied().collect())
@@ -287,35 +378,46 @@ impl Number {
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Clone)]
 struct Gear {
-    coordinate: Coordinate,
-    numbers: [Number; 2],
+    coordinate: IntVector,
+    numbers: Vec<Number>,
 }
 impl Gear {
-    fn ratio(&self) -> u32 {
-        self.numbers[0].value * self.numbers[1].value
+    fn ratio(&self, gear_ratio: GearRatio) -> u64 {
+        let values = self.numbers.iter().map(|number| number.value as u64);
+        match gear_ratio {
+            GearRatio::Product => values.product(),
+            GearRatio::Sum => values.sum(),
+        }
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::framework::fuzz;
+
+    const SAMPLE_INPUT: &str = indoc! {"
+        467..114..
+        ...*......
+        ..35..633.
+        ......#...
+        617*......
+        .....+.58.
+        ..592.....
+        ......755.
+        ...$.*....
+        .664.598..
+    "};
 
     fn sample_grid() -> Grid {
-        Grid::from_str(indoc! {"
-            467..114..
-            ...*......
-            ..35..633.
-            ......#...
-            617*......
-            .....+.58.
-            ..592.....
-            ......755.
-            ...$.*....
-            .664.598..
-        "})
-        .unwrap()
+        Grid::from_str(SAMPLE_INPUT).unwrap()
+    }
+
+    #[test]
+    fn test_parser_never_panics() {
+        fuzz::check_parser("Grid", &[SAMPLE_INPUT], Grid::from_str);
     }
 
     #[test]
@@ -334,8 +436,8 @@ mod test {
     #[test]
     fn test_grid_parse() {
         let grid = sample_grid();
-        assert_eq!(grid.get(Coordinate::new(0, 0)), Some(GridCell::Digit(4)));
-        assert_eq!(grid.get(Coordinate::new(3, 1)), Some(GridCell::Symbol('*')));
+        assert_eq!(grid.get(IntVector::new(0, 0)), Some(GridCell::Digit(4)));
+        assert_eq!(grid.get(IntVector::new(3, 1)), Some(GridCell::Symbol('*')));
     }
 
     #[test]
@@ -345,12 +447,12 @@ mod test {
         assert_eq!(
             symbols,
             vec![
-                (Coordinate { x: 3, y: 1 }, '*'),
-                (Coordinate { x: 6, y: 3 }, '#'),
-                (Coordinate { x: 3, y: 4 }, '*'),
-                (Coordinate { x: 5, y: 5 }, '+'),
-                (Coordinate { x: 3, y: 8 }, '$'),
-                (Coordinate { x: 5, y: 8 }, '*')
+                (IntVector { x: 3, y: 1 }, '*'),
+                (IntVector { x: 6, y: 3 }, '#'),
+                (IntVector { x: 3, y: 4 }, '*'),
+                (IntVector { x: 5, y: 5 }, '+'),
+                (IntVector { x: 3, y: 8 }, '$'),
+                (IntVector { x: 5, y: 8 }, '*')
             ]
         );
     }
@@ -368,7 +470,7 @@ mod test {
     #[test]
     fn test_grid_find_numbers_adjacent_to_symbols() {
         let grid = sample_grid();
-        let numbers = grid.find_numbers_adjacent_to_symbols();
+        let numbers = grid.find_numbers_adjacent_to_symbols(&SchematicRules::default());
         let sum = numbers.iter().sum::<u32>();
         assert_eq!(sum, 4361);
         let missing_numbers = grid.find_all_numbers().len() - numbers.len();
@@ -378,7 +480,7 @@ mod test {
     #[test]
     fn test_grid_find_gears() {
         let grid = sample_grid();
-        let gears = grid.find_gears();
+        let gears = grid.find_gears(&SchematicRules::default());
         assert_eq!(gears.len(), 2);
         assert_eq!(
             gears[0]
@@ -401,7 +503,74 @@ mod test {
     #[test]
     fn test_grid_gear_ratios() {
         let grid = sample_grid();
-        let ratios = grid.gear_ratios();
-        assert_eq!(ratios.into_iter().sum::<u32>(), 467835);
+        let ratios = grid.gear_ratios(&SchematicRules::default());
+        assert_eq!(ratios.into_iter().sum::<u64>(), 467835);
+    }
+
+    #[test]
+    fn test_custom_rules() {
+        let grid = sample_grid();
+        let hash_only = SchematicRules {
+            part_symbols: Some(HashSet::from(['#'])),
+            ..SchematicRules::default()
+        };
+        assert_eq!(grid.find_numbers_adjacent_to_symbols(&hash_only), vec![633]);
+
+        let lonely_gears = SchematicRules {
+            gear_size: 1,
+            ..SchematicRules::default()
+        };
+        assert_eq!(grid.gear_ratios(&lonely_gears), vec![617]);
+
+        let summed = SchematicRules {
+            gear_ratio: GearRatio::Sum,
+            ..SchematicRules::default()
+        };
+        assert_eq!(grid.gear_ratios(&summed), vec![502, 1353]);
+
+        let plus_gears = SchematicRules {
+            gear_symbol: '+',
+            gear_size: 1,
+            ..SchematicRules::default()
+        };
+        assert_eq!(grid.gear_ratios(&plus_gears), vec![592]);
+    }
+
+    #[test]
+    fn test_schematic_args() {
+        let rules = |args: &[&str]| {
+            SchematicArgs::try_parse_from(iter::once(&"annotated_schematic").chain(args))?.rules()
+        };
+        assert_eq!(rules(&[]).unwrap(), SchematicRules::default());
+        assert_eq!(
+            rules(&[
+                "--part-symbols",
+                "#$",
+                "--gear-symbol",
+                "+",
+                "--gear-ratio",
+                "sum"
+            ])
+            .unwrap(),
+            SchematicRules {
+                part_symbols: Some(HashSet::from(['#', '$'])),
+                gear_symbol: '+',
+                gear_ratio: GearRatio::Sum,
+                ..SchematicRules::default()
+            }
+        );
+        assert!(rules(&["--gear-size", "0"]).is_err());
+        assert!(rules(&["--gear-symbol", "**"]).is_err());
+    }
+
+    #[test]
+    fn test_annotate() {
+        let annotated = sample_grid().annotate(&SchematicRules::default());
+        let lines = annotated.lines().collect_vec();
+        assert_eq!(lines.len(), 10);
+        assert_eq!(lines[0], "\x1b[32m467\x1b[0m..\x1b[31m114\x1b[0m..");
+        assert_eq!(lines[1], "...\x1b[1;33m*\x1b[0m......");
+        assert_eq!(lines[4], "\x1b[32m617\x1b[0m*......");
+        assert_eq!(lines[5], ".....+.\x1b[31m58\x1b[0m.");
     }
 }
@@ -1,11 +1,13 @@
 // Day 4: Scratchcards
 
-use std::cell::Cell;
 use std::collections::HashSet;
+use std::ops::Range;
 use std::str::FromStr;
 
 use regex::Regex;
 
+use crate::framework::parse;
+use crate::framework::table::format_table;
 use crate::framework::Day;
 use crate::prelude::*;
 
@@ -29,7 +31,7 @@ impl Day for Day4 {
             cards
                 .iter()
                 .map(|card| card.score())
-                .sum::<u32>()
+                .sum::<Result<u64>>()?
                 .to_string()
                 .pipe(Ok)
         }))
@@ -38,14 +40,23 @@ impl Day for Day4 {
     fn part2(&self) -> Option<Result<String>> {
         Some(try_block(move || {
             let cards = puzzle_input()?;
-            cards
-                .iter()
-                .map(|card| card.explode(&cards))
-                .sum::<u32>()
+            total_cards(&cards, CopyOverflow::Reject)?
                 .to_string()
                 .pipe(Ok)
         }))
     }
+
+    fn run_script(&self, name: &str) -> Result<bool> {
+        match name {
+            "card_table" => {
+                // truncate rather than reject, so the table still shows where the input went wrong
+                let table = card_table(&puzzle_input()?, CopyOverflow::Truncate)?;
+                print!("{}", format_card_table(&table));
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -53,7 +64,6 @@ struct Card {
     id: u32,
     numbers: Box<[u8]>,
     winning_numbers: Box<[u8]>,
-    explosion_result: Cell<Option<u32>>,
 }
 
 impl Card {
@@ -70,73 +80,128 @@ impl Card {
             .collect()
     }
 
-    fn score(&self) -> u32 {
-        let matching_winning_numbers = self.matching_winning_numbers().len() as u32;
-        match matching_winning_numbers {
-            0 => 0,
-            1 => 1,
-            more => 2_u32.pow(more - 1),
+    fn score(&self) -> Result<u64> {
+        match self.matching_winning_numbers().len() {
+            0 => Ok(0),
+            matches => 1_u64
+                .checked_shl(matches as u32 - 1)
+                .ok_or_else(|| anyhow!("Card {} is worth too much to count", self.id)),
         }
     }
+}
 
-    fn get_cards_to_copy<'a>(&self, full_list: &'a [Card]) -> Vec<&'a Card> {
-        let matching_numbers = self.matching_winning_numbers().len();
-        if matching_numbers == 0 {
-            return vec![];
-        }
-        let next_card = self.id as usize;
-        let last_copied_card = next_card + matching_numbers - 1;
-        let copied_cards = &full_list[next_card..=last_copied_card];
-        copied_cards.iter().collect()
+/// What to do when a card wins copies of cards past the end of the table. The puzzle promises that
+/// never happens, so `Reject` treats it as bad input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CopyOverflow {
+    Reject,
+    /// Only copy the cards that exist.
+    Truncate,
+}
+
+/// The positions of the cards won by the card at `index`, which has `matches` matching numbers.
+fn won_cards(
+    cards: &[Card],
+    index: usize,
+    matches: usize,
+    policy: CopyOverflow,
+) -> Result<Range<usize>> {
+    let end = index + 1 + matches;
+    if end <= cards.len() {
+        return Ok(index + 1..end);
     }
+    match policy {
+        CopyOverflow::Reject => Err(anyhow!(
+            "Card {} wins {} copies, but only {} cards follow it",
+            cards[index].id,
+            matches,
+            cards.len() - index - 1
+        )),
+        CopyOverflow::Truncate => Ok(index + 1..cards.len()),
+    }
+}
 
-    fn explode(&self, full_list: &[Card]) -> u32 {
-        if let Some(result) = self.explosion_result.get() {
-            return result;
-        }
-        let copied_cards = self.get_cards_to_copy(full_list);
+/// How many cards you end up with, counting the originals. Works backwards from the last card,
+/// since each card only wins cards after it: a card turns into itself plus everything the cards
+/// it wins turn into.
+fn total_cards(cards: &[Card], policy: CopyOverflow) -> Result<u64> {
+    let mut cards_from = vec![0_u64; cards.len()];
+    for index in (0..cards.len()).rev() {
+        let matches = cards[index].matching_winning_numbers().len();
+        cards_from[index] = won_cards(cards, index, matches, policy)?
+            .try_fold(1_u64, |total, won| total.checked_add(cards_from[won]))
+            .ok_or_else(|| anyhow!("Too many cards won from card {}", cards[index].id))?;
+    }
+    cards_from.into_iter().try_fold(0_u64, |total, count| {
+        total
+            .checked_add(count)
+            .ok_or_else(|| anyhow!("Too many cards to count"))
+    })
+}
 
-        let result = 1 + copied_cards
-            .iter()
-            .map(|card| card.explode(full_list))
-            .sum::<u32>();
-        self.explosion_result.set(Some(result));
-        result
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct CardTableRow {
+    id: u32,
+    matches: usize,
+    score: u64,
+    /// How many of this card you end up holding, including the original
+    copies: u64,
+}
+
+fn card_table(cards: &[Card], policy: CopyOverflow) -> Result<Vec<CardTableRow>> {
+    let mut copies = vec![1_u64; cards.len()];
+    let mut rows = Vec::with_capacity(cards.len());
+    for (index, card) in cards.iter().enumerate() {
+        let matches = card.matching_winning_numbers().len();
+        for won in won_cards(cards, index, matches, policy)? {
+            copies[won] = copies[won]
+                .checked_add(copies[index])
+                .ok_or_else(|| anyhow!("Too many copies of card {}", cards[won].id))?;
+        }
+        rows.push(CardTableRow {
+            id: card.id,
+            matches,
+            score: card.score()?,
+            copies: copies[index],
+        });
     }
+    Ok(rows)
+}
+
+fn format_card_table(rows: &[CardTableRow]) -> String {
+    let headers = ["Card", "Matches", "Score", "Copies"];
+    let cells = rows
+        .iter()
+        .map(|row| {
+            [
+                row.id.to_string(),
+                row.matches.to_string(),
+                row.score.to_string(),
+                row.copies.to_string(),
+            ]
+        })
+        .collect_vec();
+    format_table(&headers, &cells, &[true; 4])
+}
+
+lazy_static! {
+    static ref CARD_REGEX: Regex = Regex::new(r"^Card +(\d+): ([0-9 ]+) \| ([0-9 ]+)$").unwrap();
 }
 
 impl FromStr for Card {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self> {
-        let framework = Regex::new(r"^Card +(\d+): ([0-9 ]+) \| ([0-9 ]+)$")
-            .unwrap()
-            .captures(s)
-            .ok_or(anyhow!("Invalid card: {s}"))?;
+        let framework = CARD_REGEX.captures(s).ok_or(anyhow!("Invalid card: {s}"))?;
 
         let id = framework.get(1).unwrap().as_str().parse()?;
-        let numbers: Box<[u8]> = framework
-            .get(2)
-            .unwrap()
-            .as_str()
-            .split_ascii_whitespace()
-            .map(|s| s.parse::<u8>().map_err(|err| anyhow!(err)))
-            .collect::<Result<Vec<u8>>>()?
-            .into_boxed_slice();
-        let winning_numbers: Box<[u8]> = framework
-            .get(3)
-            .unwrap()
-            .as_str()
-            .split_ascii_whitespace()
-            .map(|s| s.parse::<u8>().map_err(|err| anyhow!(err)))
-            .collect::<Result<Vec<u8>>>()?
-            .into_boxed_slice();
+        let numbers: Box<[u8]> = parse::numbers(&framework[2])?.into_boxed_slice();
+        let winning_numbers: Box<[u8]> = parse::numbers(&framework[3])?.into_boxed_slice();
 
         Ok(Card {
             id,
             numbers,
             winning_numbers,
-            explosion_result: Cell::new(None),
         })
     }
 }
@@ -144,6 +209,7 @@ impl FromStr for Card {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::framework::fuzz;
 
     #[test]
     fn test_part1() {
@@ -158,21 +224,29 @@ mod test {
         );
     }
 
+    const SAMPLE_INPUT: &str = indoc! {"
+        Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53
+        Card 2: 13 32 20 16 61 | 61 30 68 82 17 32 24 19
+        Card 3:  1 21 53 59 44 | 69 82 63 72 16 21 14  1
+        Card 4: 41 92 73 84 69 | 59 84 76 51 58  5 54 83
+        Card 5: 87 83 26 28 32 | 88 30 70 12 93 22 82 36
+        Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11
+    "};
+
     fn sample_input() -> Vec<Card> {
-        let input = indoc! {"
-            Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53
-            Card 2: 13 32 20 16 61 | 61 30 68 82 17 32 24 19
-            Card 3:  1 21 53 59 44 | 69 82 63 72 16 21 14  1
-            Card 4: 41 92 73 84 69 | 59 84 76 51 58  5 54 83
-            Card 5: 87 83 26 28 32 | 88 30 70 12 93 22 82 36
-            Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11
-        "};
+        let input = SAMPLE_INPUT;
         input
             .lines()
             .map(|line| Card::from_str(line).unwrap())
             .collect()
     }
 
+    #[test]
+    fn test_parser_never_panics() {
+        let corpus = SAMPLE_INPUT.lines().collect_vec();
+        fuzz::check_parser("Card", &corpus, Card::from_str);
+    }
+
     #[test]
     fn test_parse_card() {
         let input = "Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53";
@@ -180,7 +254,6 @@ mod test {
             id: 1,
             numbers: vec![41, 48, 83, 86, 17].into_boxed_slice(),
             winning_numbers: vec![83, 86, 6, 31, 17, 9, 48, 53].into_boxed_slice(),
-            explosion_result: Cell::new(None),
         };
         assert_eq!(expected_card, Card::from_str(input).unwrap());
     }
@@ -196,28 +269,70 @@ mod test {
     fn test_score() {
         let scores = sample_input()
             .iter()
-            .map(|card| (card.id, card.score()))
-            .collect::<Vec<(u32, u32)>>();
+            .map(|card| (card.id, card.score().unwrap()))
+            .collect::<Vec<(u32, u64)>>();
 
         assert_eq!(scores, vec![(1, 8), (2, 2), (3, 2), (4, 1), (5, 0), (6, 0)]);
     }
 
     #[test]
-    fn test_cards_to_copy() {
+    fn test_won_cards() {
+        let cards = sample_input();
+        assert_eq!(won_cards(&cards, 0, 4, CopyOverflow::Reject).unwrap(), 1..5);
+        assert_eq!(won_cards(&cards, 5, 0, CopyOverflow::Reject).unwrap(), 6..6);
+        assert!(won_cards(&cards, 4, 2, CopyOverflow::Reject).is_err());
+        assert_eq!(
+            won_cards(&cards, 4, 2, CopyOverflow::Truncate).unwrap(),
+            5..6
+        );
+    }
+
+    #[test]
+    fn test_total_cards() {
         let cards = sample_input();
-        let card = &cards[0];
-        let cards_to_copy = card.get_cards_to_copy(&cards);
-        let expected = vec![2, 3, 4, 5];
+        assert_eq!(total_cards(&cards, CopyOverflow::Reject).unwrap(), 30);
+        // with cards 5 and 6 gone, cards 3 and 4 win copies of cards that aren't there
+        assert!(total_cards(&cards[..4], CopyOverflow::Reject).is_err());
         assert_eq!(
-            cards_to_copy.iter().map(|it| it.id).collect::<Vec<u32>>(),
-            expected
+            total_cards(&cards[..4], CopyOverflow::Truncate).unwrap(),
+            1 + 2 + 4 + 8
         );
     }
 
     #[test]
-    fn test_explode_cards() {
+    fn test_total_cards_overflow() {
+        // every card wins a copy of each of the next two, so the counts grow like Fibonacci
+        let cards = (1..=100)
+            .map(|id| Card {
+                id,
+                numbers: vec![1, 2].into_boxed_slice(),
+                winning_numbers: vec![1, 2].into_boxed_slice(),
+            })
+            .collect_vec();
+        assert!(total_cards(&cards, CopyOverflow::Truncate).is_err());
+        assert!(total_cards(&cards[..60], CopyOverflow::Truncate).is_ok());
+    }
+
+    #[test]
+    fn test_card_table() {
         let cards = sample_input();
-        let result = cards.iter().map(|card| card.explode(&cards)).sum::<u32>();
-        assert_eq!(result, 30);
+        let table = card_table(&cards, CopyOverflow::Reject).unwrap();
+        assert_eq!(
+            table.iter().map(|row| row.copies).collect_vec(),
+            vec![1, 2, 4, 8, 14, 1]
+        );
+        assert_eq!(table.iter().map(|row| row.copies).sum::<u64>(), 30);
+        assert_eq!(
+            format_card_table(&table),
+            indoc! {"
+                Card  Matches  Score  Copies
+                   1        4      8       1
+                   2        2      2       2
+                   3        2      2       4
+                   4        1      1       8
+                   5        0      0      14
+                   6        0      0       1
+            "}
+        );
     }
 }
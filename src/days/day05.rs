@@ -1,11 +1,12 @@
 // Day 5: If You Give A Seed A Fertilizer
 
 use std::collections::HashMap;
-use std::ops::RangeInclusive;
+use std::iter;
 use std::str::FromStr;
 
 use regex::Regex;
 
+use crate::framework::interval::Interval;
 use crate::framework::Day;
 use crate::prelude::*;
 
@@ -35,42 +36,60 @@ impl Day for Day5 {
             Ok(lowest_location.to_string())
         }))
     }
-}
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-enum Category {
-    Seed,
-    Soil,
-    Fertilizer,
-    Water,
-    Light,
-    Temperature,
-    Humidity,
-    Location,
-}
 
-impl FromStr for Category {
-    type Err = Error;
-
-    fn from_str(s: &str) -> Result<Self> {
-        match s {
-            "seed" => Ok(Category::Seed),
-            "soil" => Ok(Category::Soil),
-            "fertilizer" => Ok(Category::Fertilizer),
-            "water" => Ok(Category::Water),
-            "light" => Ok(Category::Light),
-            "temperature" => Ok(Category::Temperature),
-            "humidity" => Ok(Category::Humidity),
-            "location" => Ok(Category::Location),
-            _ => Err(anyhow!("Invalid category: {}", s)),
+    fn run_script(&self, name: &str) -> Result<bool> {
+        match name {
+            "composed_map" => {
+                let almanac = puzzle_input()?;
+                let seed_to_location = almanac.compose("seed", "location")?;
+                for segment in seed_to_location.0.iter() {
+                    let destination = segment.destination();
+                    println!(
+                        "seeds {}..{} -> locations {}..{}",
+                        segment.source.start,
+                        segment.source.end,
+                        destination.start,
+                        destination.end
+                    );
+                }
+                match seed_to_location.inverse() {
+                    Ok(inverse) => println!("inverse: {} segments", inverse.0.len()),
+                    Err(error) => println!("no inverse: {error}"),
+                }
+                // the slow way, one map at a time, for comparison
+                let mut stepwise_lowest = None;
+                for seed_range in almanac.seed_ranges()? {
+                    let location_ranges = almanac.map_seed_range_to_location_ranges(seed_range)?;
+                    let lowest = location_ranges.iter().map(|it| it.start).min();
+                    stepwise_lowest = stepwise_lowest.into_iter().chain(lowest).min();
+                }
+                println!(
+                    "lowest location: {} (one map at a time: {:?})",
+                    almanac.lowest_location_with_ranges()?,
+                    stepwise_lowest
+                );
+            }
+            _ => return Ok(false),
         }
+        Ok(true)
     }
 }
 
+/// The categories are whatever the map headers name. Each category can be mapped from at most
+/// once and into at most once, so following the maps from any category gives a single chain.
 #[derive(Debug)]
 struct Almanac {
     seeds: Vec<u64>,
-    maps: HashMap<(Category, Category), AlmanacMapList>,
+    /// Keyed by source category
+    links: HashMap<String, CategoryLink>,
+}
+
+/// One `x-to-y map:` section.
+#[derive(Debug, PartialEq, Eq)]
+struct CategoryLink {
+    source: String,
+    destination: String,
+    maps: AlmanacMapList,
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -91,42 +110,27 @@ impl AlmanacMapList {
         input
     }
 
-    fn map_range(&self, input: &RangeInclusive<u64>) -> Vec<RangeInclusive<u64>> {
+    /// Rules must be sorted by source, so the output comes out in the same order as the input.
+    fn map_range(&self, input: Interval<u64>) -> Vec<Interval<u64>> {
         let mut output = Vec::new();
-        let mut current_range = input.clone();
+        let mut remaining = input;
         for rule in self.0.iter() {
-            let rule_source_end_inclusive = rule.source_range_start + rule.range_length - 1;
-            if *current_range.start() < rule.source_range_start {
-                // input extends before the current rule, so insert a passthrough range
-                let passthrough_end = (rule.source_range_start - 1).min(*current_range.end());
-                let passthrough_range = *current_range.start()..=passthrough_end;
-                output.push(passthrough_range);
-                current_range = (passthrough_end + 1)..=*current_range.end();
-                if current_range.is_empty() {
-                    return output;
-                }
-            }
-            // now that's taken care of, the current range either begins at, or in the middle of, or after, the current rule
-            // if it's after, we can skip to the next rule
-            if *current_range.start() > rule_source_end_inclusive {
-                continue;
-            }
-            // now it's definitely either at or in the middle of the rule's source range
-            let source_begin = *current_range.start();
-            let source_end = (*current_range.end()).min(rule_source_end_inclusive);
-            let source_offset = source_begin - rule.source_range_start;
-            let length = source_end - source_begin;
-            let destination_start = rule.destination_range_start + source_offset;
-            let destination_end = destination_start + length;
-            let range = destination_start..=destination_end;
-            output.push(range);
-            current_range = (source_end + 1)..=*current_range.end();
-            if current_range.is_empty() {
-                return output;
+            let source = rule.source();
+            // anything before the current rule passes through
+            let (passthrough, rest) = remaining.split_at(source.start);
+            let (mapped, rest) = rest.split_at(source.end);
+            output.push(passthrough);
+            if !mapped.is_empty() {
+                output.push(Interval::new(
+                    mapped.start - source.start + rule.destination_range_start,
+                    mapped.end - source.start + rule.destination_range_start,
+                ));
             }
+            remaining = rest;
         }
         // anything left over gets passed through
-        output.push(current_range);
+        output.push(remaining);
+        output.retain(|interval| !interval.is_empty());
         output
     }
 }
@@ -138,65 +142,253 @@ struct AlmanacMap {
     range_length: u64,
 }
 
+impl AlmanacMap {
+    fn source(&self) -> Interval<u64> {
+        Interval::new(
+            self.source_range_start,
+            self.source_range_start + self.range_length,
+        )
+    }
+}
+
+/// Part of a `PiecewiseMap`: the numbers in `source` all move by the same amount.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Segment {
+    source: Interval<u64>,
+    destination_start: u64,
+}
+
+impl Segment {
+    fn map(&self, input: u64) -> u64 {
+        input - self.source.start + self.destination_start
+    }
+
+    fn destination(&self) -> Interval<u64> {
+        Interval::new(
+            self.destination_start,
+            self.destination_start + self.source.len(),
+        )
+    }
+}
+
+/// Any number of map lists composed into one function. The segments are sorted, and cover every
+/// number below `u64::MAX` (which always maps to itself), including the ones no rule touches.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct PiecewiseMap(Vec<Segment>);
+
+impl PiecewiseMap {
+    fn identity() -> Self {
+        PiecewiseMap(vec![Segment {
+            source: Interval::new(0, u64::MAX),
+            destination_start: 0,
+        }])
+    }
+
+    fn from_map_list(map_list: &AlmanacMapList) -> Self {
+        let mut result = PiecewiseMap(Vec::new());
+        let mut covered = 0;
+        for rule in map_list.0.iter() {
+            // like in `map_range`, where rules overlap the first one wins
+            let (_, source) = rule.source().split_at(covered);
+            if source.is_empty() {
+                continue;
+            }
+            result.push(Segment {
+                source: Interval::new(covered, source.start),
+                destination_start: covered,
+            });
+            result.push(Segment {
+                source,
+                destination_start: source.start - rule.source_range_start
+                    + rule.destination_range_start,
+            });
+            covered = source.end;
+        }
+        result.push(Segment {
+            source: Interval::new(covered, u64::MAX),
+            destination_start: covered,
+        });
+        result
+    }
+
+    /// Adds a segment to the end, merging it into the last one when it carries straight on.
+    fn push(&mut self, segment: Segment) {
+        if segment.source.is_empty() {
+            return;
+        }
+        if let Some(last) = self.0.last_mut() {
+            if last.source.end == segment.source.start
+                && last.destination().end == segment.destination_start
+            {
+                last.source.end = segment.source.end;
+                return;
+            }
+        }
+        self.0.push(segment);
+    }
+
+    /// This map followed by `next`.
+    fn then(&self, next: &PiecewiseMap) -> Self {
+        let mut result = PiecewiseMap(Vec::new());
+        for segment in self.0.iter() {
+            let destination = segment.destination();
+            let first = next
+                .0
+                .partition_point(|it| it.source.end <= destination.start);
+            for next_segment in next.0[first..]
+                .iter()
+                .take_while(|it| it.source.start < destination.end)
+            {
+                let overlap = destination.intersection(&next_segment.source);
+                let source_start = overlap.start - destination.start + segment.source.start;
+                result.push(Segment {
+                    source: Interval::new(source_start, source_start + overlap.len()),
+                    destination_start: next_segment.map(overlap.start),
+                });
+            }
+        }
+        result
+    }
+
+    fn map(&self, input: u64) -> u64 {
+        let index = self.0.partition_point(|it| it.source.end <= input);
+        self.0
+            .get(index)
+            .map_or(input, |segment| segment.map(input))
+    }
+
+    /// The lowest number that anything in `input` maps to.
+    fn lowest_in(&self, input: Interval<u64>) -> Option<u64> {
+        let first = self.0.partition_point(|it| it.source.end <= input.start);
+        self.0[first..]
+            .iter()
+            .map(|segment| (segment, segment.source.intersection(&input)))
+            .take_while(|(_, overlap)| !overlap.is_empty())
+            .map(|(segment, overlap)| segment.map(overlap.start))
+            .min()
+    }
+
+    /// Every number that maps to `output`, in order.
+    fn preimage(&self, output: u64) -> Vec<u64> {
+        self.0
+            .iter()
+            .filter(|segment| segment.destination().contains(output))
+            .map(|segment| output - segment.destination_start + segment.source.start)
+            .collect()
+    }
+
+    /// The same map the other way round. Only maps that are one-to-one have an inverse, so this
+    /// fails with the first number that two different numbers map to.
+    fn inverse(&self) -> Result<PiecewiseMap> {
+        let segments = self
+            .0
+            .iter()
+            .map(|segment| Segment {
+                source: segment.destination(),
+                destination_start: segment.source.start,
+            })
+            .sorted_by_key(|segment| segment.source.start)
+            .collect_vec();
+        let mut result = PiecewiseMap(Vec::new());
+        for segment in segments {
+            if let Some(last) = result.0.last() {
+                if segment.source.start < last.source.end {
+                    let output = segment.source.start;
+                    return Err(anyhow!(
+                        "not one-to-one: both {} and {} map to {}",
+                        last.map(output),
+                        segment.map(output),
+                        output
+                    ));
+                }
+            }
+            result.push(segment);
+        }
+        Ok(result)
+    }
+}
+
 impl Almanac {
-    fn map_seed_to_location(&self, seed: u64) -> Result<u64> {
-        let mut current_number = seed;
-        for categories in [
-            Category::Seed,
-            Category::Soil,
-            Category::Fertilizer,
-            Category::Water,
-            Category::Light,
-            Category::Temperature,
-            Category::Humidity,
-            Category::Location,
-        ]
-        .windows(2)
-        {
-            let key = (categories[0], categories[1]);
-            let map = self
-                .maps
-                .get(&key)
-                .ok_or(anyhow!("Couldn't find map list for {:?}", &key))?;
-            current_number = map.map(current_number);
+    fn link_into(&self, destination: &str) -> Option<&CategoryLink> {
+        self.links
+            .values()
+            .find(|link| link.destination == destination)
+    }
+
+    /// The maps to apply, in order, to get from one category to another. When the chain is broken,
+    /// the error names the map that's missing.
+    fn path(&self, from: &str, to: &str) -> Result<Vec<&CategoryLink>> {
+        let mut path = Vec::new();
+        let mut current = from;
+        while current != to {
+            let Some(link) = self.links.get(current) else {
+                // walk back from the target as far as the maps go (unless it's on a loop), to
+                // see where the gap ends
+                let gap_end = iter::successors(Some(to), |category| {
+                    self.link_into(category).map(|link| link.source.as_str())
+                })
+                .take(self.links.len() + 1)
+                .last()
+                .filter(|category| *category != to && self.link_into(category).is_none());
+                return Err(match gap_end {
+                    Some(gap_end) => {
+                        anyhow!("no way from {from} to {to}: missing {current}-to-{gap_end} map")
+                    }
+                    None => anyhow!("no way from {from} to {to}: missing a map from {current}"),
+                });
+            };
+            if path.len() == self.links.len() {
+                return Err(anyhow!(
+                    "no way from {from} to {to}: the maps go round in a loop"
+                ));
+            }
+            path.push(link);
+            current = &link.destination;
         }
-        Ok(current_number)
-    }
-
-    fn map_seed_range_to_location_ranges(
-        &self,
-        seed: &RangeInclusive<u64>,
-    ) -> Result<Vec<RangeInclusive<u64>>> {
-        let mut current_ranges = vec![seed.clone()];
-        for categories in [
-            Category::Seed,
-            Category::Soil,
-            Category::Fertilizer,
-            Category::Water,
-            Category::Light,
-            Category::Temperature,
-            Category::Humidity,
-            Category::Location,
-        ]
-        .windows(2)
-        {
-            let key = (categories[0], categories[1]);
-            let map_list = self
-                .maps
-                .get(&key)
-                .ok_or(anyhow!("Couldn't find map list for {:?}", &key))?;
+        Ok(path)
+    }
+
+    fn map_number(&self, from: &str, to: &str, number: u64) -> Result<u64> {
+        Ok(self
+            .path(from, to)?
+            .iter()
+            .fold(number, |number, link| link.maps.map(number)))
+    }
+
+    fn map_range(&self, from: &str, to: &str, range: Interval<u64>) -> Result<Vec<Interval<u64>>> {
+        let mut current_ranges = vec![range];
+        for link in self.path(from, to)? {
             current_ranges = current_ranges
                 .iter()
-                .flat_map(|range| map_list.map_range(range))
+                .flat_map(|range| link.maps.map_range(*range))
                 .collect();
         }
         Ok(current_ranges)
     }
 
+    /// The whole path from one category to another as a single map.
+    fn compose(&self, from: &str, to: &str) -> Result<PiecewiseMap> {
+        Ok(self
+            .path(from, to)?
+            .iter()
+            .fold(PiecewiseMap::identity(), |map, link| {
+                map.then(&PiecewiseMap::from_map_list(&link.maps))
+            }))
+    }
+
+    fn map_seed_to_location(&self, seed: u64) -> Result<u64> {
+        self.map_number("seed", "location", seed)
+    }
+
+    fn map_seed_range_to_location_ranges(&self, seed: Interval<u64>) -> Result<Vec<Interval<u64>>> {
+        self.map_range("seed", "location", seed)
+    }
+
     fn lowest_location(&self) -> Result<u64> {
+        let seed_to_location = self.compose("seed", "location")?;
         let mut lowest_location = u64::MAX;
         for seed in self.seeds.iter() {
-            let location = self.map_seed_to_location(*seed)?;
+            let location = seed_to_location.map(*seed);
             if location < lowest_location {
                 lowest_location = location;
             }
@@ -204,34 +396,35 @@ impl Almanac {
         Ok(lowest_location)
     }
 
-    fn seed_ranges(&self) -> Vec<RangeInclusive<u64>> {
+    fn seed_ranges(&self) -> Result<Vec<Interval<u64>>> {
         self.seeds
             .chunks_exact(2)
-            .map(|range| {
-                let start = range[0];
-                let length = range[1];
-                start..=(start + length)
-            })
+            .map(|range| Interval::from_start_length(range[0], range[1]))
             .collect()
     }
 
     fn lowest_location_with_ranges(&self) -> Result<u64> {
-        let seed_ranges = self.seed_ranges();
-        let location_ranges: Vec<RangeInclusive<u64>> = seed_ranges
+        let seed_to_location = self.compose("seed", "location")?;
+        let seed_ranges = self.seed_ranges()?;
+        let lowest_location = seed_ranges
             .iter()
-            .map(|seed_range| self.map_seed_range_to_location_ranges(&seed_range))
-            .collect::<Result<Vec<_>>>()?
-            .into_iter()
-            .flatten()
-            .collect();
-        let lowest_location = location_ranges
-            .iter()
-            .map(|it| it.start())
+            .filter_map(|seed_range| seed_to_location.lowest_in(*seed_range))
             .min()
-            .ok_or(anyhow!("no location range found"))
-            .copied();
+            .ok_or(anyhow!("no location range found"))?;
 
-        lowest_location
+        // check the answer by going back to one of our seeds, then forwards one map at a time
+        let seed = seed_to_location
+            .preimage(lowest_location)
+            .into_iter()
+            .find(|seed| seed_ranges.iter().any(|range| range.contains(*seed)))
+            .ok_or(anyhow!("none of the seeds lands at {lowest_location}"))?;
+        let location = self.map_seed_to_location(seed)?;
+        if location != lowest_location {
+            return Err(anyhow!(
+                "seed {seed} should land at {lowest_location}, but lands at {location}"
+            ));
+        }
+        Ok(lowest_location)
     }
 }
 
@@ -258,17 +451,33 @@ impl FromStr for Almanac {
             )
         };
 
-        let header_regex = Regex::new("^\n?([a-z]+)-to-([a-z]+) map:\n").unwrap();
+        let header_regex = Regex::new("^\n?([^\\s-]+)-to-([^\\s-]+) map:\n").unwrap();
         let map_regex = Regex::new("^([0-9]+) ([0-9]+) ([0-9]+)\n").unwrap();
         let mut remaining_input = remaining_input;
-        let mut map_lists = HashMap::new();
+        let mut map_lists: HashMap<String, CategoryLink> = HashMap::new();
         while !remaining_input.trim().is_empty() {
             let captures = header_regex.captures(remaining_input).ok_or(anyhow!(
                 "expected \"[category]-to-[category] map:\", found: \"{remaining_input}\""
             ))?;
-            let category1 = Category::from_str(captures.get(1).unwrap().as_str())?;
-            let category2 = Category::from_str(captures.get(2).unwrap().as_str())?;
-            let key = (category1, category2);
+            let source = captures.get(1).unwrap().as_str().to_string();
+            let destination = captures.get(2).unwrap().as_str().to_string();
+            if source == destination {
+                return Err(anyhow!(
+                    "{source}-to-{destination} maps a category to itself"
+                ));
+            }
+            if let Some(link) = map_lists.get(&source) {
+                return Err(anyhow!(
+                    "ambiguous chain: {source} maps to both {} and {destination}",
+                    link.destination
+                ));
+            }
+            if let Some(link) = map_lists.values().find(|it| it.destination == destination) {
+                return Err(anyhow!(
+                    "ambiguous chain: {destination} is mapped from both {} and {source}",
+                    link.source
+                ));
+            }
 
             let mut maps = Vec::new();
             remaining_input = &remaining_input[captures.get(0).unwrap().len()..];
@@ -283,6 +492,8 @@ impl FromStr for Almanac {
                 let destination_range_start = u64::from_str(captures.get(1).unwrap().as_str())?;
                 let source_range_start = u64::from_str(captures.get(2).unwrap().as_str())?;
                 let range_length = u64::from_str(captures.get(3).unwrap().as_str())?;
+                Interval::from_start_length(source_range_start, range_length)?;
+                Interval::from_start_length(destination_range_start, range_length)?;
                 maps.push(AlmanacMap {
                     destination_range_start,
                     source_range_start,
@@ -292,12 +503,19 @@ impl FromStr for Almanac {
             }
             maps.sort_by(|a, b| a.source_range_start.cmp(&b.source_range_start));
 
-            map_lists.insert(key, AlmanacMapList(maps));
+            map_lists.insert(
+                source.clone(),
+                CategoryLink {
+                    source,
+                    destination,
+                    maps: AlmanacMapList(maps),
+                },
+            );
         }
 
         Ok(Almanac {
             seeds,
-            maps: map_lists,
+            links: map_lists,
         })
     }
 }
@@ -305,8 +523,22 @@ impl FromStr for Almanac {
 #[cfg(test)]
 mod test {
     use std::collections::HashSet;
+    use std::ops::RangeInclusive;
 
     use super::*;
+    use crate::framework::fuzz;
+    use crate::framework::property::{self, ensure_eq, Rng};
+
+    fn interval(range: RangeInclusive<u64>) -> Interval<u64> {
+        Interval::from_inclusive(&range).unwrap()
+    }
+
+    fn inclusive(intervals: Vec<Interval<u64>>) -> Vec<RangeInclusive<u64>> {
+        intervals
+            .iter()
+            .filter_map(|interval| interval.to_inclusive())
+            .collect()
+    }
 
     #[test]
     fn test_part1() {
@@ -324,17 +556,30 @@ mod test {
         );
     }
 
+    const EXAMPLE_INPUT: &str = include_str!("./day05_example_input.txt");
+
     fn example_input() -> Almanac {
-        Almanac::from_str(include_str!("./day05_example_input.txt")).unwrap()
+        Almanac::from_str(EXAMPLE_INPUT).unwrap()
+    }
+
+    fn map_list<'a>(almanac: &'a Almanac, from: &str, to: &str) -> &'a AlmanacMapList {
+        let link = &almanac.links[from];
+        assert_eq!(link.destination, to);
+        &link.maps
+    }
+
+    #[test]
+    fn test_parser_never_panics() {
+        fuzz::check_parser("Almanac", &[EXAMPLE_INPUT], Almanac::from_str);
     }
 
     #[test]
     fn test_parsing() {
         let result = example_input();
         assert_eq!(result.seeds, vec![79, 14, 55, 13]);
-        assert_eq!(result.maps.len(), 7);
+        assert_eq!(result.links.len(), 7);
         assert_eq!(
-            result.maps.get(&(Category::Seed, Category::Soil)).unwrap(),
+            map_list(&result, "seed", "soil"),
             &AlmanacMapList(vec![
                 AlmanacMap {
                     destination_range_start: 52,
@@ -353,7 +598,7 @@ mod test {
     #[test]
     fn test_mapping() {
         let almanac = example_input();
-        let seed_to_soil = almanac.maps.get(&(Category::Seed, Category::Soil)).unwrap();
+        let seed_to_soil = map_list(&almanac, "seed", "soil");
         assert_eq!(seed_to_soil.map(79), 81);
         assert_eq!(seed_to_soil.map(14), 14);
         assert_eq!(seed_to_soil.map(55), 57);
@@ -363,14 +608,8 @@ mod test {
     #[test]
     fn test_problem_mappings() {
         let almanac = example_input();
-        let fertilizer_to_water = almanac
-            .maps
-            .get(&(Category::Fertilizer, Category::Water))
-            .unwrap();
-        let water_to_light = almanac
-            .maps
-            .get(&(Category::Water, Category::Light))
-            .unwrap();
+        let fertilizer_to_water = map_list(&almanac, "fertilizer", "water");
+        let water_to_light = map_list(&almanac, "water", "light");
         assert_eq!(fertilizer_to_water.map(53), 49);
         assert_eq!(water_to_light.map(81), 74);
     }
@@ -387,9 +626,9 @@ mod test {
     #[test]
     fn test_map_ranges() {
         let almanac = example_input();
-        let seed_to_soil = almanac.maps.get(&(Category::Seed, Category::Soil)).unwrap();
+        let seed_to_soil = map_list(&almanac, "seed", "soil");
         assert_eq!(
-            seed_to_soil.map_range(&(47..=105)),
+            inclusive(seed_to_soil.map_range(interval(47..=105))),
             vec![47..=49, 52..=99, 50..=51, 100..=105]
         );
     }
@@ -397,24 +636,27 @@ mod test {
     #[test]
     fn test_problem_ranges() {
         let almanac = example_input();
-        let seed_to_soil = almanac.maps.get(&(Category::Seed, Category::Soil)).unwrap();
-        let fertizilizer_to_water = almanac
-            .maps
-            .get(&(Category::Fertilizer, Category::Water))
-            .unwrap();
-        let temperature_to_humidity = almanac
-            .maps
-            .get(&(Category::Temperature, Category::Humidity))
-            .unwrap();
-        assert_eq!(seed_to_soil.map_range(&(79..=93)), vec![81..=95]);
-        assert_eq!(fertizilizer_to_water.map_range(&(81..=95)), vec![81..=95]);
+        let seed_to_soil = map_list(&almanac, "seed", "soil");
+        let fertizilizer_to_water = map_list(&almanac, "fertilizer", "water");
+        let temperature_to_humidity = map_list(&almanac, "temperature", "humidity");
+        assert_eq!(
+            inclusive(seed_to_soil.map_range(interval(79..=93))),
+            vec![81..=95]
+        );
+        assert_eq!(
+            inclusive(fertizilizer_to_water.map_range(interval(81..=95))),
+            vec![81..=95]
+        );
         assert_eq!(
-            fertizilizer_to_water.map_range(&(57..=70)),
+            inclusive(fertizilizer_to_water.map_range(interval(57..=70))),
             vec![53..=56, 61..=70]
         );
-        assert_eq!(temperature_to_humidity.map_range(&(93..=99)), vec![93..=99]);
         assert_eq!(
-            temperature_to_humidity.map_range(&(68..=74)),
+            inclusive(temperature_to_humidity.map_range(interval(93..=99))),
+            vec![93..=99]
+        );
+        assert_eq!(
+            inclusive(temperature_to_humidity.map_range(interval(68..=74))),
             vec![69..=69, 0..=0, 70..=74]
         );
     }
@@ -422,42 +664,286 @@ mod test {
     #[test]
     fn test_ranges_equivalence() {
         let almanac = example_input();
-        let mut current_ranges = almanac.seed_ranges();
-        for categories in [
-            Category::Seed,
-            Category::Soil,
-            Category::Fertilizer,
-            Category::Water,
-            Category::Light,
-            Category::Temperature,
-            Category::Humidity,
-            Category::Location,
-        ]
-        .windows(2)
-        {
-            let key = (categories[0], categories[1]);
-            let map_list = almanac.maps.get(&key).unwrap();
+        let mut current_ranges = almanac.seed_ranges().unwrap();
+        for link in almanac.path("seed", "location").unwrap() {
+            let map_list = &link.maps;
             let ranges = current_ranges.clone();
-            let all_numbers: Vec<u64> = current_ranges.iter().flat_map(|it| it.clone()).collect();
+            let all_numbers: Vec<u64> = current_ranges
+                .iter()
+                .flat_map(|it| it.start..it.end)
+                .collect();
             let mapped_numbers: HashSet<u64> =
                 all_numbers.iter().map(|it| map_list.map(*it)).collect();
             current_ranges = current_ranges
                 .iter()
-                .flat_map(|range| map_list.map_range(range))
+                .flat_map(|range| map_list.map_range(*range))
+                .collect();
+            let numbers_in_ranges: HashSet<u64> = current_ranges
+                .iter()
+                .flat_map(|it| it.start..it.end)
                 .collect();
-            let numbers_in_ranges: HashSet<u64> =
-                current_ranges.iter().flat_map(|it| it.clone()).collect();
             assert_eq!(
                 numbers_in_ranges, mapped_numbers,
-                "{:?} mapping: {:?}",
-                key, ranges
+                "{}-to-{} mapping: {:?}",
+                link.source, link.destination, ranges
             )
         }
     }
 
+    #[test]
+    fn test_path() {
+        let almanac = example_input();
+        let path = almanac.path("soil", "humidity").unwrap();
+        assert_eq!(
+            path.iter()
+                .map(|link| link.destination.as_str())
+                .collect_vec(),
+            vec!["fertilizer", "water", "light", "temperature", "humidity"]
+        );
+        assert_eq!(almanac.map_number("soil", "humidity", 81).unwrap(), 78);
+        assert_eq!(almanac.map_number("light", "light", 74).unwrap(), 74);
+        assert_eq!(
+            almanac.path("humidity", "soil").unwrap_err().to_string(),
+            "no way from humidity to soil: missing location-to-seed map"
+        );
+    }
+
+    #[test]
+    fn test_any_category_names() {
+        let almanac = Almanac::from_str(indoc! {"
+            seeds: 1 2
+
+            bean-to-sprout map:
+            10 0 5
+
+            sprout-to-stalk map:
+            0 10 1
+        "})
+        .unwrap();
+        assert_eq!(almanac.map_number("bean", "stalk", 0).unwrap(), 0);
+        assert_eq!(almanac.map_number("bean", "stalk", 1).unwrap(), 11);
+        assert_eq!(almanac.map_number("sprout", "stalk", 10).unwrap(), 0);
+
+        let almanac = Almanac::from_str(indoc! {"
+            seeds: 1 2
+
+            Soil2-to-water_3 map:
+            5 0 5
+
+            water_3-to-Ünïcödé map:
+            0 5 1
+        "})
+        .unwrap();
+        assert_eq!(almanac.map_number("Soil2", "Ünïcödé", 0).unwrap(), 0);
+        assert_eq!(almanac.map_number("Soil2", "water_3", 4).unwrap(), 9);
+    }
+
+    #[test]
+    fn test_broken_chain() {
+        let almanac = Almanac::from_str(indoc! {"
+            seeds: 1 2
+
+            seed-to-soil map:
+            0 0 1
+
+            fertilizer-to-water map:
+            0 0 1
+
+            water-to-location map:
+            0 0 1
+        "})
+        .unwrap();
+        assert_eq!(
+            almanac.map_seed_to_location(1).unwrap_err().to_string(),
+            "no way from seed to location: missing soil-to-fertilizer map"
+        );
+        assert_eq!(almanac.map_number("fertilizer", "location", 0).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_ambiguous_chain() {
+        let branching = indoc! {"
+            seeds: 1 2
+
+            seed-to-soil map:
+            0 0 1
+
+            seed-to-water map:
+            0 0 1
+        "};
+        assert_eq!(
+            Almanac::from_str(branching).unwrap_err().to_string(),
+            "ambiguous chain: seed maps to both soil and water"
+        );
+        let merging = indoc! {"
+            seeds: 1 2
+
+            seed-to-soil map:
+            0 0 1
+
+            water-to-soil map:
+            0 0 1
+        "};
+        assert_eq!(
+            Almanac::from_str(merging).unwrap_err().to_string(),
+            "ambiguous chain: soil is mapped from both seed and water"
+        );
+        let looping = Almanac::from_str(indoc! {"
+            seeds: 1 2
+
+            soil-to-water map:
+            0 0 1
+
+            water-to-soil map:
+            0 0 1
+        "})
+        .unwrap();
+        assert_eq!(
+            looping.path("soil", "seed").unwrap_err().to_string(),
+            "no way from soil to seed: the maps go round in a loop"
+        );
+        assert_eq!(
+            looping.path("seed", "soil").unwrap_err().to_string(),
+            "no way from seed to soil: missing a map from seed"
+        );
+    }
+
+    #[test]
+    fn test_seed_ranges() {
+        let almanac = example_input();
+        assert_eq!(
+            inclusive(almanac.seed_ranges().unwrap()),
+            vec![79..=92, 55..=67]
+        );
+    }
+
     #[test]
     fn test_lowest_location_with_ranges() {
         let almanac = example_input();
         assert_eq!(almanac.lowest_location_with_ranges().unwrap(), 46);
     }
+
+    #[test]
+    fn test_compose() {
+        let almanac = example_input();
+        let seed_to_location = almanac.compose("seed", "location").unwrap();
+        for seed in 0..120 {
+            assert_eq!(
+                seed_to_location.map(seed),
+                almanac.map_seed_to_location(seed).unwrap(),
+                "seed {}",
+                seed
+            );
+        }
+        assert_eq!(seed_to_location.map(u64::MAX), u64::MAX);
+        assert_eq!(seed_to_location.lowest_in(interval(79..=92)), Some(46));
+        assert_eq!(seed_to_location.lowest_in(interval(55..=67)), Some(56));
+        assert_eq!(seed_to_location.lowest_in(Interval::empty()), None);
+        assert_eq!(
+            almanac.compose("light", "light").unwrap(),
+            PiecewiseMap::identity()
+        );
+    }
+
+    #[test]
+    fn test_inverse() {
+        let almanac = example_input();
+        let seed_to_location = almanac.compose("seed", "location").unwrap();
+        let location_to_seed = seed_to_location.inverse().unwrap();
+        for (seed, location) in [(79, 82), (14, 43), (55, 86), (13, 35)] {
+            assert_eq!(location_to_seed.map(location), seed);
+            assert_eq!(seed_to_location.preimage(location), vec![seed]);
+        }
+        assert_eq!(location_to_seed.inverse().unwrap(), seed_to_location);
+    }
+
+    #[test]
+    fn test_inverse_not_one_to_one() {
+        let map = PiecewiseMap::from_map_list(&AlmanacMapList(vec![AlmanacMap {
+            destination_range_start: 0,
+            source_range_start: 10,
+            range_length: 5,
+        }]));
+        assert_eq!(map.preimage(3), vec![3, 13]);
+        assert_eq!(map.preimage(12), vec![]);
+        assert_eq!(
+            map.inverse().unwrap_err().to_string(),
+            "not one-to-one: both 0 and 10 map to 0"
+        );
+    }
+
+    /// Small almanacs with non-overlapping rules, in the puzzle's text format.
+    fn random_almanac(rng: &mut Rng, size: usize) -> Almanac {
+        let seeds = (0..rng.gen_range(1..=size.div_ceil(2)))
+            .flat_map(|_| [rng.gen_range(0..=100), rng.gen_range(0..=size as u64 * 3)])
+            .join(" ");
+        let mut input = format!("seeds: {}\n", seeds);
+        let categories = [
+            "seed",
+            "soil",
+            "fertilizer",
+            "water",
+            "light",
+            "temperature",
+            "humidity",
+            "location",
+        ];
+        for pair in categories.windows(2) {
+            input += &format!("\n{}-to-{} map:\n", pair[0], pair[1]);
+            let mut source_start = 0;
+            for _ in 0..rng.gen_range(0..=size.min(4)) {
+                source_start += rng.gen_range(0..=10);
+                let length = rng.gen_range(1..=10);
+                let destination_start = rng.gen_range(0..=100);
+                input += &format!("{} {} {}\n", destination_start, source_start, length);
+                source_start += length;
+            }
+        }
+        input.parse().unwrap()
+    }
+
+    #[test]
+    fn test_point_and_range_mapping_agree() {
+        property::check("point and range mapping agree", random_almanac, |almanac| {
+            let mut lowest_by_points: Option<u64> = None;
+            for seed_range in almanac.seed_ranges()? {
+                let by_points = (seed_range.start..seed_range.end)
+                    .map(|seed| almanac.map_seed_to_location(seed))
+                    .collect::<Result<Vec<_>>>()?;
+                let by_ranges = almanac
+                    .map_seed_range_to_location_ranges(seed_range)?
+                    .into_iter()
+                    .flat_map(|range| range.start..range.end)
+                    .collect_vec();
+                ensure_eq(&by_ranges, &by_points, "locations in order")?;
+                lowest_by_points = lowest_by_points.into_iter().chain(by_points).min();
+            }
+            if let Some(lowest) = lowest_by_points {
+                ensure_eq(
+                    almanac.lowest_location_with_ranges()?,
+                    lowest,
+                    "lowest location",
+                )?;
+            }
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn test_composed_map_agrees_with_each_map_in_turn() {
+        property::check("composed map", random_almanac, |almanac| {
+            let seed_to_location = almanac.compose("seed", "location")?;
+            for seed in 0..=150 {
+                let location = almanac.map_seed_to_location(seed)?;
+                ensure_eq(seed_to_location.map(seed), location, "location")?;
+                if !seed_to_location.preimage(location).contains(&seed) {
+                    return Err(anyhow!("{seed} missing from preimage of {location}"));
+                }
+                if let Ok(location_to_seed) = seed_to_location.inverse() {
+                    ensure_eq(location_to_seed.map(location), seed, "round trip")?;
+                }
+            }
+            Ok(())
+        });
+    }
 }
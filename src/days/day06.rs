@@ -2,6 +2,8 @@
 
 use std::str::FromStr;
 
+use crate::framework::interval::Interval;
+use crate::framework::parse;
 use crate::framework::Day;
 use crate::prelude::*;
 
@@ -29,12 +31,11 @@ impl Day for Day6 {
 
 /// Gets the distance in millimeters travelled by a boat in a race `time` milliseconds long,
 /// when you hold the boat down for `button_hold` milliseconds.
-/// `button_hold` must be less than `time`.
-fn simulate_race(time: u64, button_hold: u64) -> u64 {
+/// `button_hold` must be at most `time`. The result is a `u128` so it can't overflow.
+fn simulate_race(time: u64, button_hold: u64) -> u128 {
     let remaining_time = time - button_hold;
     let velocity = button_hold;
-    let distance = velocity * remaining_time;
-    distance
+    velocity as u128 * remaining_time as u128
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -46,36 +47,52 @@ struct Race {
 
 impl Race {
     fn from_str_with_bad_kerning(input: &str) -> Result<Self> {
-        let lines = input.lines().collect::<Vec<_>>();
-        let time_line = lines[0].trim_start_matches("Time: ");
-        let time = time_line.replace(" ", "").parse::<u64>()?;
-        let distance_line = lines[1].trim_start_matches("Distance: ");
-        let distance = distance_line.replace(" ", "").parse::<u64>()?;
+        let (time_line, distance_line) = input
+            .lines()
+            .collect_tuple()
+            .ok_or(anyhow!("Expected a Time line and a Distance line"))?;
+        let time = parse::labeled(time_line, "Time")?
+            .replace(" ", "")
+            .parse::<u64>()?;
+        let distance = parse::labeled(distance_line, "Distance")?
+            .replace(" ", "")
+            .parse::<u64>()?;
         Ok(Race { time, distance })
     }
 
-    fn ways_to_win(&self) -> u64 {
-        let max_hold_time = self.time - 1;
-        let halfway = max_hold_time / 2;
-        let victory_threshold = {
-            let mut victory_search_min = 1;
-            let mut victory_search_max = halfway;
-            while victory_search_min < victory_search_max {
-                let search_midpoint = (victory_search_min + victory_search_max) / 2;
-                let is_win = simulate_race(self.time, search_midpoint) > self.distance;
-                if is_win {
-                    victory_search_max = search_midpoint;
-                } else {
-                    victory_search_min = search_midpoint + 1;
-                }
-            }
-            victory_search_min
-        };
+    fn wins(&self, button_hold: u64) -> bool {
+        button_hold <= self.time && simulate_race(self.time, button_hold) > self.distance as u128
+    }
 
-        let wins_between_victory_threshold_and_midpoint = (halfway + 1) - victory_threshold;
+    /// Every button hold that beats the record. Holding for `h` wins when
+    /// `h * (time - h) > distance`, which is between the roots of `h² - time·h + distance`.
+    /// The distance is symmetric around `time / 2`, so the winning holds are too.
+    fn winning_holds(&self) -> Interval<u64> {
+        let time = self.time as u128;
+        let distance = self.distance as u128;
+        // with no roots, or only one (where the best hold ties the record), nothing wins
+        let Some(discriminant) = (time * time).checked_sub(4 * distance).filter(|it| *it > 0)
+        else {
+            return Interval::empty();
+        };
+        // The square root is rounded down, so this is less than half a step above the lower
+        // root and less than a whole step below it. The first win is here or just after.
+        let estimate = ((time - discriminant.isqrt()) / 2) as u64;
+        let first_win = if self.wins(estimate) {
+            estimate
+        } else {
+            estimate + 1
+        };
+        if !self.wins(first_win) {
+            // the roots are no more than a step apart, with no whole number strictly between
+            return Interval::empty();
+        }
+        // holding for 0 never wins, so this can't overflow
+        Interval::new(first_win, self.time - first_win + 1)
+    }
 
-        let is_odd = max_hold_time % 2 == 1;
-        wins_between_victory_threshold_and_midpoint * 2 + if is_odd { 1 } else { 0 }
+    fn ways_to_win(&self) -> u64 {
+        self.winning_holds().len()
     }
 }
 
@@ -95,17 +112,12 @@ impl FromStr for Leaderboard {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self> {
-        let lines = s.lines().collect::<Vec<_>>();
-        let time_line = lines[0].trim_start_matches("Time: ");
-        let times = time_line
-            .split_whitespace()
-            .map(|s| s.parse::<u64>().map_err(anyhow::Error::from))
-            .collect::<Result<Vec<u64>>>()?;
-        let distance_line = lines[1].trim_start_matches("Distance: ");
-        let distances = distance_line
-            .split_whitespace()
-            .map(|s| s.parse::<u64>().map_err(anyhow::Error::from))
-            .collect::<Result<Vec<u64>>>()?;
+        let (time_line, distance_line) = s
+            .lines()
+            .collect_tuple()
+            .ok_or(anyhow!("Expected a Time line and a Distance line"))?;
+        let times: Vec<u64> = parse::numbers(parse::labeled(time_line, "Time")?)?;
+        let distances: Vec<u64> = parse::numbers(parse::labeled(distance_line, "Distance")?)?;
 
         let races = times
             .iter()
@@ -122,6 +134,8 @@ impl FromStr for Leaderboard {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::framework::fuzz;
+    use crate::framework::property::{self, ensure_eq, Rng};
 
     #[test]
     fn test_part1() {
@@ -136,20 +150,23 @@ mod test {
         );
     }
 
+    const SAMPLE_INPUT: &str = indoc! {"
+        Time:      7  15   30
+        Distance:  9  40  200
+    "};
+
     fn sample_input() -> Leaderboard {
-        let input = indoc! {"
-            Time:      7  15   30
-            Distance:  9  40  200
-        "};
-        Leaderboard::from_str(input).unwrap()
+        Leaderboard::from_str(SAMPLE_INPUT).unwrap()
     }
 
     fn sample_input_pt_2() -> Race {
-        let input = indoc! {"
-            Time:      7  15   30
-            Distance:  9  40  200
-        "};
-        Race::from_str_with_bad_kerning(input).unwrap()
+        Race::from_str_with_bad_kerning(SAMPLE_INPUT).unwrap()
+    }
+
+    #[test]
+    fn test_parser_never_panics() {
+        fuzz::check_parser("Leaderboard", &[SAMPLE_INPUT], Leaderboard::from_str);
+        fuzz::check_parser("Race", &[SAMPLE_INPUT], Race::from_str_with_bad_kerning);
     }
 
     #[test]
@@ -196,6 +213,67 @@ mod test {
         assert_eq!(race.ways_to_win(), 4);
     }
 
+    #[test]
+    fn test_winning_holds() {
+        let holds = |time, distance| Race { time, distance }.winning_holds().to_inclusive();
+        assert_eq!(holds(7, 9), Some(2..=5));
+        assert_eq!(holds(15, 40), Some(4..=11));
+        assert_eq!(holds(30, 200), Some(11..=19));
+        // holding for 2 or 8 ties the record, which isn't enough
+        assert_eq!(holds(10, 16), Some(3..=7));
+        // the best hold only ties
+        assert_eq!(holds(10, 25), None);
+        assert_eq!(holds(3, 100), None);
+        assert_eq!(holds(0, 0), None);
+        assert_eq!(holds(1, 0), None);
+        assert_eq!(holds(2, 0), Some(1..=1));
+        // the roots are 1 and 2, which both tie
+        assert_eq!(holds(3, 2), None);
+    }
+
+    #[test]
+    fn test_huge_races() {
+        let race = Race {
+            time: u64::MAX,
+            distance: 0,
+        };
+        assert_eq!(race.ways_to_win(), u64::MAX - 1);
+        let race = Race {
+            time: u64::MAX,
+            distance: u64::MAX,
+        };
+        assert_eq!(race.winning_holds().start, 2);
+        assert!(!race.wins(1));
+        let race = Race {
+            time: 1 << 32,
+            distance: 1 << 62,
+        };
+        assert_eq!(race.ways_to_win(), 0);
+    }
+
+    fn random_race(rng: &mut Rng, size: usize) -> Race {
+        let time = rng.gen_range(0..=size as u64 * 4);
+        Race {
+            time,
+            distance: rng.gen_range(0..=(time * time / 4 + 2)),
+        }
+    }
+
+    #[test]
+    fn test_winning_holds_match_simulation() {
+        property::check("winning holds", random_race, |race| {
+            let simulated = (0..=race.time)
+                .filter(|hold| simulate_race(race.time, *hold) > race.distance as u128)
+                .collect_vec();
+            let holds = race.winning_holds();
+            ensure_eq(
+                (holds.start..holds.end).collect_vec(),
+                simulated,
+                "winning holds",
+            )
+        });
+    }
+
     #[test]
     fn test_part1_solution() {
         let leaderboard = sample_input();
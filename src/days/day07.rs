@@ -1,14 +1,17 @@
 // Day 7: Camel Cards
 
 use std::fmt::Display;
-use std::str::FromStr;
+use std::iter;
 
+use clap::{Parser, ValueEnum};
+
+use crate::framework::table::format_table;
 use crate::framework::Day;
 use crate::prelude::*;
 
-fn puzzle_input<HandType: Hand>() -> Result<Game<HandType>> {
+fn puzzle_input(rules: &Rules) -> Result<Game> {
     let input = include_str!("./day07_input.txt");
-    Game::from_str(input)
+    Game::parse(input, rules)
 }
 
 pub struct Day7;
@@ -20,343 +23,327 @@ impl Day for Day7 {
 
     fn part1(&self) -> Option<Result<String>> {
         Some(try_block(move || {
-            Ok(puzzle_input::<HandMk1>()?.total_winnings().to_string())
+            Ok(puzzle_input(&Rules::standard())?
+                .total_winnings()
+                .to_string())
         }))
     }
 
     fn part2(&self) -> Option<Result<String>> {
         Some(try_block(move || {
-            Ok(puzzle_input::<HandMk2>()?.total_winnings().to_string())
+            Ok(puzzle_input(&Rules::jokers())?.total_winnings().to_string())
         }))
     }
+
+    fn run_script_with_args(&self, name: &str, args: &[String]) -> Result<bool> {
+        match name {
+            "winnings" => {
+                let rules = RuleArgs::try_parse_from(
+                    iter::once(name).chain(args.iter().map(String::as_str)),
+                )?
+                .rules()?;
+                println!("{}", puzzle_input(&rules)?.total_winnings());
+            }
+            "standings" => {
+                let args = StandingsArgs::try_parse_from(
+                    iter::once(name).chain(args.iter().map(String::as_str)),
+                )?;
+                let rules = args.rules.rules()?;
+                let standings = puzzle_input(&rules)?.standings(&rules);
+                print!("{}", format_standings(&standings, args.format));
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
 }
 
 type Number = u64;
 
-trait Card: Ord + Copy {
-    fn to_char(&self) -> char;
-    fn from_char(input: char) -> Result<Self>;
+/// Picks the rules from the command line, e.g. `--script winnings -- --rules jokers --hand-size 6`.
+#[derive(Debug, Parser)]
+struct RuleArgs {
+    /// The rule set to start from: "standard" or "jokers"
+    #[arg(long, default_value = "standard")]
+    rules: String,
+    /// Every card, weakest first
+    #[arg(long)]
+    card_order: Option<String>,
+    /// The wild card, or "none"
+    #[arg(long)]
+    wildcard: Option<String>,
+    #[arg(long)]
+    hand_size: Option<usize>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
-enum CardMk1 {
-    Two,
-    Three,
-    Four,
-    Five,
-    Six,
-    Seven,
-    Eight,
-    Nine,
-    Ten,
-    Jack,
-    Queen,
-    King,
-    Ace,
-}
-
-impl Card for CardMk1 {
-    fn to_char(&self) -> char {
-        match self {
-            CardMk1::Two => '2',
-            CardMk1::Three => '3',
-            CardMk1::Four => '4',
-            CardMk1::Five => '5',
-            CardMk1::Six => '6',
-            CardMk1::Seven => '7',
-            CardMk1::Eight => '8',
-            CardMk1::Nine => '9',
-            CardMk1::Ten => 'T',
-            CardMk1::Jack => 'J',
-            CardMk1::Queen => 'Q',
-            CardMk1::King => 'K',
-            CardMk1::Ace => 'A',
-        }
+impl RuleArgs {
+    fn rules(&self) -> Result<Rules> {
+        let base = Rules::named(&self.rules)?;
+        let wildcard = match self.wildcard.as_deref() {
+            None => base.wildcard,
+            Some("none") => None,
+            Some(wildcard) => Some(
+                wildcard
+                    .chars()
+                    .exactly_one()
+                    .map_err(|_| anyhow!("the wild card should be one card, not {wildcard:?}"))?,
+            ),
+        };
+        Rules::new(
+            &self
+                .card_order
+                .clone()
+                .unwrap_or_else(|| base.card_order.iter().collect()),
+            wildcard,
+            self.hand_size.unwrap_or(base.hand_size),
+        )
     }
+}
 
-    fn from_char(input: char) -> Result<Self> {
-        match input {
-            '2' => Ok(CardMk1::Two),
-            '3' => Ok(CardMk1::Three),
-            '4' => Ok(CardMk1::Four),
-            '5' => Ok(CardMk1::Five),
-            '6' => Ok(CardMk1::Six),
-            '7' => Ok(CardMk1::Seven),
-            '8' => Ok(CardMk1::Eight),
-            '9' => Ok(CardMk1::Nine),
-            'T' => Ok(CardMk1::Ten),
-            'J' => Ok(CardMk1::Jack),
-            'Q' => Ok(CardMk1::Queen),
-            'K' => Ok(CardMk1::King),
-            'A' => Ok(CardMk1::Ace),
-            _ => Err(anyhow!("Invalid card: {}", input)),
-        }
-    }
+#[derive(Debug, Parser)]
+struct StandingsArgs {
+    #[command(flatten)]
+    rules: RuleArgs,
+    #[arg(long, value_enum, default_value_t = ReportFormat::Text)]
+    format: ReportFormat,
 }
 
-impl From<CardMk2> for CardMk1 {
-    fn from(value: CardMk2) -> Self {
-        match value {
-            CardMk2::Joker => CardMk1::Jack,
-            CardMk2::Two => CardMk1::Two,
-            CardMk2::Three => CardMk1::Three,
-            CardMk2::Four => CardMk1::Four,
-            CardMk2::Five => CardMk1::Five,
-            CardMk2::Six => CardMk1::Six,
-            CardMk2::Seven => CardMk1::Seven,
-            CardMk2::Eight => CardMk1::Eight,
-            CardMk2::Nine => CardMk1::Nine,
-            CardMk2::Ten => CardMk1::Ten,
-            CardMk2::Queen => CardMk1::Queen,
-            CardMk2::King => CardMk1::King,
-            CardMk2::Ace => CardMk1::Ace,
-        }
-    }
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum ReportFormat {
+    Text,
+    Csv,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
-enum CardMk2 {
-    Joker,
-    Two,
-    Three,
-    Four,
-    Five,
-    Six,
-    Seven,
-    Eight,
-    Nine,
-    Ten,
-    Queen,
-    King,
-    Ace,
+/// Everything that differs between versions of the game.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Rules {
+    /// Every card, weakest first
+    card_order: Vec<char>,
+    /// Counts as whichever card makes the hand strongest, but ties break on where it is in the
+    /// card order
+    wildcard: Option<char>,
+    hand_size: usize,
 }
 
-impl Card for CardMk2 {
-    fn to_char(&self) -> char {
-        match self {
-            CardMk2::Joker => 'J',
-            CardMk2::Two => '2',
-            CardMk2::Three => '3',
-            CardMk2::Four => '4',
-            CardMk2::Five => '5',
-            CardMk2::Six => '6',
-            CardMk2::Seven => '7',
-            CardMk2::Eight => '8',
-            CardMk2::Nine => '9',
-            CardMk2::Ten => 'T',
-            CardMk2::Queen => 'Q',
-            CardMk2::King => 'K',
-            CardMk2::Ace => 'A',
+impl Rules {
+    fn new(card_order: &str, wildcard: Option<char>, hand_size: usize) -> Result<Self> {
+        let card_order = card_order.chars().collect_vec();
+        if card_order.is_empty() {
+            return Err(anyhow!("there has to be at least one card"));
+        }
+        if let Some(card) = card_order.iter().duplicates().next() {
+            return Err(anyhow!("{card} is in the card order more than once"));
         }
+        if let Some(wildcard) = wildcard.filter(|it| !card_order.contains(it)) {
+            return Err(anyhow!("the wild card {wildcard} isn't in the card order"));
+        }
+        if hand_size == 0 {
+            return Err(anyhow!("hands have to have at least one card"));
+        }
+        Ok(Rules {
+            card_order,
+            wildcard,
+            hand_size,
+        })
     }
 
-    fn from_char(input: char) -> Result<Self> {
-        let card_mk1 = CardMk1::from_char(input)?;
-        Ok(card_mk1.into())
+    fn standard() -> Self {
+        Rules::new("23456789TJQKA", None, 5).unwrap()
     }
-}
 
-impl From<CardMk1> for CardMk2 {
-    fn from(card: CardMk1) -> Self {
-        match card {
-            CardMk1::Two => CardMk2::Two,
-            CardMk1::Three => CardMk2::Three,
-            CardMk1::Four => CardMk2::Four,
-            CardMk1::Five => CardMk2::Five,
-            CardMk1::Six => CardMk2::Six,
-            CardMk1::Seven => CardMk2::Seven,
-            CardMk1::Eight => CardMk2::Eight,
-            CardMk1::Nine => CardMk2::Nine,
-            CardMk1::Ten => CardMk2::Ten,
-            CardMk1::Jack => CardMk2::Joker,
-            CardMk1::Queen => CardMk2::Queen,
-            CardMk1::King => CardMk2::King,
-            CardMk1::Ace => CardMk2::Ace,
-        }
+    /// Jacks become jokers: wild, but the weakest card in a tie.
+    fn jokers() -> Self {
+        Rules::new("J23456789TQKA", Some('J'), 5).unwrap()
     }
-}
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
-enum HandType {
-    HighCard,
-    OnePair,
-    TwoPair,
-    ThreeOfAKind,
-    FullHouse,
-    FourOfAKind,
-    FiveOfAKind,
-}
-
-trait Hand: FromStr<Err = Error> + Ord + Copy {
-    fn get_type(&self) -> HandType;
-}
+    fn named(name: &str) -> Result<Self> {
+        match name {
+            "standard" => Ok(Rules::standard()),
+            "jokers" => Ok(Rules::jokers()),
+            _ => Err(anyhow!("no rule set called {name:?}")),
+        }
+    }
 
-#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
-struct ArrayHand<CardType: Card> {
-    cards: [CardType; 5],
+    fn strength(&self, card: char) -> Result<usize> {
+        self.card_order
+            .iter()
+            .position(|it| *it == card)
+            .ok_or(anyhow!("Invalid card: {}", card))
+    }
 }
 
-type HandMk1 = ArrayHand<CardMk1>;
-type HandMk2 = ArrayHand<CardMk2>;
-
-impl Hand for ArrayHand<CardMk1> {
-    fn get_type(&self) -> HandType {
-        let matching_cards = self.cards.iter().counts();
-        let counts = matching_cards
-            .values()
+/// The sizes of the groups of matching cards, biggest first. Comparing these in order ranks five
+/// of a kind over four of a kind, a full house over three of a kind and so on, for any hand size.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct HandType(Vec<usize>);
+
+impl HandType {
+    /// The wild cards all join the biggest group. Nothing else makes the biggest group as big,
+    /// and the rest of the groups are the same whichever biggest group they join.
+    fn classify(cards: &[char], wildcard: Option<char>) -> Self {
+        let wildcards = cards.iter().filter(|card| Some(**card) == wildcard).count();
+        let mut groups = cards
+            .iter()
+            .filter(|card| Some(**card) != wildcard)
+            .counts()
+            .into_values()
             .sorted()
             .rev()
-            .copied()
             .collect_vec();
-        if counts.get(0) == Some(&5) {
-            HandType::FiveOfAKind
-        } else if counts.get(0) == Some(&4) {
-            HandType::FourOfAKind
-        } else if counts.get(0) == Some(&3) {
-            if counts.get(1) == Some(&2) {
-                HandType::FullHouse
-            } else {
-                HandType::ThreeOfAKind
-            }
-        } else if counts.get(0) == Some(&2) {
-            if counts.get(1) == Some(&2) {
-                HandType::TwoPair
-            } else {
-                HandType::OnePair
-            }
-        } else {
-            HandType::HighCard
+        match groups.first_mut() {
+            Some(biggest) => *biggest += wildcards,
+            None if wildcards > 0 => groups.push(wildcards),
+            None => {}
         }
+        HandType(groups)
     }
 }
 
-impl Hand for HandMk2 {
-    fn get_type(&self) -> HandType {
-        let jokers = self
-            .cards
-            .iter()
-            .filter(|card| **card == CardMk2::Joker)
-            .count();
-        if jokers == 5 {
-            return HandType::FiveOfAKind;
-        }
-        let matching_remaining_cards = self
-            .cards
-            .iter()
-            .filter(|card| **card != CardMk2::Joker)
-            .counts();
-        let counts = matching_remaining_cards
-            .values()
-            .sorted()
-            .rev()
-            .copied()
-            .collect_vec();
-        if counts.get(0).map(|it| it + jokers) == Some(5) {
-            HandType::FiveOfAKind
-        } else if counts.get(0).map(|it| it + jokers) == Some(4) {
-            HandType::FourOfAKind
-        } else if counts.get(0).map(|it| it + jokers) == Some(3) {
-            // note: there's no circumstance where a joker would be applied to a full house
-            // it would become a four-of-a-kind instead
-            if counts.get(1) == Some(&2) {
-                HandType::FullHouse
-            } else {
-                HandType::ThreeOfAKind
-            }
-        } else if counts.get(0).map(|it| it + jokers) == Some(2) {
-            // note: there's no circumstance where a joker would be applied to a two-pair
-            // it would become a three-of-a-kind instead
-            if counts.get(1) == Some(&2) {
-                HandType::TwoPair
-            } else {
-                HandType::OnePair
-            }
-        } else {
-            HandType::HighCard
+impl Display for HandType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let groups = self.0.iter().filter(|size| **size > 1).collect_vec();
+        match groups.as_slice() {
+            [] => write!(f, "high card"),
+            [2] => write!(f, "one pair"),
+            [2, 2] => write!(f, "two pair"),
+            [3] => write!(f, "three of a kind"),
+            [3, 2] => write!(f, "full house"),
+            [4] => write!(f, "four of a kind"),
+            [5] => write!(f, "five of a kind"),
+            _ => write!(f, "{}", groups.iter().join("+")),
         }
     }
 }
 
-impl<CardType: Card> Ord for ArrayHand<CardType>
-where
-    Self: Hand,
-{
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        let self_type = self.get_type();
-        let other_type = other.get_type();
-        if self_type != other_type {
-            return self_type.cmp(&other_type);
-        }
-        let self_cards = self.cards.iter().copied().collect_vec();
-        let other_cards = other.cards.iter().copied().collect_vec();
-        for (self_card, other_card) in self_cards.iter().zip(other_cards.iter()) {
-            if self_card != other_card {
-                return self_card.cmp(&other_card);
-            }
+/// Fields are in the order hands are compared: type first, then card by card.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct Hand {
+    hand_type: HandType,
+    /// Where each card is in the card order
+    strengths: Vec<usize>,
+    cards: Vec<char>,
+}
+
+impl Hand {
+    fn new(cards: &str, rules: &Rules) -> Result<Self> {
+        let cards = cards.chars().collect_vec();
+        if cards.len() != rules.hand_size {
+            return Err(anyhow!(
+                "Invalid hand: {} (should have {} cards)",
+                cards.iter().collect::<String>(),
+                rules.hand_size
+            ));
         }
-        std::cmp::Ordering::Equal
+        let strengths = cards
+            .iter()
+            .map(|card| rules.strength(*card))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Hand {
+            hand_type: HandType::classify(&cards, rules.wildcard),
+            strengths,
+            cards,
+        })
     }
-}
 
-impl<CardType: Card> PartialOrd for ArrayHand<CardType>
-where
-    Self: Hand,
-{
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.cmp(other))
+    /// The cards with the wild cards swapped for what they count as: the card in the biggest
+    /// group (the strongest, if there's a choice), or the strongest card if they're all wild.
+    fn played_as(&self, rules: &Rules) -> Vec<char> {
+        let Some(wildcard) = rules.wildcard else {
+            return self.cards.clone();
+        };
+        let counts = self.cards.iter().filter(|card| **card != wildcard).counts();
+        let played_as = counts
+            .into_iter()
+            .max_by_key(|(card, count)| (*count, rules.strength(**card).ok()))
+            .map(|(card, _)| *card)
+            .or_else(|| {
+                rules
+                    .card_order
+                    .iter()
+                    .rev()
+                    .find(|card| **card != wildcard)
+                    .copied()
+            })
+            // the wild card is the only card there is
+            .unwrap_or(wildcard);
+        self.cards
+            .iter()
+            .map(|card| if *card == wildcard { played_as } else { *card })
+            .collect()
     }
 }
 
-impl<CardType: Card> Display for ArrayHand<CardType> {
+impl Display for Hand {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         for card in self.cards.iter() {
-            write!(f, "{}", card.to_char())?;
+            write!(f, "{}", card)?;
         }
         Ok(())
     }
 }
 
-impl<CardType: Card> FromStr for ArrayHand<CardType> {
-    type Err = Error;
-
-    fn from_str(s: &str) -> Result<Self> {
-        let chars = s.chars();
-        let cards_vec = chars.map(CardType::from_char).collect::<Result<Vec<_>>>()?;
-        let cards = cards_vec
-            .try_into()
-            .map_err(|_| anyhow!("Invalid hand: {}", s))?;
-        Ok(ArrayHand { cards })
-    }
-}
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-struct HandWithBid<HandType: Hand> {
-    hand: HandType,
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct HandWithBid {
+    hand: Hand,
     bid: Number,
 }
 
-impl<HandType: Hand> FromStr for HandWithBid<HandType> {
-    type Err = Error;
-
-    fn from_str(s: &str) -> Result<Self> {
+impl HandWithBid {
+    fn parse(s: &str, rules: &Rules) -> Result<Self> {
         let (hand_str, bid_str) = s.split_once(" ").ok_or(anyhow!("Invalid input: {s}"))?;
-        let hand = HandType::from_str(hand_str)?;
+        let hand = Hand::new(hand_str, rules)?;
         let bid = bid_str.parse::<Number>()?;
         Ok(HandWithBid { hand, bid })
     }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-struct Game<HandType: Hand> {
-    hands: Vec<HandWithBid<HandType>>,
+struct Game {
+    hands: Vec<HandWithBid>,
 }
 
-impl<HandType: Hand> Game<HandType> {
+impl Game {
+    fn parse(s: &str, rules: &Rules) -> Result<Self> {
+        let hands = s
+            .lines()
+            .map(|line| HandWithBid::parse(line, rules))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Game { hands })
+    }
+
+    /// Every hand, weakest first, with what it won and why it beat the hand below it.
+    fn standings(&self, rules: &Rules) -> Vec<Standing> {
+        let ranked_hands = self
+            .hands
+            .iter()
+            .sorted_by_key(|hand| &hand.hand)
+            .collect_vec();
+        ranked_hands
+            .iter()
+            .enumerate()
+            .map(|(index, hand)| {
+                let tie_break = match index.checked_sub(1) {
+                    Some(below) => TieBreak::between(&hand.hand, &ranked_hands[below].hand),
+                    None => TieBreak::Bottom,
+                };
+                let rank = index as Number + 1;
+                Standing {
+                    rank,
+                    hand: hand.hand.clone(),
+                    played_as: hand.hand.played_as(rules),
+                    bid: hand.bid,
+                    winnings: hand.bid * rank,
+                    tie_break,
+                }
+            })
+            .collect()
+    }
+
     fn total_winnings(&self) -> Number {
         let ranked_hands = self
             .hands
             .iter()
-            .sorted_by_key(|hand| hand.hand)
+            .sorted_by_key(|hand| &hand.hand)
             .enumerate();
         ranked_hands
             .map(|(rank, hand)| hand.bid * (rank as Number + 1))
@@ -364,21 +351,129 @@ impl<HandType: Hand> Game<HandType> {
     }
 }
 
-impl<HandType: Hand> FromStr for Game<HandType> {
-    type Err = Error;
+/// Why a hand ranks above the hand just below it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum TieBreak {
+    /// The weakest hand has nothing below it
+    Bottom,
+    HandType {
+        below: HandType,
+    },
+    /// The first card that differs, counting from 1
+    Card {
+        position: usize,
+        card: char,
+        below: char,
+    },
+    /// The same cards as the hand below, so either order wins the same
+    Tied,
+}
 
-    fn from_str(s: &str) -> Result<Self> {
-        let hands = s
-            .lines()
-            .map(|line| line.parse::<HandWithBid<HandType>>())
-            .collect::<Result<Vec<_>>>()?;
-        Ok(Game { hands })
+impl TieBreak {
+    fn between(hand: &Hand, below: &Hand) -> Self {
+        if hand.hand_type != below.hand_type {
+            return TieBreak::HandType {
+                below: below.hand_type.clone(),
+            };
+        }
+        let position = hand
+            .strengths
+            .iter()
+            .zip(below.strengths.iter())
+            .position(|(card, below)| card != below);
+        match position {
+            Some(index) => TieBreak::Card {
+                position: index + 1,
+                card: hand.cards[index],
+                below: below.cards[index],
+            },
+            None => TieBreak::Tied,
+        }
+    }
+}
+
+impl Display for TieBreak {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TieBreak::Bottom => write!(f, "-"),
+            TieBreak::HandType { below } => write!(f, "type beats {}", below),
+            TieBreak::Card {
+                position,
+                card,
+                below,
+            } => write!(f, "card {}: {} beats {}", position, card, below),
+            TieBreak::Tied => write!(f, "same cards"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Standing {
+    rank: Number,
+    hand: Hand,
+    played_as: Vec<char>,
+    bid: Number,
+    /// Rank times bid
+    winnings: Number,
+    tie_break: TieBreak,
+}
+
+fn format_standings(standings: &[Standing], format: ReportFormat) -> String {
+    let headers = [
+        "Rank",
+        "Hand",
+        "Type",
+        "Played as",
+        "Bid",
+        "Winnings",
+        "Tie break",
+    ];
+    let numeric = [true, false, false, false, true, true, false];
+    let cells = standings
+        .iter()
+        .map(|standing| {
+            [
+                standing.rank.to_string(),
+                standing.hand.to_string(),
+                standing.hand.hand_type.to_string(),
+                standing.played_as.iter().collect(),
+                standing.bid.to_string(),
+                standing.winnings.to_string(),
+                standing.tie_break.to_string(),
+            ]
+        })
+        .collect_vec();
+    match format {
+        ReportFormat::Csv => {
+            let format_row = |row: &[&str]| row.iter().map(|cell| csv_field(cell)).join(",") + "\n";
+            let mut table = format_row(&headers);
+            for row in &cells {
+                table += &format_row(&row.iter().map(String::as_str).collect_vec());
+            }
+            table
+        }
+        ReportFormat::Text => {
+            let table = format_table(&headers, &cells, &numeric);
+            let total: Number = standings.iter().map(|standing| standing.winnings).sum();
+            table + &format!("Total winnings: {}\n", total)
+        }
+    }
+}
+
+/// Quotes a field if it has anything in it that CSV would trip over.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::framework::fuzz;
+    use crate::framework::property::{self, ensure_eq, Rng};
 
     #[test]
     fn test_part1() {
@@ -390,210 +485,321 @@ mod test {
 
     #[test]
     fn test_part2() {
-        assert_eq!("251135960".to_string(), super::Day7.part2().unwrap().unwrap());
+        assert_eq!(
+            "251135960".to_string(),
+            super::Day7.part2().unwrap().unwrap()
+        );
     }
 
-    fn sample_input<HandType: Hand>() -> Game<HandType> {
-        let input = indoc! {"
-            32T3K 765
-            T55J5 684
-            KK677 28
-            KTJJT 220
-            QQQJA 483
-        "};
-        input.parse::<Game<HandType>>().unwrap()
+    const SAMPLE_INPUT: &str = indoc! {"
+        32T3K 765
+        T55J5 684
+        KK677 28
+        KTJJT 220
+        QQQJA 483
+    "};
+
+    fn sample_input(rules: &Rules) -> Game {
+        Game::parse(SAMPLE_INPUT, rules).unwrap()
+    }
+
+    fn standard_hand(cards: &str) -> Hand {
+        Hand::new(cards, &Rules::standard()).unwrap()
+    }
+
+    fn joker_hand(cards: &str) -> Hand {
+        Hand::new(cards, &Rules::jokers()).unwrap()
+    }
+
+    #[test]
+    fn test_parser_never_panics() {
+        fuzz::check_parser("Game", &[SAMPLE_INPUT], |input| {
+            Game::parse(input, &Rules::standard())
+        });
+        fuzz::check_parser("Game", &[SAMPLE_INPUT], |input| {
+            Game::parse(input, &Rules::jokers())
+        });
     }
 
     #[test]
     fn test_parsing() {
-        let game = sample_input();
+        let game = sample_input(&Rules::standard());
         assert_eq!(5, game.hands.len());
         assert_eq!(
             HandWithBid {
-                hand: HandMk1 {
-                    cards: [
-                        CardMk1::Three,
-                        CardMk1::Two,
-                        CardMk1::Ten,
-                        CardMk1::Three,
-                        CardMk1::King
-                    ]
+                hand: Hand {
+                    hand_type: HandType(vec![2, 1, 1, 1]),
+                    strengths: vec![1, 0, 8, 1, 11],
+                    cards: vec!['3', '2', 'T', '3', 'K'],
                 },
                 bid: 765
             },
             game.hands[0]
         );
+        assert!(Game::parse("32T3 765", &Rules::standard()).is_err());
+        assert!(Game::parse("32T3X 765", &Rules::standard()).is_err());
     }
 
     #[test]
     fn test_hand_type() {
-        assert_eq!(
-            HandMk1::from_str("AAAAA").unwrap().get_type(),
-            HandType::FiveOfAKind
-        );
-        assert_eq!(
-            HandMk1::from_str("AA8AA").unwrap().get_type(),
-            HandType::FourOfAKind
-        );
-        assert_eq!(
-            HandMk1::from_str("23332").unwrap().get_type(),
-            HandType::FullHouse
-        );
-        assert_eq!(
-            HandMk1::from_str("TTT98").unwrap().get_type(),
-            HandType::ThreeOfAKind
-        );
-        assert_eq!(
-            HandMk1::from_str("23432").unwrap().get_type(),
-            HandType::TwoPair
-        );
-        assert_eq!(
-            HandMk1::from_str("A23A4").unwrap().get_type(),
-            HandType::OnePair
-        );
-        assert_eq!(
-            HandMk1::from_str("23456").unwrap().get_type(),
-            HandType::HighCard
-        );
+        let hand_type = |cards| standard_hand(cards).hand_type.to_string();
+        assert_eq!(hand_type("AAAAA"), "five of a kind");
+        assert_eq!(hand_type("AA8AA"), "four of a kind");
+        assert_eq!(hand_type("23332"), "full house");
+        assert_eq!(hand_type("TTT98"), "three of a kind");
+        assert_eq!(hand_type("23432"), "two pair");
+        assert_eq!(hand_type("A23A4"), "one pair");
+        assert_eq!(hand_type("23456"), "high card");
     }
 
     #[test]
     fn test_comparison() {
-        assert!(HandMk1::from_str("AA8AA").unwrap() > HandMk1::from_str("23456").unwrap());
-        assert!(HandMk1::from_str("33332").unwrap() > HandMk1::from_str("2AAAA").unwrap());
-        assert!(HandMk1::from_str("77888").unwrap() > HandMk1::from_str("77788").unwrap());
+        assert!(standard_hand("AA8AA") > standard_hand("23456"));
+        assert!(standard_hand("33332") > standard_hand("2AAAA"));
+        assert!(standard_hand("77888") > standard_hand("77788"));
     }
 
     #[test]
     fn test_total_winnings() {
-        let game: Game<HandMk1> = sample_input();
+        let game = sample_input(&Rules::standard());
         assert_eq!(game.total_winnings(), 6440);
     }
 
     #[test]
     fn test_jokers() {
-        assert_eq!(
-            HandMk2::from_str("QJJQ2").unwrap().get_type(),
-            HandType::FourOfAKind
-        );
-        assert_eq!(
-            HandMk2::from_str("T55J5").unwrap().get_type(),
-            HandType::FourOfAKind
-        );
-        assert_eq!(
-            HandMk2::from_str("KTJJT").unwrap().get_type(),
-            HandType::FourOfAKind
-        );
-        assert_eq!(
-            HandMk2::from_str("QQQJA").unwrap().get_type(),
-            HandType::FourOfAKind
-        );
+        assert_eq!(joker_hand("QJJQ2").hand_type.to_string(), "four of a kind");
+        assert_eq!(joker_hand("T55J5").hand_type.to_string(), "four of a kind");
+        assert_eq!(joker_hand("KTJJT").hand_type.to_string(), "four of a kind");
+        assert_eq!(joker_hand("QQQJA").hand_type.to_string(), "four of a kind");
     }
 
     #[test]
     fn test_joker_full_house() {
-        assert_eq!(
-            HandMk2::from_str("QQJTT").unwrap().get_type(),
-            HandType::FullHouse
-        );
+        assert_eq!(joker_hand("QQJTT").hand_type.to_string(), "full house");
     }
 
     #[test]
     fn test_joker_ordering() {
-        assert!(HandMk2::from_str("QQQQ2").unwrap() > HandMk2::from_str("JKKK2").unwrap());
+        assert!(joker_hand("QQQQ2") > joker_hand("JKKK2"));
     }
 
     #[test]
     fn test_random_real_inputs() {
-        fn get_hand_type(input: &str) -> HandType {
-            HandMk2::from_str(input).unwrap().get_type()
+        fn get_hand_type(input: &str) -> String {
+            joker_hand(input).hand_type.to_string()
         }
-        assert_eq!(get_hand_type("528Q8"), HandType::OnePair);
-        assert_eq!(get_hand_type("72776"), HandType::ThreeOfAKind);
-        assert_eq!(get_hand_type("TTJJT"), HandType::FiveOfAKind);
-        assert_eq!(get_hand_type("K68JJ"), HandType::ThreeOfAKind);
-        assert_eq!(get_hand_type("68868"), HandType::FullHouse);
-        assert_eq!(get_hand_type("4A527"), HandType::HighCard);
-        assert_eq!(get_hand_type("8T843"), HandType::OnePair);
-        assert_eq!(get_hand_type("AQ347"), HandType::HighCard);
-        assert_eq!(get_hand_type("737AJ"), HandType::ThreeOfAKind);
-        assert_eq!(get_hand_type("9Q93Q"), HandType::TwoPair);
-        assert_eq!(get_hand_type("47J47"), HandType::FullHouse);
-        assert_eq!(get_hand_type("5K26T"), HandType::HighCard);
-        assert_eq!(get_hand_type("6AK6A"), HandType::TwoPair);
-        assert_eq!(get_hand_type("T33JJ"), HandType::FourOfAKind);
-        assert_eq!(get_hand_type("5A2J6"), HandType::OnePair);
-        assert_eq!(get_hand_type("6JQ4K"), HandType::OnePair);
-        assert_eq!(get_hand_type("QQQ6Q"), HandType::FourOfAKind);
+        assert_eq!(get_hand_type("528Q8"), "one pair");
+        assert_eq!(get_hand_type("72776"), "three of a kind");
+        assert_eq!(get_hand_type("TTJJT"), "five of a kind");
+        assert_eq!(get_hand_type("K68JJ"), "three of a kind");
+        assert_eq!(get_hand_type("68868"), "full house");
+        assert_eq!(get_hand_type("4A527"), "high card");
+        assert_eq!(get_hand_type("8T843"), "one pair");
+        assert_eq!(get_hand_type("AQ347"), "high card");
+        assert_eq!(get_hand_type("737AJ"), "three of a kind");
+        assert_eq!(get_hand_type("9Q93Q"), "two pair");
+        assert_eq!(get_hand_type("47J47"), "full house");
+        assert_eq!(get_hand_type("5K26T"), "high card");
+        assert_eq!(get_hand_type("6AK6A"), "two pair");
+        assert_eq!(get_hand_type("T33JJ"), "four of a kind");
+        assert_eq!(get_hand_type("5A2J6"), "one pair");
+        assert_eq!(get_hand_type("6JQ4K"), "one pair");
+        assert_eq!(get_hand_type("QQQ6Q"), "four of a kind");
     }
 
     #[test]
     fn test_problematic_hands() {
+        assert_eq!(joker_hand("JJJJJ").hand_type.to_string(), "five of a kind");
+    }
+
+    #[test]
+    fn test_winnings_mk2() {
+        let game = sample_input(&Rules::jokers());
+        assert_eq!(game.total_winnings(), 5905);
+    }
+
+    #[test]
+    fn test_custom_rules() {
+        // three card hands, where the ace is low and the two is wild
+        let rules = Rules::new("A23456789TJQK", Some('2'), 3).unwrap();
+        let hand = |cards| Hand::new(cards, &rules).unwrap();
+        assert_eq!(hand("K2K").hand_type, HandType(vec![3]));
+        assert_eq!(hand("A2K").hand_type.to_string(), "one pair");
+        assert!(hand("A2K") > hand("QKJ"));
+        // both three of a kind, so the first card decides
+        assert!(hand("2KK") < hand("KKK"));
+        assert!(hand("2KK") > hand("A22"));
+        assert!(Hand::new("AKKK", &rules).is_err());
+
+        let hand = |cards| Hand::new(cards, &Rules::new("abc", None, 7).unwrap()).unwrap();
+        assert_eq!(hand("aaaabbc").hand_type.to_string(), "4+2");
+        assert_eq!(hand("cccbbba").hand_type.to_string(), "3+3");
+        assert!(hand("aaaabbc") > hand("cccbbba"));
+    }
+
+    #[test]
+    fn test_invalid_rules() {
+        assert!(Rules::new("", None, 5).is_err());
+        assert!(Rules::new("23452", None, 5).is_err());
+        assert!(Rules::new("2345", Some('J'), 5).is_err());
+        assert!(Rules::new("2345", None, 0).is_err());
+    }
+
+    #[test]
+    fn test_rule_args() {
+        let rules = |args: &[&str]| {
+            RuleArgs::try_parse_from(iter::once(&"winnings").chain(args))
+                .unwrap()
+                .rules()
+        };
+        assert_eq!(rules(&[]).unwrap(), Rules::standard());
+        assert_eq!(rules(&["--rules", "jokers"]).unwrap(), Rules::jokers());
         assert_eq!(
-            HandMk2::from_str("JJJJJ").unwrap().get_type(),
-            HandType::FiveOfAKind
+            rules(&[
+                "--rules",
+                "jokers",
+                "--wildcard",
+                "none",
+                "--hand-size",
+                "4"
+            ])
+            .unwrap(),
+            Rules::new("J23456789TQKA", None, 4).unwrap()
         );
+        assert_eq!(
+            rules(&["--card-order", "abc", "--wildcard", "b"]).unwrap(),
+            Rules::new("abc", Some('b'), 5).unwrap()
+        );
+        assert!(rules(&["--rules", "poker"]).is_err());
+        assert!(rules(&["--wildcard", "JQ"]).is_err());
     }
 
-    // #[test]
-    // fn test_brute_force_equivalence() {
-    //     let game: Game<HandMk2> = puzzle_input().unwrap();
-    //     for hand_with_bid in game.hands.iter() {
-    //         let mk2_type = hand_with_bid.hand.get_type();
-    //         let possible_mk1_cards = hand_with_bid
-    //             .hand
-    //             .cards
-    //             .iter()
-    //             .map(|card| match card {
-    //                 CardMk2::Joker => vec![
-    //                     CardMk1::Two,
-    //                     CardMk1::Three,
-    //                     CardMk1::Four,
-    //                     CardMk1::Five,
-    //                     CardMk1::Six,
-    //                     CardMk1::Seven,
-    //                     CardMk1::Eight,
-    //                     CardMk1::Nine,
-    //                     CardMk1::Ten,
-    //                     CardMk1::Queen,
-    //                     CardMk1::King,
-    //                     CardMk1::Ace,
-    //                 ],
-    //                 _ => vec![(*card).conv::<CardMk1>()],
-    //             })
-    //             .collect_vec();
-    //         let mut possible_mk1_hands = vec![vec![]];
-    //         for possible_cards in possible_mk1_cards.iter() {
-    //             let mut new_possible_mk1_hands = vec![];
-    //             for possible_hand in possible_mk1_hands.iter() {
-    //                 for possible_card in possible_cards.iter() {
-    //                     let mut new_possible_hand = possible_hand.clone();
-    //                     new_possible_hand.push(*possible_card);
-    //                     new_possible_mk1_hands.push(new_possible_hand);
-    //                 }
-    //             }
-    //             possible_mk1_hands = new_possible_mk1_hands;
-    //         }
-    //         let best_mk1_hand = possible_mk1_hands
-    //             .iter()
-    //             .map(|cards| HandMk1 {
-    //                 cards: cards.clone().try_into().unwrap(),
-    //             })
-    //             .max()
-    //             .unwrap();
-
-    //         let mk1_type = best_mk1_hand.get_type();
-    //         assert_eq!(
-    //             mk2_type, mk1_type,
-    //             "{} interpreted as {}, {:?}",
-    //             hand_with_bid.hand, best_mk1_hand, mk1_type
-    //         );
-    //     }
-    // }
+    fn random_hand(rng: &mut Rng, size: usize) -> Vec<char> {
+        let cards = &['A', 'K', 'Q', 'J', 'T'][..size.clamp(2, 5)];
+        (0..rng.gen_range(1..=5))
+            .map(|_| *rng.choose(cards))
+            .collect()
+    }
 
     #[test]
-    fn test_winnings_mk2() {
-        let game: Game<HandMk2> = sample_input();
-        assert_eq!(game.total_winnings(), 5905);
+    fn test_wildcards_make_the_best_hand() {
+        property::check("wildcards make the best hand", random_hand, |cards| {
+            // try the jacks as every other card
+            let best = cards
+                .iter()
+                .map(|card| match card {
+                    'J' => vec!['A', 'K', 'Q', 'T', '9'],
+                    _ => vec![*card],
+                })
+                .multi_cartesian_product()
+                .map(|cards| HandType::classify(&cards, None))
+                .max()
+                .unwrap();
+            ensure_eq(
+                HandType::classify(cards, Some('J')),
+                best.clone(),
+                "hand type",
+            )?;
+            let rules = Rules::new("JTQKA", Some('J'), cards.len())?;
+            let played_as = Hand::new(&cards.iter().collect::<String>(), &rules)?.played_as(&rules);
+            ensure_eq(HandType::classify(&played_as, None), best, "played as")
+        });
+    }
+
+    #[test]
+    fn test_standings() {
+        let rules = Rules::jokers();
+        let standings = sample_input(&rules).standings(&rules);
+        assert_eq!(
+            standings
+                .iter()
+                .map(|standing| standing.hand.to_string())
+                .collect_vec(),
+            vec!["32T3K", "KK677", "T55J5", "QQQJA", "KTJJT"]
+        );
+        assert_eq!(
+            standings
+                .iter()
+                .map(|standing| standing.played_as.iter().collect::<String>())
+                .collect_vec(),
+            vec!["32T3K", "KK677", "T5555", "QQQQA", "KTTTT"]
+        );
+        assert_eq!(standings[0].tie_break, TieBreak::Bottom);
+        assert_eq!(
+            standings[1].tie_break,
+            TieBreak::HandType {
+                below: HandType(vec![2, 1, 1, 1])
+            }
+        );
+        assert_eq!(
+            standings[3].tie_break,
+            TieBreak::Card {
+                position: 1,
+                card: 'Q',
+                below: 'T'
+            }
+        );
+        assert_eq!(
+            standings
+                .iter()
+                .map(|standing| standing.winnings)
+                .sum::<Number>(),
+            5905
+        );
+
+        let game = Game::parse("AKQJT 1\nAKQJT 2\nAKQJ9 3", &Rules::standard()).unwrap();
+        let standings = game.standings(&Rules::standard());
+        assert_eq!(
+            standings[1].tie_break,
+            TieBreak::Card {
+                position: 5,
+                card: 'T',
+                below: '9'
+            }
+        );
+        assert_eq!(standings[2].tie_break, TieBreak::Tied);
+    }
+
+    #[test]
+    fn test_played_as() {
+        let rules = Rules::jokers();
+        let played_as = |cards| {
+            joker_hand(cards)
+                .played_as(&rules)
+                .iter()
+                .collect::<String>()
+        };
+        assert_eq!(played_as("JJJJJ"), "AAAAA");
+        assert_eq!(played_as("2J3J4"), "24344");
+        assert_eq!(played_as("KKJQQ"), "KKKQQ");
+        assert_eq!(played_as("23456"), "23456");
+    }
+
+    #[test]
+    fn test_format_standings() {
+        let rules = Rules::jokers();
+        let standings = sample_input(&rules).standings(&rules);
+        assert_eq!(
+            format_standings(&standings, ReportFormat::Text),
+            indoc! {"
+                Rank  Hand   Type            Played as  Bid  Winnings  Tie break
+                   1  32T3K  one pair        32T3K      765       765  -
+                   2  KK677  two pair        KK677       28        56  type beats one pair
+                   3  T55J5  four of a kind  T5555      684      2052  type beats two pair
+                   4  QQQJA  four of a kind  QQQQA      483      1932  card 1: Q beats T
+                   5  KTJJT  four of a kind  KTTTT      220      1100  card 1: K beats Q
+                Total winnings: 5905
+            "}
+        );
+        assert_eq!(
+            format_standings(&standings[..2], ReportFormat::Csv),
+            indoc! {"
+                Rank,Hand,Type,Played as,Bid,Winnings,Tie break
+                1,32T3K,one pair,32T3K,765,765,-
+                2,KK677,two pair,KK677,28,56,type beats one pair
+            "}
+        );
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("\""), "\"\"\"\"");
     }
 }
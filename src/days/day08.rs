@@ -1,11 +1,16 @@
 // Day 8: Haunted Wasteland
 
-use std::collections::HashMap;
-use std::fmt::{Debug, Display};
+use std::iter;
 use std::str::FromStr;
 
+use clap::Parser;
+use num::integer::ExtendedGcd;
+use num::Integer;
 use regex::Regex;
 
+use crate::framework::cycle;
+use crate::framework::graph::{Graph, NodeShape, NodeStyle};
+use crate::framework::interner::Interner;
 use crate::framework::Day;
 use crate::prelude::*;
 
@@ -23,17 +28,55 @@ impl Day for Day8 {
 
     fn part1(&self) -> Option<Result<String>> {
         Some(try_block(move || {
-            Ok(puzzle_input()?.steps_to_reach_zzz()?.to_string())
+            Ok(puzzle_input()?
+                .steps_to_reach(&RoutePatterns::camel())?
+                .to_string())
         }))
     }
 
     fn part2(&self) -> Option<Result<String>> {
         Some(try_block(move || {
             Ok(puzzle_input()?
-                .steps_to_reach_ghostly_destinations()?
+                .steps_to_reach_ghostly_destinations(&RoutePatterns::ghosts())?
                 .to_string())
         }))
     }
+
+    fn run_script_with_args(&self, name: &str, args: &[String]) -> Result<bool> {
+        let patterns = || {
+            PatternArgs::try_parse_from(iter::once(name).chain(args.iter().map(String::as_str)))?
+                .patterns()
+        };
+        match name {
+            "mermaid_diagram" => println!(
+                "{}",
+                puzzle_input()?.network.as_mermaid_diagram(&patterns()?)
+            ),
+            "dot_diagram" => println!("{}", puzzle_input()?.network.as_dot_diagram(&patterns()?)),
+            "ghost_steps" => println!(
+                "{}",
+                puzzle_input()?.steps_to_reach_ghostly_destinations(&patterns()?)?
+            ),
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+}
+
+/// Picks where the ghosts start and where they're going from the command line, e.g.
+/// `--script ghost_steps -- --start '*A' --destination '*Z'`.
+#[derive(Debug, Parser)]
+struct PatternArgs {
+    #[arg(long, default_value = "*A")]
+    start: String,
+    #[arg(long, default_value = "*Z")]
+    destination: String,
+}
+
+impl PatternArgs {
+    fn patterns(&self) -> Result<RoutePatterns> {
+        RoutePatterns::new(&self.start, &self.destination)
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -42,65 +85,141 @@ enum Direction {
     Left,
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash)]
-struct NodeLabel([u8; 3]);
+/// Matches node labels, with `*` standing for any run of characters, like `*Z`.
+#[derive(Debug, Clone)]
+struct LabelPattern(Regex);
 
-impl NodeLabel {
-    fn is_start(&self) -> bool {
-        self.0[2] == 'A' as u8
+impl LabelPattern {
+    fn matches(&self, label: &str) -> bool {
+        self.0.is_match(label)
     }
+}
+
+impl FromStr for LabelPattern {
+    type Err = Error;
 
-    fn is_destination(&self) -> bool {
-        self.0[2] == 'Z' as u8
+    fn from_str(s: &str) -> Result<Self> {
+        let pattern = s.split('*').map(regex::escape).join(".*");
+        Ok(LabelPattern(Regex::new(&format!("^{}$", pattern))?))
     }
 }
 
-impl Display for NodeLabel {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}{}{}",
-            self.0[0].conv::<char>(),
-            self.0[1].conv::<char>(),
-            self.0[2].conv::<char>(),
-        )
-    }
+/// Which nodes to start from, and which nodes count as getting there.
+#[derive(Debug, Clone)]
+struct RoutePatterns {
+    start: LabelPattern,
+    destination: LabelPattern,
 }
 
-impl Debug for NodeLabel {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        Display::fmt(self, f)
+impl RoutePatterns {
+    fn new(start: &str, destination: &str) -> Result<Self> {
+        Ok(RoutePatterns {
+            start: start.parse()?,
+            destination: destination.parse()?,
+        })
+    }
+
+    /// From `AAA` to `ZZZ`.
+    fn camel() -> Self {
+        RoutePatterns::new("AAA", "ZZZ").unwrap()
+    }
+
+    /// From everything ending in `A` to anything ending in `Z`.
+    fn ghosts() -> Self {
+        RoutePatterns::new("*A", "*Z").unwrap()
     }
 }
 
-impl FromStr for NodeLabel {
-    type Err = Error;
+/// Where a node is listed in the input.
+type NodeIndex = usize;
 
-    fn from_str(s: &str) -> Result<Self> {
-        if s.chars().count() != 3 {
-            return Err(anyhow!("Invalid label: {}", s));
+/// Labels are only needed for parsing and output. Walking the network is all array lookups.
+#[derive(Debug, Clone)]
+struct Network {
+    labels: Interner<String>,
+    /// Where going left from each node leads
+    left: Vec<NodeIndex>,
+    /// Where going right from each node leads
+    right: Vec<NodeIndex>,
+}
+
+impl Network {
+    fn node_count(&self) -> usize {
+        self.labels.len()
+    }
+
+    fn label(&self, node: NodeIndex) -> &str {
+        self.labels.resolve(node)
+    }
+
+    fn next(&self, node: NodeIndex, direction: Direction) -> NodeIndex {
+        match direction {
+            Direction::Right => self.right[node],
+            Direction::Left => self.left[node],
         }
-        let mut chars = s.chars();
-        let mut label = [0; 3];
-        for i in 0..3 {
-            label[i] = chars
-                .next()
-                .ok_or_else(|| anyhow!("Invalid label"))?
-                .try_into()?;
+    }
+
+    fn nodes_matching<'a>(
+        &'a self,
+        pattern: &'a LabelPattern,
+    ) -> impl Iterator<Item = NodeIndex> + 'a {
+        self.labels
+            .iter()
+            .filter(|(_, label)| pattern.matches(label))
+            .map(|(node, _)| node)
+    }
+
+    /// Whether each node matches, indexed by node.
+    fn matching(&self, pattern: &LabelPattern) -> Vec<bool> {
+        self.labels
+            .iter()
+            .map(|(_, label)| pattern.matches(label))
+            .collect()
+    }
+
+    /// Each node has two outgoing edges, labeled with the direction that takes them.
+    fn graph(&self) -> Graph<NodeIndex, Direction> {
+        Graph::from_weighted_adjacency((0..self.node_count()).map(|node| {
+            (
+                node,
+                [
+                    (self.left[node], Direction::Left),
+                    (self.right[node], Direction::Right),
+                ],
+            )
+        }))
+    }
+
+    /// Starting points are hexagons, and destinations are double circles.
+    fn node_style(&self, patterns: &RoutePatterns, node: NodeIndex) -> NodeStyle {
+        let label = self.label(node);
+        let shape = if patterns.start.matches(label) {
+            NodeShape::Hexagon
+        } else if patterns.destination.matches(label) {
+            NodeShape::DoubleCircle
+        } else {
+            NodeShape::Box
+        };
+        NodeStyle::new(label, shape)
+    }
+
+    fn edge_label(direction: &Direction) -> Option<String> {
+        match direction {
+            Direction::Left => Some("L".to_string()),
+            Direction::Right => Some("R".to_string()),
         }
-        Ok(Self(label))
     }
-}
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-struct Node {
-    label: NodeLabel,
-    left: NodeLabel,
-    right: NodeLabel,
-}
+    fn as_mermaid_diagram(&self, patterns: &RoutePatterns) -> String {
+        self.graph()
+            .to_mermaid(|node| self.node_style(patterns, *node), Self::edge_label)
+    }
 
-#[derive(Debug, Clone)]
-struct Network(HashMap<NodeLabel, Node>);
+    fn as_dot_diagram(&self, patterns: &RoutePatterns) -> String {
+        self.graph()
+            .to_dot(|node| self.node_style(patterns, *node), Self::edge_label)
+    }
+}
 
 #[derive(Debug, Clone)]
 struct DesertMap {
@@ -109,7 +228,7 @@ struct DesertMap {
 }
 
 impl DesertMap {
-    fn path(&self, starting_node: NodeLabel) -> DesertPathIterator {
+    fn path(&self, starting_node: NodeIndex) -> DesertPathIterator {
         DesertPathIterator {
             map: self,
             current_node: starting_node,
@@ -117,113 +236,117 @@ impl DesertMap {
         }
     }
 
-    fn steps_to_reach(&self, destination: NodeLabel) -> Result<u32> {
-        let start: NodeLabel = "AAA".parse().unwrap();
-        for (i, result) in self.path(start).enumerate() {
-            let (node, _direction) = result?;
-            if node == destination {
-                return Ok(i as u32);
-            }
-        }
-        return Err(anyhow!("No path found"));
+    /// Once every (node, instruction) pair has come up, the path can only repeat itself.
+    fn state_count(&self) -> usize {
+        self.network.node_count() * self.instructions.len()
     }
 
-    fn steps_to_reach_zzz(&self) -> Result<u32> {
-        let zzz: NodeLabel = "ZZZ".parse().unwrap();
-        self.steps_to_reach(zzz)
+    /// Steps from the one node matching the start pattern to the first destination.
+    fn steps_to_reach(&self, patterns: &RoutePatterns) -> Result<usize> {
+        let start = self
+            .network
+            .nodes_matching(&patterns.start)
+            .exactly_one()
+            .map_err(|starts| anyhow!("Expected one starting node, found {}", starts.count()))?;
+        let is_destination = self.network.matching(&patterns.destination);
+        self.path(start)
+            .take(self.state_count() + 1)
+            .position(|(node, _direction)| is_destination[node])
+            .ok_or(anyhow!("No path found"))
     }
 
-    fn find_loop(&self, starting_node: NodeLabel) -> Result<PathLoop> {
-        let mut current_node = starting_node;
-        let mut sequence: Vec<NodeLabel> = vec![];
-        let mut seen_steps = HashMap::<(NodeLabel, usize), usize>::new();
-        let mut instruction_index = 0;
-        loop {
-            if seen_steps.contains_key(&(current_node, instruction_index)) {
-                break;
-            }
-            seen_steps.insert((current_node, instruction_index), sequence.len());
-            let instruction = self.instructions[instruction_index];
-            let node = self
-                .network
-                .0
-                .get(&current_node)
-                .ok_or(anyhow!("Couldn't find a node with label {}", current_node))?;
-            sequence.push(current_node);
-            current_node = match instruction {
-                Direction::Right => node.right,
-                Direction::Left => node.left,
-            };
-            instruction_index = (instruction_index + 1) % self.instructions.len();
-
-            if sequence.len() > self.instructions.len() * 100 {
-                return Err(anyhow!("Didn't find a loop after 100 iterations"));
-            }
-        }
-        let loop_start = *seen_steps.get(&(current_node, instruction_index)).unwrap();
-        let init = sequence[0..loop_start]
-            .iter()
-            .copied()
-            .collect_vec()
-            .into_boxed_slice();
-        let loop_sequence = sequence[loop_start..]
-            .iter()
-            .copied()
-            .collect_vec()
-            .into_boxed_slice();
+    fn find_loop(&self, starting_node: NodeIndex) -> Result<PathLoop> {
+        let instruction_count = self.instructions.len();
+        let path_cycle = cycle::find_cycle_by_index(
+            (starting_node, 0),
+            self.state_count(),
+            |&(node, instruction_index)| node * instruction_count + instruction_index,
+            |&(current_node, instruction_index)| {
+                let next_node = self
+                    .network
+                    .next(current_node, self.instructions[instruction_index]);
+                Ok((next_node, (instruction_index + 1) % instruction_count))
+            },
+        )?;
+        let nodes = |steps: &[(NodeIndex, usize)]| {
+            steps
+                .iter()
+                .map(|(node, _)| *node)
+                .collect_vec()
+                .into_boxed_slice()
+        };
         Ok(PathLoop {
-            init,
-            sequence: loop_sequence,
+            init: nodes(path_cycle.tail()),
+            sequence: nodes(path_cycle.looping()),
         })
     }
 
-    fn steps_to_reach_ghostly_destinations(&self) -> Result<usize> {
-        let starting_nodes = self
-            .network
-            .0
-            .keys()
-            .copied()
-            .filter(|label| label.is_start())
-            .collect_vec();
+    fn steps_to_reach_ghostly_destinations(&self, patterns: &RoutePatterns) -> Result<usize> {
+        let starting_nodes = self.network.nodes_matching(&patterns.start).collect_vec();
+        let is_destination = self.network.matching(&patterns.destination);
         let loops: Vec<PathLoop> = starting_nodes
             .par_iter()
             .map(|it| self.find_loop(*it))
             .collect::<Result<_>>()?;
-        let times_til_first_destination = loops.into_iter().map(|path_loop| {
-            let first_destination = path_loop
-                .sequence
+        let longest_init = loops
+            .iter()
+            .map(|path_loop| path_loop.init.len())
+            .max()
+            .ok_or(anyhow!("No loops found"))?;
+
+        // Until every ghost has reached its loop, just check each step
+        let early_arrival = (0..longest_init).find(|&step| {
+            loops
                 .iter()
-                .enumerate()
-                .find_map(|(i, label)| {
-                    if label.is_destination() {
-                        Some(i)
-                    } else {
-                        None
-                    }
-                })
-                .ok_or(anyhow!("No destinations in loop"))?;
-            Ok(path_loop.init.len() + first_destination)
+                .all(|path_loop| is_destination[path_loop.get(step)])
         });
-        times_til_first_destination
-            .reduce(|a, b| match (a, b) {
-                (Ok(a), Ok(b)) => Ok(num::integer::lcm(a, b)),
-                (Err(err), Ok(_)) => Err(err),
-                (Ok(_), Err(err)) => Err(err),
-                (Err(err1), Err(_)) => Err(err1),
+        if let Some(step) = early_arrival {
+            return Ok(step);
+        }
+
+        // After that, each ghost is at a destination whenever the step count is one of a few
+        // remainders modulo its loop length. Try every combination of remainders.
+        let congruences_per_loop = loops
+            .iter()
+            .map(|path_loop| {
+                let period = path_loop.sequence.len();
+                path_loop
+                    .sequence
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, node)| is_destination[**node])
+                    .map(|(i, _)| Congruence {
+                        remainder: ((path_loop.init.len() + i) % period) as i128,
+                        modulus: period as i128,
+                    })
+                    .collect_vec()
             })
-            .ok_or(anyhow!("No loops found"))
-            .and_then(|it| it)
+            .collect_vec();
+        let steps = congruences_per_loop
+            .iter()
+            .multi_cartesian_product()
+            .filter_map(|congruences| {
+                congruences
+                    .into_iter()
+                    .try_fold(Congruence::ANY, |combined, congruence| {
+                        combined.combine(*congruence)
+                    })
+            })
+            .map(|solution| solution.first_at_or_after(longest_init as i128))
+            .min()
+            .ok_or(anyhow!(
+                "The ghosts never reach destinations at the same time"
+            ))?;
+        Ok(usize::try_from(steps)?)
     }
 
     #[cfg(feature = "slow_solutions")]
-    fn steps_to_reach_ghostly_destinations_brute_force(&self) -> Result<usize> {
-        let starting_nodes = self
-            .network
-            .0
-            .keys()
-            .copied()
-            .filter(|label| label.is_start())
-            .collect_vec();
+    fn steps_to_reach_ghostly_destinations_brute_force(
+        &self,
+        patterns: &RoutePatterns,
+    ) -> Result<usize> {
+        let starting_nodes = self.network.nodes_matching(&patterns.start).collect_vec();
+        let is_destination = self.network.matching(&patterns.destination);
         let loops: Vec<PathLoop> = starting_nodes
             .par_iter()
             .map(|it| self.find_loop(*it))
@@ -244,7 +367,7 @@ impl DesertMap {
                     .sequence
                     .iter()
                     .enumerate()
-                    .filter_map(|(i, node)| if node.is_destination() { Some(i) } else { None })
+                    .filter_map(|(i, node)| if is_destination[*node] { Some(i) } else { None })
                     .collect_vec(),
             })
             .collect_vec();
@@ -311,12 +434,19 @@ impl DesertMap {
     }
 }
 
+lazy_static! {
+    static ref NODE_LINE_PATTERN: Regex =
+        Regex::new(r"^([^\s=(),]+) = \(([^\s=(),]+), ([^\s=(),]+)\)$").unwrap();
+}
+
 impl FromStr for DesertMap {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self> {
         let s = s.replace("\r\n", "\n");
-        let (instructions_str, nodes_str) = s.split_once("\n\n").unwrap();
+        let (instructions_str, nodes_str) = s
+            .split_once("\n\n")
+            .ok_or_else(|| anyhow!("Expected instructions, a blank line, then nodes"))?;
         let instructions = instructions_str
             .chars()
             .map(|char| match char {
@@ -325,25 +455,52 @@ impl FromStr for DesertMap {
                 _ => Err(anyhow!("Invalid direction: {}", char)),
             })
             .collect::<Result<Vec<_>>>()?;
-        let node_line_pattern =
-            Regex::new("^([A-Z0-9]{3}) = \\(([A-Z0-9]{3}), ([A-Z0-9]{3})\\)$").unwrap();
-        let nodes = nodes_str
+        if instructions.is_empty() {
+            return Err(anyhow!("Expected at least one instruction"));
+        }
+        let node_lines = nodes_str
             .lines()
             .map(|node_line| {
-                let captures = node_line_pattern
+                let captures = NODE_LINE_PATTERN
                     .captures(node_line)
                     .ok_or_else(|| anyhow!("Invalid node: {}", node_line))?;
-                let label = captures.get(1).unwrap().as_str().parse()?;
-                let left = captures.get(2).unwrap().as_str().parse()?;
-                let right = captures.get(3).unwrap().as_str().parse()?;
-                Ok(Node { label, left, right })
+                Ok((
+                    captures.get(1).unwrap().as_str(),
+                    captures.get(2).unwrap().as_str(),
+                    captures.get(3).unwrap().as_str(),
+                ))
             })
             .collect::<Result<Vec<_>>>()?;
 
-        let network = Network(nodes.into_iter().map(|node| (node.label, node)).collect());
+        // every node gets its index before any edges are looked up, so edges can point forwards
+        let mut labels = Interner::new();
+        for (label, _, _) in node_lines.iter() {
+            if labels.get(*label).is_some() {
+                return Err(anyhow!("Node {} is listed more than once", label));
+            }
+            labels.intern(label.to_string());
+        }
+        let find = |label: &str| {
+            labels
+                .get(label)
+                .ok_or_else(|| anyhow!("Couldn't find a node with label {}", label))
+        };
+        let left = node_lines
+            .iter()
+            .map(|(_, left, _)| find(left))
+            .collect::<Result<Vec<_>>>()?;
+        let right = node_lines
+            .iter()
+            .map(|(_, _, right)| find(right))
+            .collect::<Result<Vec<_>>>()?;
+
         Ok(DesertMap {
             instructions,
-            network,
+            network: Network {
+                labels,
+                left,
+                right,
+            },
         })
     }
 }
@@ -351,43 +508,43 @@ impl FromStr for DesertMap {
 #[derive(Debug)]
 struct DesertPathIterator<'a> {
     map: &'a DesertMap,
-    current_node: NodeLabel,
+    current_node: NodeIndex,
     instruction_index: usize,
 }
 
 impl<'a> Iterator for DesertPathIterator<'a> {
     /// The current item, and the next direction to go
-    type Item = Result<(NodeLabel, Direction)>;
+    type Item = (NodeIndex, Direction);
 
     fn next(&mut self) -> Option<Self::Item> {
         let instruction = self.map.instructions[self.instruction_index];
-        let node = self
-            .map
-            .network
-            .0
-            .get(&self.current_node)
-            .ok_or(anyhow!("Couldn't find a node with label"));
-        let node = match node {
-            Ok(node) => node,
-            Err(err) => return Some(Err(err)),
-        };
-        self.current_node = match instruction {
-            Direction::Right => node.right,
-            Direction::Left => node.left,
-        };
+        let node = self.current_node;
+        self.current_node = self.map.network.next(node, instruction);
         self.instruction_index = (self.instruction_index + 1) % self.map.instructions.len();
-        Some(Ok((node.label, instruction)))
+        Some((node, instruction))
     }
 }
 
 #[derive(Debug, PartialEq, Eq)]
 struct PathLoop {
-    init: Box<[NodeLabel]>,
-    sequence: Box<[NodeLabel]>,
+    init: Box<[NodeIndex]>,
+    sequence: Box<[NodeIndex]>,
+}
+
+impl PathLoop {
+    /// The node reached after `step` steps.
+    fn get(&self, step: usize) -> NodeIndex {
+        if step < self.init.len() {
+            self.init[step]
+        } else {
+            let loop_index = step - self.init.len();
+            self.sequence[loop_index % self.sequence.len()]
+        }
+    }
 }
 
 impl<'a> IntoIterator for &'a PathLoop {
-    type Item = NodeLabel;
+    type Item = NodeIndex;
     type IntoIter = PathLoopIterator<'a>;
 
     fn into_iter(self) -> Self::IntoIter {
@@ -405,23 +562,64 @@ struct PathLoopIterator<'a> {
 }
 
 impl<'a> Iterator for PathLoopIterator<'a> {
-    type Item = NodeLabel;
+    type Item = NodeIndex;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let result = if self.index < self.path_loop.init.len() {
-            Some(self.path_loop.init[self.index])
-        } else {
-            let loop_index = self.index - self.path_loop.init.len();
-            Some(self.path_loop.sequence[loop_index % self.path_loop.sequence.len()])
-        };
+        let result = self.path_loop.get(self.index);
         self.index += 1;
-        result
+        Some(result)
+    }
+}
+
+/// `x ≡ remainder (mod modulus)`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Congruence {
+    remainder: i128,
+    modulus: i128,
+}
+
+impl Congruence {
+    /// Satisfied by every number.
+    const ANY: Congruence = Congruence {
+        remainder: 0,
+        modulus: 1,
+    };
+
+    /// Generalized Chinese Remainder Theorem; the moduli don't need to be coprime. Returns `None`
+    /// if no number satisfies both, or if the combined modulus doesn't fit in an `i128`.
+    fn combine(self, other: Congruence) -> Option<Congruence> {
+        let ExtendedGcd { gcd, x, .. } = self.modulus.extended_gcd(&other.modulus);
+        let difference = other.remainder - self.remainder;
+        if difference % gcd != 0 {
+            return None;
+        }
+        let other_reduced = other.modulus / gcd;
+        let modulus = (self.modulus / gcd).checked_mul(other.modulus)?;
+        // self.remainder + self.modulus * k satisfies both when
+        // k ≡ (difference / gcd) * x (mod other.modulus / gcd)
+        let k = (difference / gcd)
+            .rem_euclid(other_reduced)
+            .checked_mul(x.rem_euclid(other_reduced))?
+            .rem_euclid(other_reduced);
+        let remainder = (self.remainder + self.modulus.checked_mul(k)?).rem_euclid(modulus);
+        Some(Congruence { remainder, modulus })
+    }
+
+    fn first_at_or_after(&self, minimum: i128) -> i128 {
+        if self.remainder >= minimum {
+            self.remainder
+        } else {
+            let periods = (minimum - self.remainder + self.modulus - 1) / self.modulus;
+            self.remainder + periods * self.modulus
+        }
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::framework::fuzz;
+    use crate::framework::property::{self, ensure_eq, Rng};
 
     #[test]
     fn test_part1() {
@@ -439,34 +637,82 @@ mod test {
         assert_eq!(result, 13663968099527);
     }
 
-    fn sample_input() -> DesertMap {
-        let input = indoc! {"
-            RL
+    const SAMPLE_INPUT: &str = indoc! {"
+        RL
 
-            AAA = (BBB, CCC)
-            BBB = (DDD, EEE)
-            CCC = (ZZZ, GGG)
-            DDD = (DDD, DDD)
-            EEE = (EEE, EEE)
-            GGG = (GGG, GGG)
-            ZZZ = (ZZZ, ZZZ)
-        "};
-        DesertMap::from_str(input).unwrap()
+        AAA = (BBB, CCC)
+        BBB = (DDD, EEE)
+        CCC = (ZZZ, GGG)
+        DDD = (DDD, DDD)
+        EEE = (EEE, EEE)
+        GGG = (GGG, GGG)
+        ZZZ = (ZZZ, ZZZ)
+    "};
+
+    fn sample_input() -> DesertMap {
+        DesertMap::from_str(SAMPLE_INPUT).unwrap()
     }
 
     #[test]
     fn test_parse() {
         let desert_map = sample_input();
         assert_eq!(desert_map.instructions.len(), 2);
-        assert_eq!(desert_map.network.0.len(), 7);
-        assert!(desert_map.network.0.contains_key(&"AAA".parse().unwrap()));
-        assert!(desert_map.network.0.contains_key(&"ZZZ".parse().unwrap()));
+        assert_eq!(desert_map.network.node_count(), 7);
+        let aaa = desert_map.network.labels.get("AAA").unwrap();
+        let bbb = desert_map.network.labels.get("BBB").unwrap();
+        assert_eq!(desert_map.network.label(aaa), "AAA");
+        assert_eq!(desert_map.network.next(aaa, Direction::Left), bbb);
+        assert!(desert_map.network.labels.get("ZZZ").is_some());
+        assert!(desert_map.network.labels.get("XXX").is_none());
+    }
+
+    #[test]
+    fn test_graph() {
+        let desert_map = sample_input();
+        let graph = desert_map.network.graph();
+        assert_eq!(graph.node_count(), 7);
+        assert_eq!(graph.edge_count(), 14);
+        let network = &desert_map.network;
+        let start = graph.find(|node| network.label(*node) == "AAA").unwrap();
+        let end = graph.find(|node| network.label(*node) == "ZZZ").unwrap();
+        assert_eq!(graph.shortest_paths(start, |_| 1)[end], Some(2));
+        let into_end = graph
+            .in_edges(end)
+            .map(|edge| {
+                (
+                    network.label(*graph.node(edge.from)).to_string(),
+                    edge.weight,
+                )
+            })
+            .sorted_by_key(|(from, _)| from.clone())
+            .collect_vec();
+        assert_eq!(
+            into_end,
+            vec![
+                ("CCC".to_string(), Direction::Left),
+                ("ZZZ".to_string(), Direction::Left),
+                ("ZZZ".to_string(), Direction::Right),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_mermaid_diagram() {
+        let desert_map = sample_input();
+        let diagram = desert_map
+            .network
+            .as_mermaid_diagram(&RoutePatterns::ghosts());
+        assert!(diagram.contains("{{\"AAA\"}}"));
+        assert!(diagram.contains("[\"BBB\"]"));
+        assert!(diagram.contains("(((\"ZZZ\")))"));
+        assert_eq!(diagram.matches("-->|\"L\"|").count(), 7);
+        assert_eq!(diagram.matches("-->|\"R\"|").count(), 7);
     }
 
     #[test]
     fn test_navigate() {
         let desert_map = sample_input();
-        let result = desert_map.steps_to_reach_zzz().unwrap();
+        let result = desert_map.steps_to_reach(&RoutePatterns::camel()).unwrap();
         assert_eq!(result, 2);
     }
 
@@ -480,39 +726,302 @@ mod test {
             ZZZ = (ZZZ, ZZZ)
         "})
         .unwrap();
-        let result = desert_map.steps_to_reach_zzz().unwrap();
+        let result = desert_map.steps_to_reach(&RoutePatterns::camel()).unwrap();
         assert_eq!(result, 6);
     }
 
+    const GHOST_SAMPLE_INPUT: &str = indoc! {"
+        LR
+
+        11A = (11B, XXX)
+        11B = (XXX, 11Z)
+        11Z = (11B, XXX)
+        22A = (22B, XXX)
+        22B = (22C, 22C)
+        22C = (22Z, 22Z)
+        22Z = (22B, 22B)
+        XXX = (XXX, XXX)
+    "};
+
     fn sample_input_for_ghosts() -> DesertMap {
-        let input = indoc! {"
-            LR
-
-            11A = (11B, XXX)
-            11B = (XXX, 11Z)
-            11Z = (11B, XXX)
-            22A = (22B, XXX)
-            22B = (22C, 22C)
-            22C = (22Z, 22Z)
-            22Z = (22B, 22B)
-            XXX = (XXX, XXX)
-        "};
-        DesertMap::from_str(input).unwrap()
+        DesertMap::from_str(GHOST_SAMPLE_INPUT).unwrap()
+    }
+
+    #[test]
+    fn test_parser_never_panics() {
+        fuzz::check_parser(
+            "DesertMap",
+            &[SAMPLE_INPUT, GHOST_SAMPLE_INPUT],
+            DesertMap::from_str,
+        );
     }
 
     #[test]
     fn test_navigate_for_ghosts() {
         let desert_map = sample_input_for_ghosts();
-        let result = desert_map.steps_to_reach_ghostly_destinations().unwrap();
+        let result = desert_map
+            .steps_to_reach_ghostly_destinations(&RoutePatterns::ghosts())
+            .unwrap();
         assert_eq!(result, 6);
     }
 
+    /// Moves every ghost one step at a time, for checking the real solver
+    fn simulate_ghosts(desert_map: &DesertMap, limit: usize) -> Option<usize> {
+        let patterns = RoutePatterns::ghosts();
+        let is_destination = desert_map.network.matching(&patterns.destination);
+        let mut paths = desert_map
+            .network
+            .nodes_matching(&patterns.start)
+            .map(|node| desert_map.path(node))
+            .collect_vec();
+        (0..limit).find(|_| {
+            paths
+                .iter_mut()
+                .map(|path| path.next().unwrap().0)
+                .collect_vec()
+                .iter()
+                .all(|node| is_destination[*node])
+        })
+    }
+
+    #[test]
+    fn test_ghosts_with_several_destinations_per_loop() {
+        // loops of length 5, 3 and 6, with destinations at different offsets
+        let desert_map = DesertMap::from_str(indoc! {"
+            L
+
+            11A = (12B, 12B)
+            12B = (13Z, 13Z)
+            13Z = (14B, 14B)
+            14B = (15Z, 15Z)
+            15Z = (16B, 16B)
+            16B = (12B, 12B)
+            21A = (22B, 22B)
+            22B = (23B, 23B)
+            23B = (24Z, 24Z)
+            24Z = (22B, 22B)
+            31A = (32Z, 32Z)
+            32Z = (33B, 33B)
+            33B = (34B, 34B)
+            34B = (35B, 35B)
+            35B = (36B, 36B)
+            36B = (37Z, 37Z)
+            37Z = (32Z, 32Z)
+        "})
+        .unwrap();
+        let result = desert_map
+            .steps_to_reach_ghostly_destinations(&RoutePatterns::ghosts())
+            .unwrap();
+        assert_eq!(result, 12);
+        assert_eq!(simulate_ghosts(&desert_map, 1000), Some(12));
+    }
+
+    #[test]
+    fn test_ghosts_with_destinations_before_loop() {
+        let desert_map = DesertMap::from_str(indoc! {"
+            L
+
+            11A = (11Z, 11Z)
+            11Z = (11B, 11B)
+            11B = (11C, 11C)
+            11C = (11B, 11B)
+            22A = (22Z, 22Z)
+            22Z = (22Z, 22Z)
+        "})
+        .unwrap();
+        assert_eq!(
+            desert_map
+                .steps_to_reach_ghostly_destinations(&RoutePatterns::ghosts())
+                .unwrap(),
+            1
+        );
+        assert_eq!(simulate_ghosts(&desert_map, 1000), Some(1));
+    }
+
+    #[test]
+    fn test_ghosts_that_never_meet() {
+        let desert_map = DesertMap::from_str(indoc! {"
+            L
+
+            11A = (11Z, 11Z)
+            11Z = (11A, 11A)
+            22A = (22B, 22B)
+            22B = (22Z, 22Z)
+            22Z = (22B, 22B)
+        "})
+        .unwrap();
+        assert!(desert_map
+            .steps_to_reach_ghostly_destinations(&RoutePatterns::ghosts())
+            .is_err());
+        assert_eq!(simulate_ghosts(&desert_map, 1000), None);
+    }
+
+    /// Up to 6 nodes and 3 ghosts, with short instructions, so that simulating every ghost until
+    /// their combined state repeats stays cheap.
+    fn random_desert_map(rng: &mut Rng, size: usize) -> DesertMap {
+        let instructions = (0..rng.gen_range(1..=3))
+            .map(|_| *rng.choose(&['L', 'R']))
+            .collect::<String>();
+        let mut start_count = 0;
+        let labels = (0..rng.gen_range(2..=2 + size.min(4)))
+            .map(|i| {
+                let suffix = if i == 0 || (start_count < 3 && rng.chance(1, 4)) {
+                    start_count += 1;
+                    'A'
+                } else if rng.chance(1, 3) {
+                    'Z'
+                } else {
+                    'X'
+                };
+                format!("Q{}{}", char::from(b'B' + i as u8), suffix)
+            })
+            .collect_vec();
+        let nodes = labels
+            .iter()
+            .map(|label| {
+                let left = rng.choose(&labels);
+                let right = rng.choose(&labels);
+                format!("{} = ({}, {})", label, left, right)
+            })
+            .join("\n");
+        DesertMap::from_str(&format!("{}\n\n{}\n", instructions, nodes)).unwrap()
+    }
+
+    #[test]
+    fn test_ghost_solutions_agree() {
+        property::check("ghost solutions agree", random_desert_map, |desert_map| {
+            // once the tuple of every ghost's (node, instruction) repeats, nothing new can happen
+            let patterns = RoutePatterns::ghosts();
+            let states = desert_map.state_count();
+            let ghosts = desert_map.network.nodes_matching(&patterns.start);
+            let limit = ghosts.fold(1, |limit, _| limit * states) + 1;
+            let simulated = simulate_ghosts(desert_map, limit);
+            let solved = desert_map.steps_to_reach_ghostly_destinations(&RoutePatterns::ghosts());
+            match (&solved, simulated) {
+                (Ok(solved), Some(simulated)) => ensure_eq(*solved, simulated, "steps")?,
+                (Err(_), None) => {}
+                _ => return Err(anyhow!("solved {:?}, simulated {:?}", solved, simulated)),
+            }
+
+            #[cfg(feature = "slow_solutions")]
+            if let Some(simulated) = simulated {
+                // the brute force only looks once every ghost is in its loop
+                let longest_init = desert_map
+                    .network
+                    .nodes_matching(&patterns.start)
+                    .map(|node| desert_map.find_loop(node).map(|it| it.init.len()))
+                    .fold_ok(0, usize::max)?;
+                if simulated >= longest_init {
+                    ensure_eq(
+                        desert_map.steps_to_reach_ghostly_destinations_brute_force(&patterns)?,
+                        simulated,
+                        "brute force steps",
+                    )?;
+                }
+            }
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn test_any_labels() {
+        let desert_map = DesertMap::from_str(indoc! {"
+            RL
+
+            start = (lever, a-long-way)
+            a-long-way = (fin, fin)
+            lever = (start, fin)
+            fin = (fin, fin)
+            öö = (fin, start)
+        "})
+        .unwrap();
+        assert_eq!(desert_map.network.node_count(), 5);
+        let patterns = RoutePatterns::new("start", "fin").unwrap();
+        assert_eq!(desert_map.steps_to_reach(&patterns).unwrap(), 2);
+        let patterns = RoutePatterns::new("*t", "f*").unwrap();
+        assert_eq!(
+            desert_map
+                .steps_to_reach_ghostly_destinations(&patterns)
+                .unwrap(),
+            2
+        );
+        let patterns = RoutePatterns::new("öö", "start").unwrap();
+        assert_eq!(desert_map.steps_to_reach(&patterns).unwrap(), 1);
+        let patterns = RoutePatterns::new("*", "fin").unwrap();
+        assert!(desert_map.steps_to_reach(&patterns).is_err());
+        let patterns = RoutePatterns::new("fin", "start").unwrap();
+        assert_eq!(
+            desert_map
+                .steps_to_reach(&patterns)
+                .unwrap_err()
+                .to_string(),
+            "No path found"
+        );
+    }
+
+    #[test]
+    fn test_label_patterns() {
+        let pattern = LabelPattern::from_str("*Z").unwrap();
+        assert!(pattern.matches("ZZZ"));
+        assert!(pattern.matches("Z"));
+        assert!(!pattern.matches("ZZA"));
+        let pattern = LabelPattern::from_str("a.*b").unwrap();
+        assert!(pattern.matches("a.b"));
+        assert!(pattern.matches("a.xyzb"));
+        assert!(!pattern.matches("axb"));
+    }
+
+    #[test]
+    fn test_invalid_networks() {
+        let parse = |input| DesertMap::from_str(input).unwrap_err().to_string();
+        assert_eq!(
+            parse("L\n\nAAA = (AAA, BBB)\n"),
+            "Couldn't find a node with label BBB"
+        );
+        assert_eq!(
+            parse("L\n\nAAA = (AAA, AAA)\nAAA = (AAA, AAA)\n"),
+            "Node AAA is listed more than once"
+        );
+        assert_eq!(
+            parse("\n\nAAA = (AAA, AAA)\n"),
+            "Expected at least one instruction"
+        );
+    }
+
+    #[test]
+    fn test_combine_congruences() {
+        let combined = Congruence {
+            remainder: 2,
+            modulus: 4,
+        }
+        .combine(Congruence {
+            remainder: 4,
+            modulus: 6,
+        });
+        assert_eq!(
+            combined,
+            Some(Congruence {
+                remainder: 10,
+                modulus: 12
+            })
+        );
+        let impossible = Congruence {
+            remainder: 1,
+            modulus: 4,
+        }
+        .combine(Congruence {
+            remainder: 2,
+            modulus: 6,
+        });
+        assert_eq!(impossible, None);
+    }
+
     #[cfg(feature = "slow_solutions")]
     #[test]
     fn test_navigate_for_ghosts_brute_force() {
         let desert_map = sample_input_for_ghosts();
         let result = desert_map
-            .steps_to_reach_ghostly_destinations_brute_force()
+            .steps_to_reach_ghostly_destinations_brute_force(&RoutePatterns::ghosts())
             .unwrap();
         assert_eq!(result, 6);
     }
@@ -522,7 +1031,7 @@ mod test {
     fn test_part_two_brute_force() {
         let desert_map = puzzle_input().unwrap();
         let result = desert_map
-            .steps_to_reach_ghostly_destinations_brute_force()
+            .steps_to_reach_ghostly_destinations_brute_force(&RoutePatterns::ghosts())
             .unwrap();
         assert_eq!(result, 13663968099527);
     }
@@ -530,87 +1039,57 @@ mod test {
     #[test]
     fn test_loop_equivalence() {
         let desert_map = sample_input_for_ghosts();
-        let loop1 = desert_map
-            .find_loop(NodeLabel::from_str("11A").unwrap())
-            .unwrap();
-        let path1 = desert_map.path(NodeLabel::from_str("11A").unwrap());
-        for (i, (path_node, loop_node)) in path1.zip(&loop1).take(100).enumerate() {
-            assert_eq!(path_node.unwrap().0, loop_node, "step {}", i);
-        }
-
-        let loop1 = desert_map
-            .find_loop(NodeLabel::from_str("22A").unwrap())
-            .unwrap();
-        let path1 = desert_map.path(NodeLabel::from_str("22A").unwrap());
-        for (i, (path_node, loop_node)) in path1.zip(&loop1).take(100).enumerate() {
-            assert_eq!(path_node.unwrap().0, loop_node, "step {}", i);
+        for start in ["11A", "22A"] {
+            let start = desert_map.network.labels.get(start).unwrap();
+            let path_loop = desert_map.find_loop(start).unwrap();
+            let path = desert_map.path(start);
+            for (i, (path_node, loop_node)) in path.zip(&path_loop).take(100).enumerate() {
+                assert_eq!(path_node.0, loop_node, "step {}", i);
+            }
         }
     }
 
     #[test]
     fn test_assumptions() {
+        let patterns = RoutePatterns::ghosts();
         let puzzle_input = puzzle_input().unwrap();
         let sample_input = sample_input_for_ghosts();
 
-        let puzzle_starting_nodes = puzzle_input
-            .network
-            .0
-            .keys()
-            .copied()
-            .filter(|label| label.is_start())
-            .collect_vec();
-        let puzzle_loops = puzzle_starting_nodes
-            .iter()
-            .map(|starting_node| puzzle_input.find_loop(*starting_node))
-            .collect::<Result<Vec<_>>>()
-            .unwrap();
-
-        let sample_loops = sample_input
-            .network
-            .0
-            .keys()
-            .copied()
-            .filter(|label| label.is_start())
-            .map(|starting_node| sample_input.find_loop(starting_node))
-            .collect::<Result<Vec<_>>>()
-            .unwrap();
-
-        let all_loops = puzzle_loops.iter().chain(sample_loops.iter()).collect_vec();
-
-        for path_loop in &all_loops {
-            let starting_node = path_loop.init[0];
-            let destinations_in_init = path_loop
-                .init
-                .iter()
-                .copied()
-                .enumerate()
-                .filter(|(_, label)| label.is_destination())
-                .collect_vec();
-            let destinations_in_sequence = path_loop
-                .sequence
-                .iter()
-                .copied()
-                .enumerate()
-                .filter(|(_, label)| label.is_destination())
-                .collect_vec();
-            assert_eq!(
-                destinations_in_init.len(),
-                0,
-                "path starting at {} should not have destinations in init. found: {:?}",
-                starting_node,
-                destinations_in_init
-            );
-            assert!(
-                destinations_in_sequence.len() >= 1,
-                "path starting at {} should have at least one destination in sequence. found: {:?}",
-                starting_node,
-                destinations_in_sequence
-            );
-
-            // I have no idea why this might be the case, but it sure is useful
-            assert!(destinations_in_sequence.iter().any(|destination| {
-                path_loop.sequence.len() == path_loop.init.len() + destination.0
-            }));
+        for desert_map in [&puzzle_input, &sample_input] {
+            let is_destination = desert_map.network.matching(&patterns.destination);
+            for starting_node in desert_map.network.nodes_matching(&patterns.start) {
+                let path_loop = desert_map.find_loop(starting_node).unwrap();
+                let starting_node = desert_map.network.label(starting_node);
+                let destinations = |nodes: &[NodeIndex]| {
+                    nodes
+                        .iter()
+                        .copied()
+                        .enumerate()
+                        .filter(|(_, node)| is_destination[*node])
+                        .map(|(i, node)| (i, desert_map.network.label(node).to_string()))
+                        .collect_vec()
+                };
+                let destinations_in_init = destinations(&path_loop.init);
+                let destinations_in_sequence = destinations(&path_loop.sequence);
+                assert_eq!(
+                    destinations_in_init.len(),
+                    0,
+                    "path starting at {} should not have destinations in init. found: {:?}",
+                    starting_node,
+                    destinations_in_init
+                );
+                assert!(
+                    destinations_in_sequence.len() >= 1,
+                    "path starting at {} should have at least one destination in sequence. found: {:?}",
+                    starting_node,
+                    destinations_in_sequence
+                );
+
+                // I have no idea why this might be the case, but it sure is useful
+                assert!(destinations_in_sequence.iter().any(|destination| {
+                    path_loop.sequence.len() == path_loop.init.len() + destination.0
+                }));
+            }
         }
     }
 }
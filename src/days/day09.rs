@@ -1,15 +1,13 @@
 // Day 9: Mirage Maintenance
 
+use crate::framework::parse;
 use crate::framework::Day;
 use crate::prelude::*;
 
 pub struct Day9;
 
 fn puzzle_input() -> Result<Vec<Vec<i32>>> {
-    include_str!("./day09_input.txt")
-        .lines()
-        .map(parse_sequence)
-        .collect()
+    parse_sequences(include_str!("./day09_input.txt"))
 }
 
 impl Day for Day9 {
@@ -36,11 +34,8 @@ impl Day for Day9 {
     }
 }
 
-fn parse_sequence(input: &str) -> Result<Vec<i32>> {
-    input
-        .split_ascii_whitespace()
-        .map(|s| s.parse::<i32>().map_err(|err| err.into()))
-        .collect()
+fn parse_sequences(input: &str) -> Result<Vec<Vec<i32>>> {
+    parse::lines(input, parse::numbers)
 }
 
 fn get_difference_sequence(input: &[i32]) -> Vec<i32> {
@@ -66,6 +61,7 @@ fn extrapolate_backwards(input: &[i32]) -> i32 {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::framework::fuzz;
 
     #[test]
     fn test_part1() {
@@ -80,27 +76,30 @@ mod test {
         assert_eq!(super::Day9.part2().unwrap().unwrap(), "908".to_string());
     }
 
+    const SAMPLE_INPUT: &str = indoc! {"
+        0 3 6 9 12 15
+        1 3 6 10 15 21
+        10 13 16 21 30 45
+    "};
+
     fn sample_input() -> Vec<Vec<i32>> {
-        indoc! {"
-            0 3 6 9 12 15
-            1 3 6 10 15 21
-            10 13 16 21 30 45
-        "}
-        .lines()
-        .map(parse_sequence)
-        .collect::<Result<Vec<Vec<i32>>>>()
-        .unwrap()
+        parse_sequences(SAMPLE_INPUT).unwrap()
+    }
+
+    #[test]
+    fn test_parser_never_panics() {
+        fuzz::check_parser("sequences", &[SAMPLE_INPUT], parse_sequences);
     }
 
     #[test]
     fn test_parsing() {
-        let result = parse_sequence("0 3 6 9 12 15").unwrap();
+        let result = parse::numbers::<i32>("0 3 6 9 12 15").unwrap();
         assert_eq!(result, vec![0, 3, 6, 9, 12, 15])
     }
 
     #[test]
     fn test_get_difference_sequence() {
-        let input = parse_sequence("0 3 6 9 12 15").unwrap();
+        let input = parse::numbers::<i32>("0 3 6 9 12 15").unwrap();
         let expected = vec![3, 3, 3, 3, 3];
         let result = get_difference_sequence(&input);
         assert_eq!(result, expected);
@@ -433,6 +433,7 @@ impl Display for Grid {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::framework::fuzz;
 
     #[test]
     fn test_part1() {
@@ -457,15 +458,16 @@ mod test {
         assert_eq!(grid.to_string(), input);
     }
 
+    const SIMPLE_INPUT: &str = indoc! {"
+        .....
+        .S-7.
+        .|.|.
+        .L-J.
+        .....
+    "};
+
     fn simple_input() -> Grid {
-        let input = indoc! {"
-            .....
-            .S-7.
-            .|.|.
-            .L-J.
-            .....
-        "};
-        Grid::from_str(input).unwrap()
+        Grid::from_str(SIMPLE_INPUT).unwrap()
     }
 
     fn complex_input() -> Grid {
@@ -515,20 +517,26 @@ mod test {
         Grid::from_str(input).unwrap()
     }
 
+    const COMPLEX_ENCLOSED: &str = indoc! {"
+        FF7FSF7F7F7F7F7F---7
+        L|LJ||||||||||||F--J
+        FL-7LJLJ||||||LJL-77
+        F--JF--7||LJLJ7F7FJ-
+        L---JF-JLJ.||-FJLJJ7
+        |F|F-JF---7F7-L7L|7|
+        |FFJF7L7F-JF7|JL---7
+        7-L-JL7||F7|L7F-7F7|
+        L.L7LFJ|||||FJL7||LJ
+        L7JLJL-JLJLJL--JLJ.L
+    "};
+
     fn complex_enclosed() -> Grid {
-        let input = indoc! {"
-            FF7FSF7F7F7F7F7F---7
-            L|LJ||||||||||||F--J
-            FL-7LJLJ||||||LJL-77
-            F--JF--7||LJLJ7F7FJ-
-            L---JF-JLJ.||-FJLJJ7
-            |F|F-JF---7F7-L7L|7|
-            |FFJF7L7F-JF7|JL---7
-            7-L-JL7||F7|L7F-7F7|
-            L.L7LFJ|||||FJL7||LJ
-            L7JLJL-JLJLJL--JLJ.L
-        "};
-        Grid::from_str(input).unwrap()
+        Grid::from_str(COMPLEX_ENCLOSED).unwrap()
+    }
+
+    #[test]
+    fn test_parser_never_panics() {
+        fuzz::check_parser("Grid", &[SIMPLE_INPUT, COMPLEX_ENCLOSED], Grid::from_str);
     }
 
     #[test]
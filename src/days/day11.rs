@@ -3,6 +3,7 @@
 use std::fmt::Display;
 use std::str::FromStr;
 
+use crate::framework::bit_grid::BitGrid;
 use crate::framework::grid::{GridShape, IntVector};
 use crate::framework::Day;
 use crate::prelude::*;
@@ -52,41 +53,13 @@ impl Image {
     fn expand(&self, expansion_factor: usize) -> Image {
         assert!(expansion_factor > 0);
         let expand_to = expansion_factor - 1; // the number of new lines to add
-        let rows: Box<[Box<[bool]>]> = (0..self.shape.height)
-            .map(|y| {
-                (0..self.shape.width)
-                    .map(|x| self.galaxy_at(IntVector::new(x as isize, y as isize)))
-                    .collect()
-            })
+        let galaxy_map = BitGrid::from_points(self.shape.clone(), self.galaxies.iter().copied())
+            .expect("galaxies should be inside the image");
+        let expanded_row_indices: Vec<usize> = (0..self.shape.height)
+            .filter(|&y| galaxy_map.count_row(y) == 0)
             .collect();
-        let expanded_row_indices: Vec<usize> = rows
-            .into_iter()
-            .enumerate()
-            .filter_map(|(y, row)| {
-                if row.iter().all(|pixel| !pixel) {
-                    Some(y)
-                } else {
-                    None
-                }
-            })
-            .collect();
-        let columns: Box<[Box<[bool]>]> = (0..self.shape.width)
-            .map(|x| {
-                (0..self.shape.height)
-                    .map(|y| self.galaxy_at(IntVector::new(x as isize, y as isize)))
-                    .collect()
-            })
-            .collect();
-        let expanded_column_indices: Vec<usize> = columns
-            .into_iter()
-            .enumerate()
-            .filter_map(|(x, column)| {
-                if column.iter().all(|pixel| !pixel) {
-                    Some(x)
-                } else {
-                    None
-                }
-            })
+        let expanded_column_indices: Vec<usize> = (0..self.shape.width)
+            .filter(|&x| galaxy_map.count_column(x) == 0)
             .collect();
         let expanded = Image {
             shape: GridShape {
@@ -175,6 +148,7 @@ impl Display for Image {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::framework::fuzz;
 
     #[test]
     fn test_part1() {
@@ -193,20 +167,26 @@ mod test {
     }
 
 
+    const SAMPLE_INPUT: &str = indoc! {"
+        ...#......
+        .......#..
+        #.........
+        ..........
+        ......#...
+        .#........
+        .........#
+        ..........
+        .......#..
+        #...#.....
+    "};
+
     fn sample_input() -> Image {
-        let input = indoc! {"
-            ...#......
-            .......#..
-            #.........
-            ..........
-            ......#...
-            .#........
-            .........#
-            ..........
-            .......#..
-            #...#.....
-        "};
-        Image::from_str(input).unwrap()
+        Image::from_str(SAMPLE_INPUT).unwrap()
+    }
+
+    #[test]
+    fn test_parser_never_panics() {
+        fuzz::check_parser("Image", &[SAMPLE_INPUT], Image::from_str);
     }
 
     #[test]
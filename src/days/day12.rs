@@ -1,10 +1,10 @@
 // Day 12: Hot Springs
 
-use std::collections::HashMap;
 use std::str::FromStr;
 
 use regex::Regex;
 
+use crate::framework::memo::Memo;
 use crate::framework::Day;
 use crate::prelude::*;
 
@@ -67,7 +67,7 @@ impl Record {
             current_damage_sequence_length: usize,
             remaining_damage_sequence: &[Option<bool>],
             remaining_contiguous_damage_sequences: &[u32],
-            cache: &mut HashMap<QueryKey, usize>,
+            cache: &mut Memo<QueryKey, usize>,
         ) -> usize {
             // println!(
             //     "{}{}->{} {}",
@@ -133,7 +133,7 @@ impl Record {
             current_damage_sequence_length: usize,
             remaining_damage_sequence: &[Option<bool>],
             remaining_contiguous_damage_sequences: &[u32],
-            cache: &mut HashMap<QueryKey, usize>,
+            cache: &mut Memo<QueryKey, usize>,
         ) -> usize {
             if remaining_damage_sequence.is_empty() {
                 if remaining_contiguous_damage_sequences.is_empty() {
@@ -186,30 +186,27 @@ impl Record {
             remaining_damage_sequence: Box<[Option<bool>]>,
             remaining_contiguous_damage_sequences: Box<[u32]>,
         }
-        let mut cache: HashMap<QueryKey, usize> = HashMap::new();
+        let mut cache = Memo::new();
 
         fn remaining_valid_arrangements_with_cache(
             current_damage_sequence_length: usize,
             remaining_damage_sequence: &[Option<bool>],
             remaining_contiguous_damage_sequences: &[u32],
-            cache: &mut HashMap<QueryKey, usize>,
+            cache: &mut Memo<QueryKey, usize>,
         ) -> usize {
             let query_key = QueryKey {
                 current_damage_sequence_length,
                 remaining_damage_sequence: remaining_damage_sequence.into(),
                 remaining_contiguous_damage_sequences: remaining_contiguous_damage_sequences.into(),
             };
-            if let Some(result) = cache.get(&query_key) {
-                return *result;
-            }
-            let result = remaining_valid_arrangements(
-                current_damage_sequence_length,
-                remaining_damage_sequence,
-                remaining_contiguous_damage_sequences,
-                cache,
-            );
-            cache.insert(query_key, result);
-            return result;
+            cache.get_or_compute(query_key, |cache| {
+                remaining_valid_arrangements(
+                    current_damage_sequence_length,
+                    remaining_damage_sequence,
+                    remaining_contiguous_damage_sequences,
+                    cache,
+                )
+            })
         }
 
         remaining_valid_arrangements(
@@ -238,12 +235,15 @@ impl Record {
     }
 }
 
+lazy_static! {
+    static ref RECORD_REGEX: Regex = Regex::new("^([.#?]+) ([0-9,]+)$").unwrap();
+}
+
 impl FromStr for Record {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self> {
-        let regex = Regex::new("^([.#?]+) ([0-9,]+)$")?;
-        let captures = regex
+        let captures = RECORD_REGEX
             .captures(s)
             .ok_or_else(|| anyhow!("Invalid record: {}", s))?;
         let damage_sequence = captures
@@ -277,6 +277,7 @@ impl FromStr for Record {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::framework::fuzz;
 
     #[test]
     fn test_part1() {
@@ -409,4 +410,15 @@ mod test {
             506250
         );
     }
+
+    #[test]
+    fn test_parser_never_panics() {
+        let corpus = [
+            "???.### 1,1,3",
+            ".??..??...?##. 1,1,3",
+            "?#?#?#?#?#?#?#? 1,3,1,6",
+            "????.#...#... 4,1,1",
+        ];
+        fuzz::check_parser("Record", &corpus, Record::from_str);
+    }
 }
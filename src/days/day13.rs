@@ -3,15 +3,15 @@
 use std::fmt::Display;
 use std::str::FromStr;
 
-use crate::framework::grid::{GridShape, IntVector};
+use crate::framework::bit_grid::BitGrid;
+use crate::framework::parse;
 use crate::framework::Day;
 use crate::prelude::*;
 
 fn puzzle_input() -> Result<Box<[Pattern]>> {
-    include_str!("./day13_input.txt")
-        .replace("\r\n", "\n")
-        .split("\n\n")
-        .map(|pattern_str| pattern_str.parse())
+    parse::sections(include_str!("./day13_input.txt"))
+        .iter()
+        .map(|section| section.parse())
         .collect()
 }
 
@@ -47,23 +47,28 @@ impl Day for Day13 {
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 struct Pattern {
-    shape: GridShape,
-    rocks: Box<[bool]>,
+    rocks: BitGrid,
+    /// `rocks` transposed, so that columns can be compared a word at a time too
+    columns: BitGrid,
+}
+
+/// Finds a line between two rows where the rows on either side mirror each other, apart from
+/// exactly `expected_errors` cells. Returns the number of rows above the line.
+fn reflection_line(rows: &BitGrid, expected_errors: usize) -> Option<usize> {
+    let height = rows.shape().height;
+    (1..height).find(|&y| {
+        let reflection_size = usize::min(y, height - y);
+        let errors: usize = (0..reflection_size)
+            .map(|i| rows.row_difference(y - 1 - i, y + i))
+            .sum();
+        errors == expected_errors
+    })
 }
 
 impl Pattern {
-    fn row(&self, y: usize) -> &[bool] {
-        let left_coord = self.shape.arr_index(IntVector::new(0, y as isize));
-        let right_coord = self
-            .shape
-            .arr_index(IntVector::new(self.shape.width as isize, y as isize));
-        &self.rocks[left_coord..right_coord]
-    }
-    fn column(&self, x: usize) -> Box<[bool]> {
-        // This could be memoized for a lil performance boost, but it didn't seem necessary
-        (0..(self.shape.height))
-            .map(|y| self.rocks[self.shape.arr_index(IntVector::new(x as isize, y as isize))])
-            .collect()
+    fn new(rocks: BitGrid) -> Self {
+        let columns = rocks.transpose();
+        Pattern { rocks, columns }
     }
 
     fn vertical_reflection(&self) -> Option<usize> {
@@ -71,28 +76,7 @@ impl Pattern {
     }
 
     fn vertical_reflection_expecting_errors(&self, expected_errors: usize) -> Option<usize> {
-        for x in 1..self.shape.width {
-            let reflection_size = usize::min(x, self.shape.width - x);
-            let left_columns: Box<[Box<[bool]>]> = (x - reflection_size..x)
-                .map(|column_x| self.column(column_x))
-                .collect();
-            let right_columns: Box<[Box<[bool]>]> = (x..x + reflection_size)
-                .rev()
-                .map(|column_x| self.column(column_x))
-                .collect();
-
-            let errors = left_columns
-                .iter()
-                .flat_map(|column| column.iter())
-                .zip(right_columns.iter().flat_map(|column| column.iter()))
-                .filter(|(a, b)| a != b)
-                .count();
-
-            if errors == expected_errors {
-                return Some(x);
-            }
-        }
-        None
+        reflection_line(&self.columns, expected_errors)
     }
 
     fn horizontal_reflection(&self) -> Option<usize> {
@@ -100,28 +84,7 @@ impl Pattern {
     }
 
     fn horizontal_reflection_expecting_errors(&self, expected_errors: usize) -> Option<usize> {
-        for y in 1..self.shape.height {
-            let reflection_size = usize::min(y, self.shape.height - y);
-            let top_rows: Box<[&[bool]]> = (y - reflection_size..y)
-                .map(|row_y| self.row(row_y))
-                .collect();
-            let bottom_rows: Box<[&[bool]]> = (y..y + reflection_size)
-                .rev()
-                .map(|row_y| self.row(row_y))
-                .collect();
-
-            let errors = top_rows
-                .iter()
-                .flat_map(|row| row.iter())
-                .zip(bottom_rows.iter().flat_map(|row| row.iter()))
-                .filter(|(a, b)| a != b)
-                .count();
-
-            if errors == expected_errors {
-                return Some(y);
-            }
-        }
-        None
+        reflection_line(&self.rocks, expected_errors)
     }
 
     fn reflection_score(&self) -> usize {
@@ -149,35 +112,25 @@ impl FromStr for Pattern {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self> {
-        let (shape, chars) = GridShape::parse_char_grid(s)?;
-        let rocks = chars
-            .into_iter()
-            .map(|c| match c {
-                '.' => Ok(false),
-                '#' => Ok(true),
-                _ => Err(anyhow!("invalid character in pattern: {:?}", c)),
-            })
-            .collect::<Result<_>>()?;
-
-        Ok(Pattern { shape, rocks })
+        let rocks = BitGrid::parse_char_grid(s, |c| match c {
+            '.' => Ok(false),
+            '#' => Ok(true),
+            _ => Err(anyhow!("invalid character in pattern: {:?}", c)),
+        })?;
+        Ok(Pattern::new(rocks))
     }
 }
 
 impl Display for Pattern {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let grid = self
-            .shape
-            .format_char_grid(self.rocks.iter().map(|bool| match bool {
-                true => '#',
-                false => '.',
-            }));
-        f.write_str(&grid)
+        Display::fmt(&self.rocks, f)
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::framework::fuzz;
 
     #[test]
     fn test_part1() {
@@ -189,30 +142,41 @@ mod test {
         assert_eq!(super::Day13.part2().unwrap().unwrap(), "31603".to_string());
     }
 
+    const SAMPLE_WITH_VERTICAL: &str = indoc! {"
+        #.##..##.
+        ..#.##.#.
+        ##......#
+        ##......#
+        ..#.##.#.
+        ..##..##.
+        #.#.##.#.
+    "};
+
     fn sample_with_vertical() -> Pattern {
-        Pattern::from_str(indoc! {"
-            #.##..##.
-            ..#.##.#.
-            ##......#
-            ##......#
-            ..#.##.#.
-            ..##..##.
-            #.#.##.#.
-        "})
-        .unwrap()
+        Pattern::from_str(SAMPLE_WITH_VERTICAL).unwrap()
     }
 
+    const SAMPLE_WITH_HORIZONTAL: &str = indoc! {"
+        #...##..#
+        #....#..#
+        ..##..###
+        #####.##.
+        #####.##.
+        ..##..###
+        #....#..#
+    "};
+
     fn sample_with_horizontal() -> Pattern {
-        Pattern::from_str(indoc! {"
-            #...##..#
-            #....#..#
-            ..##..###
-            #####.##.
-            #####.##.
-            ..##..###
-            #....#..#
-        "})
-        .unwrap()
+        Pattern::from_str(SAMPLE_WITH_HORIZONTAL).unwrap()
+    }
+
+    #[test]
+    fn test_parser_never_panics() {
+        fuzz::check_parser(
+            "Pattern",
+            &[SAMPLE_WITH_VERTICAL, SAMPLE_WITH_HORIZONTAL],
+            Pattern::from_str,
+        );
     }
 
     #[test]
@@ -1,11 +1,9 @@
 // Day 14: Parabolic Reflector Dish
 
-use std::collections::hash_map::DefaultHasher;
-use std::collections::HashMap;
 use std::fmt::Display;
-use std::hash::{Hash, Hasher};
 use std::str::FromStr;
 
+use crate::framework::cycle;
 use crate::framework::grid::{GridShape, IntVector, EAST, NORTH, SOUTH, WEST};
 use crate::framework::Day;
 use crate::prelude::*;
@@ -149,29 +147,15 @@ impl Platform {
     }
 
     fn spin_cycle_repeat(&mut self, times: usize) -> Result<()> {
-        let mut sequence_so_far = Vec::<Box<[Option<Rock>]>>::new();
-        let mut seen_states = HashMap::<u64, usize>::new();
-        let mut sequence: Option<(usize, usize)> = None;
-        for i in 0..times {
-            let state_hash = {
-                let mut hasher = DefaultHasher::new();
-                self.rocks.hash(&mut hasher);
-                hasher.finish()
+        let shape = self.shape.clone();
+        self.rocks = cycle::state_after(self.rocks.clone(), times, |rocks| {
+            let mut platform = Platform {
+                shape: shape.clone(),
+                rocks: rocks.clone(),
             };
-            if let Some(seen_state) = seen_states.get(&state_hash) {
-                sequence = Some((*seen_state, i));
-                break;
-            }
-            seen_states.insert(state_hash, i);
-            sequence_so_far.push(self.rocks.clone());
-            self.spin_cycle()?;
-        }
-        if let Some((sequence_start, sequence_end)) = sequence {
-            let looping_sequence = &sequence_so_far[sequence_start..sequence_end];
-            let cycles_left = times - sequence_end;
-            let final_state = &looping_sequence[cycles_left % looping_sequence.len()];
-            self.rocks = final_state.clone();
-        }
+            platform.spin_cycle()?;
+            Ok(platform.rocks)
+        })?;
         Ok(())
     }
 }
@@ -201,6 +185,7 @@ impl Display for Platform {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::framework::fuzz;
 
     #[test]
     fn test_part1() {
@@ -212,21 +197,26 @@ mod test {
         assert_eq!(super::Day14.part2().unwrap().unwrap(), "96317".to_string());
     }
 
+    const SAMPLE_INPUT: &str = indoc! {"
+        O....#....
+        O.OO#....#
+        .....##...
+        OO.#O....O
+        .O.....O#.
+        O.#..O.#.#
+        ..O..#O..O
+        .......O..
+        #....###..
+        #OO..#....
+    "};
+
     fn sample_input() -> Platform {
-        indoc! {"
-            O....#....
-            O.OO#....#
-            .....##...
-            OO.#O....O
-            .O.....O#.
-            O.#..O.#.#
-            ..O..#O..O
-            .......O..
-            #....###..
-            #OO..#....
-      "}
-        .parse()
-        .unwrap()
+        SAMPLE_INPUT.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parser_never_panics() {
+        fuzz::check_parser("Platform", &[SAMPLE_INPUT], Platform::from_str);
     }
 
     #[test]
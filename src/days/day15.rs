@@ -12,7 +12,11 @@ use crate::prelude::*;
 pub struct Day15;
 
 fn puzzle_input() -> Result<Vec<InitializationStep>> {
-    include_str!("./day15_input.txt")
+    parse_steps(include_str!("./day15_input.txt"))
+}
+
+fn parse_steps(input: &str) -> Result<Vec<InitializationStep>> {
+    input
         .replace("\r", "")
         .replace("\n", "")
         .split(",")
@@ -224,6 +228,7 @@ impl Display for Boxen {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::framework::fuzz;
 
     #[test]
     fn test_part1() {
@@ -240,9 +245,15 @@ mod test {
         assert_eq!("HASH".holiday_hash(), 52);
     }
 
+    const SAMPLE_INPUT: &str = "rn=1,cm-,qp=3,cm=2,qp-,pc=4,ot=9,ab=5,pc-,pc=6,ot=7";
+
     fn sample_input() -> Vec<InitializationStep> {
-        let input = "rn=1,cm-,qp=3,cm=2,qp-,pc=4,ot=9,ab=5,pc-,pc=6,ot=7";
-        input.split(",").map(|step| step.parse().unwrap()).collect()
+        parse_steps(SAMPLE_INPUT).unwrap()
+    }
+
+    #[test]
+    fn test_parser_never_panics() {
+        fuzz::check_parser("steps", &[SAMPLE_INPUT], parse_steps);
     }
 
     #[test]
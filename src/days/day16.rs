@@ -96,6 +96,7 @@ impl Contraption {
                             Direction::East => Direction::North,
                             Direction::South => Direction::West,
                             Direction::West => Direction::South,
+                            diagonal => unreachable!("beams can't travel {:?}", diagonal),
                         };
                         next_beams.push(Beam {
                             position: beam.position + new_direction.into(),
@@ -108,6 +109,7 @@ impl Contraption {
                             Direction::East => Direction::South,
                             Direction::South => Direction::East,
                             Direction::West => Direction::North,
+                            diagonal => unreachable!("beams can't travel {:?}", diagonal),
                         };
                         next_beams.push(Beam {
                             position: beam.position + new_direction.into(),
@@ -208,6 +210,7 @@ impl FromStr for Contraption {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::framework::fuzz;
 
     #[test]
     fn test_part1() {
@@ -219,20 +222,26 @@ mod test {
         assert_eq!(super::Day16.part2().unwrap().unwrap(), "8061".to_string(),);
     }
 
+    const SAMPLE_INPUT: &str = indoc! {r"
+        .|...\....
+        |.-.\.....
+        .....|-...
+        ........|.
+        ..........
+        .........\
+        ..../.\\..
+        .-.-/..|..
+        .|....-|.\
+        ..//.|....
+    "};
+
     fn sample_input() -> Contraption {
-        let input = indoc! {r"
-            .|...\....
-            |.-.\.....
-            .....|-...
-            ........|.
-            ..........
-            .........\
-            ..../.\\..
-            .-.-/..|..
-            .|....-|.\
-            ..//.|....
-        "};
-        input.parse().unwrap()
+        SAMPLE_INPUT.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parser_never_panics() {
+        fuzz::check_parser("Contraption", &[SAMPLE_INPUT], Contraption::from_str);
     }
 
     #[test]
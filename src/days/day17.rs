@@ -8,8 +8,8 @@ use crate::framework::grid::{Direction, GridShape, IntVector};
 use crate::framework::Day;
 use crate::prelude::*;
 
-fn puzzle_input() -> CityMap {
-    include_str!("./day17_input.txt").parse().unwrap()
+fn puzzle_input() -> Result<CityMap> {
+    include_str!("./day17_input.txt").parse()
 }
 
 pub struct Day17;
@@ -21,7 +21,7 @@ impl Day for Day17 {
 
     fn part1(&self) -> Option<Result<String>> {
         Some(try_block(move || {
-            puzzle_input()
+            puzzle_input()?
                 .find_minimal_heat_loss(SimpleCrucible)
                 .ok_or(anyhow!("No path found"))?
                 .to_string()
@@ -31,7 +31,7 @@ impl Day for Day17 {
 
     fn part2(&self) -> Option<Result<String>> {
         Some(try_block(move || {
-            puzzle_input()
+            puzzle_input()?
                 .find_minimal_heat_loss(UltraCrucible)
                 .ok_or(anyhow!("No path found"))?
                 .to_string()
@@ -129,6 +129,7 @@ impl Crucible for UltraCrucible {
     }
 }
 
+#[derive(Debug)]
 struct CityMap {
     shape: GridShape,
     heat_loss_for_blocks: Box<[u8]>,
@@ -299,6 +300,7 @@ impl CityMap {
                             Direction::East => '>',
                             Direction::South => 'v',
                             Direction::West => '<',
+                            _ => '*',
                         }
                     } else {
                         '.'
@@ -338,6 +340,7 @@ impl FromStr for CityMap {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::framework::fuzz;
 
     #[test]
     fn test_part1() {
@@ -349,24 +352,29 @@ mod test {
         assert_eq!(super::Day17.part2().unwrap().unwrap(), "1362".to_string(),);
     }
 
+    const SAMPLE_INPUT: &str = indoc! {"
+        2413432311323
+        3215453535623
+        3255245654254
+        3446585845452
+        4546657867536
+        1438598798454
+        4457876987766
+        3637877979653
+        4654967986887
+        4564679986453
+        1224686865563
+        2546548887735
+        4322674655533
+    "};
+
     fn sample_input() -> CityMap {
-        indoc! {"
-            2413432311323
-            3215453535623
-            3255245654254
-            3446585845452
-            4546657867536
-            1438598798454
-            4457876987766
-            3637877979653
-            4654967986887
-            4564679986453
-            1224686865563
-            2546548887735
-            4322674655533
-        "}
-        .parse()
-        .unwrap()
+        SAMPLE_INPUT.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parser_never_panics() {
+        fuzz::check_parser("CityMap", &[SAMPLE_INPUT], CityMap::from_str);
     }
 
     #[test]
@@ -1,11 +1,12 @@
 // Day 18: Lavaduct Lagoon
 
-use std::collections::{HashSet, VecDeque};
+use std::collections::VecDeque;
 use std::fmt::Display;
-use std::ops::Range;
 use std::str::FromStr;
 
-use crate::framework::grid::{Direction, GridShape, IntVector, EAST, NORTH, SOUTH, WEST};
+use crate::framework::bit_grid::BitGrid;
+use crate::framework::compression::CoordinateCompression;
+use crate::framework::grid::{Direction, IntVector, EAST, NORTH, SOUTH, WEST};
 use crate::framework::Day;
 use crate::prelude::*;
 
@@ -23,7 +24,7 @@ impl Day for Day18 {
 
     fn part1(&self) -> Option<Result<String>> {
         Some(try_block(move || {
-            let mut dig_site = DigSite::from_instructions(&puzzle_input()?);
+            let mut dig_site = DigSite::from_instructions(&puzzle_input()?)?;
             let result = dig_site.dig_interior();
             result?;
             Ok(format!("{}", dig_site.capacity()))
@@ -49,6 +50,9 @@ struct DigInstruction {
 
 impl DigInstruction {
     fn repair_from_hex_code(hex_code: &str) -> Result<DigInstruction> {
+        if hex_code.chars().count() != 6 {
+            return Err(anyhow!("Invalid hex code: {}", hex_code));
+        }
         let distance_hex: String = hex_code.chars().take(5).collect();
         let direction_hex: char = hex_code.chars().nth(5).unwrap();
         let distance = usize::from_str_radix(&distance_hex, 16)?;
@@ -100,10 +104,8 @@ fn parse_instructions(input: &str) -> Result<Vec<DigInstruction>> {
 }
 
 struct DigSite {
-    rows: Vec<Range<isize>>,
-    columns: Vec<Range<isize>>,
-    compressed_shape: GridShape,
-    compressed_map: Box<[bool]>,
+    compression: CoordinateCompression,
+    compressed_map: BitGrid,
     compressed_interior_point: IntVector,
 }
 
@@ -118,10 +120,10 @@ impl DigSite {
                     .pipe(|hex_code| DigInstruction::repair_from_hex_code(hex_code))
             })
             .collect::<Result<Vec<_>>>()?;
-        Ok(Self::from_instructions(&repaired_instructions))
+        Self::from_instructions(&repaired_instructions)
     }
 
-    fn from_instructions(instructions: &[DigInstruction]) -> Self {
+    fn from_instructions(instructions: &[DigInstruction]) -> Result<Self> {
         let mut dig_lines = Vec::<DigLine>::new();
 
         let mut current_position = IntVector::new(0, 0);
@@ -136,140 +138,45 @@ impl DigSite {
             current_position += delta * instruction.distance as isize;
         }
 
-        fn coordinate_ranges(
-            coordinates_on_axis: impl IntoIterator<Item = isize>,
-        ) -> Vec<Range<isize>> {
-            let coordinates_in_order = coordinates_on_axis
-                .into_iter()
-                .collect::<HashSet<_>>()
-                .into_iter()
-                .collect_vec()
-                .pipe(|mut coords| {
-                    coords.sort_unstable();
-                    coords
-                });
-
-            let mut ranges = Vec::<Range<isize>>::new();
-            let mut prev_coord = coordinates_in_order[0];
-            ranges.push(prev_coord..prev_coord + 1);
-            for coord in coordinates_in_order.into_iter().skip(1) {
-                if coord - prev_coord > 1 {
-                    // gap
-                    ranges.push(prev_coord + 1..coord);
-                }
-                ranges.push(coord..coord + 1);
-                prev_coord = coord;
-            }
-            ranges
-        }
-
-        let columns = coordinate_ranges(dig_lines.iter().map(|it| it.start.x));
-        let rows = coordinate_ranges(dig_lines.iter().map(|it| it.start.y));
-
-        let compressed_shape = GridShape {
-            width: columns.len(),
-            height: rows.len(),
-        };
-        let mut compressed_map = vec![false; compressed_shape.area()].into_boxed_slice();
-
-        let mut current_position_uncompressed = IntVector::new(0, 0);
-        for instruction in instructions {
-            let current_position_compressed =
-                get_compressed_position(current_position_uncompressed, &rows, &columns);
-            let end_uncompressed = current_position_uncompressed
-                + instruction.direction.as_vector() * instruction.distance as isize;
-            let compressed_coords = match instruction.direction {
-                Direction::North => {
-                    let y_coords = rows
-                        .iter()
-                        .enumerate()
-                        .rev()
-                        .skip_while(|(_, range)| !range.contains(&current_position_uncompressed.y))
-                        .take_while(|(_, range)| range.start > end_uncompressed.y)
-                        .map(|it| IntVector::new(current_position_compressed.x, it.0 as isize))
-                        .collect_vec();
-                    y_coords
-                }
-                Direction::South => {
-                    let y_coords = rows
-                        .iter()
-                        .enumerate()
-                        .skip_while(|(_, range)| !range.contains(&current_position_uncompressed.y))
-                        .take_while(|(_, range)| range.end <= end_uncompressed.y)
-                        .map(|it| IntVector::new(current_position_compressed.x, it.0 as isize))
-                        .collect_vec();
-                    y_coords
-                }
-                Direction::East => {
-                    let x_coords = columns
-                        .iter()
-                        .enumerate()
-                        .skip_while(|(_, range)| !range.contains(&current_position_uncompressed.x))
-                        .take_while(|(_, range)| range.end <= end_uncompressed.x)
-                        .map(|it| IntVector::new(it.0 as isize, current_position_compressed.y))
-                        .collect_vec();
-                    x_coords
-                }
-                Direction::West => {
-                    let x_coords = columns
-                        .iter()
-                        .enumerate()
-                        .rev()
-                        .skip_while(|(_, range)| !range.contains(&current_position_uncompressed.x))
-                        .take_while(|(_, range)| range.start > end_uncompressed.x)
-                        .map(|it| IntVector::new(it.0 as isize, current_position_compressed.y))
-                        .collect_vec();
-                    x_coords
-                }
-            };
+        let compression = CoordinateCompression::from_points(dig_lines.iter().map(|it| it.start));
+        let mut compressed_map = BitGrid::new(compression.shape());
 
-            for coord in compressed_coords {
-                compressed_map[compressed_shape.arr_index(coord)] = true;
+        for dig_line in dig_lines.iter() {
+            let end = dig_line.start + dig_line.direction.as_vector() * dig_line.length as isize;
+            for coord in compression.compress_segment(dig_line.start, end)? {
+                compressed_map.set(coord, true);
             }
-            current_position_uncompressed = end_uncompressed;
         }
 
         // lil hack to get the interior point
         // we can assume the dig lines run clockwise and the corners don't get too close to each other
-        let dig_line = dig_lines[0];
+        let dig_line = dig_lines.first().ok_or(anyhow!("No dig lines"))?;
         let interior_point = dig_line.start
             + match dig_line.direction {
                 Direction::North => NORTH + EAST,
                 Direction::South => SOUTH + WEST,
                 Direction::East => EAST + SOUTH,
                 Direction::West => WEST + NORTH,
+                diagonal => unreachable!("can't dig {:?}", diagonal),
             };
-        let compressed_interior_point = get_compressed_position(interior_point, &rows, &columns);
-        if compressed_map[compressed_shape.arr_index(compressed_interior_point)] {
-            panic!("Interior point is a wall");
+        let compressed_interior_point = compression
+            .compress_point(interior_point)
+            .ok_or_else(|| anyhow!("Interior point {:?} is out of bounds", interior_point))?;
+        if compressed_map.is_set(compressed_interior_point) {
+            return Err(anyhow!("Interior point is a wall"));
         }
 
-        Self {
-            rows,
-            columns,
-            compressed_shape,
+        Ok(Self {
+            compression,
             compressed_map,
             compressed_interior_point,
-        }
-    }
-
-    fn get_compressed(&self, coord: IntVector) -> bool {
-        let index = self.compressed_shape.arr_index(coord);
-        self.compressed_map[index]
+        })
     }
 
     fn capacity(&self) -> usize {
         self.compressed_map
-            .iter()
-            .copied()
-            .enumerate()
-            .filter(|(_, it)| *it)
-            .map(|(compressed_index, _)| {
-                let compressed_coord = self.compressed_shape.coordinate_for_index(compressed_index);
-                let x_range = self.columns[compressed_coord.x as usize].clone();
-                let y_range = self.rows[compressed_coord.y as usize].clone();
-                x_range.len() * y_range.len() as usize
-            })
+            .iter_ones()
+            .map(|compressed_coord| self.compression.cell_area(compressed_coord))
             .sum()
     }
 
@@ -277,14 +184,14 @@ impl DigSite {
         let mut queue = VecDeque::<IntVector>::new();
         queue.push_back(self.compressed_interior_point);
         while let Some(coord) = queue.pop_front() {
-            if self.get_compressed(coord) {
+            if self.compressed_map.is_set(coord) {
                 continue;
             }
 
-            self.compressed_map[self.compressed_shape.arr_index(coord)] = true;
+            self.compressed_map.set(coord, true);
             let neighbors = coord.cardinal_neighbors();
             for neighbor in neighbors {
-                if self.compressed_shape.in_bounds(neighbor) {
+                if self.compressed_map.shape().in_bounds(neighbor) {
                     queue.push_back(neighbor);
                 }
             }
@@ -293,37 +200,9 @@ impl DigSite {
     }
 }
 
-fn get_compressed_position(
-    position: IntVector,
-    rows: &[Range<isize>],
-    columns: &[Range<isize>],
-) -> IntVector {
-    let x = columns
-        .iter()
-        .enumerate()
-        .find(|(_, range)| range.contains(&position.x))
-        .map(|it| it.0 as isize)
-        .expect(format!("No column found for {:?}", position).as_str());
-    let y = rows
-        .iter()
-        .enumerate()
-        .find(|(_, range)| range.contains(&position.y))
-        .map(|it| it.0 as isize)
-        .expect(format!("No column found for {:?}", position).as_str());
-    IntVector::new(x, y)
-}
-
 impl Display for DigSite {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_str(&self.compressed_shape.format_char_grid(
-            self.compressed_shape.coord_iter().map(|coord| {
-                if self.get_compressed(coord) {
-                    '#'
-                } else {
-                    '.'
-                }
-            }),
-        ))
+        Display::fmt(&self.compressed_map, f)
     }
 }
 
@@ -337,6 +216,8 @@ struct DigLine {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::framework::fuzz;
+    use crate::framework::polygon::Polygon;
 
     #[test]
     fn test_part1() {
@@ -351,25 +232,37 @@ mod test {
         );
     }
 
+    const SAMPLE_INPUT: &str = indoc! {"
+        R 6 (#70c710)
+        D 5 (#0dc571)
+        L 2 (#5713f0)
+        D 2 (#d2c081)
+        R 2 (#59c680)
+        D 2 (#411b91)
+        L 5 (#8ceee2)
+        U 2 (#caa173)
+        L 1 (#1b58a2)
+        U 2 (#caa171)
+        R 2 (#7807d2)
+        U 3 (#a77fa3)
+        L 2 (#015232)
+        U 2 (#7a21e3)
+    "};
+
     fn sample_input() -> Vec<DigInstruction> {
-        indoc! {"
-          R 6 (#70c710)
-          D 5 (#0dc571)
-          L 2 (#5713f0)
-          D 2 (#d2c081)
-          R 2 (#59c680)
-          D 2 (#411b91)
-          L 5 (#8ceee2)
-          U 2 (#caa173)
-          L 1 (#1b58a2)
-          U 2 (#caa171)
-          R 2 (#7807d2)
-          U 3 (#a77fa3)
-          L 2 (#015232)
-          U 2 (#7a21e3)
-      "}
-        .pipe(parse_instructions)
-        .unwrap()
+        parse_instructions(SAMPLE_INPUT).unwrap()
+    }
+
+    #[test]
+    fn test_parser_never_panics() {
+        fuzz::check_parser("DigInstruction", &[SAMPLE_INPUT], |input| {
+            let instructions = parse_instructions(input)?;
+            instructions
+                .iter()
+                .filter_map(|it| it.hex_color.as_deref())
+                .map(DigInstruction::repair_from_hex_code)
+                .collect::<Result<Vec<_>>>()
+        });
     }
 
     #[test]
@@ -385,17 +278,30 @@ mod test {
 
     #[test]
     fn test_dig_sides() {
-        let dig_site = DigSite::from_instructions(&sample_input());
+        let dig_site = DigSite::from_instructions(&sample_input()).unwrap();
         assert_eq!(dig_site.capacity(), 38);
     }
 
     #[test]
     fn test_dig_interior() {
-        let mut dig_site = DigSite::from_instructions(&sample_input());
+        let mut dig_site = DigSite::from_instructions(&sample_input()).unwrap();
         dig_site.dig_interior().unwrap();
         assert_eq!(dig_site.capacity(), 62);
     }
 
+    #[test]
+    fn test_polygon_matches_dig_site() {
+        let polygon = Polygon::from_steps(
+            sample_input()
+                .iter()
+                .map(|instruction| (instruction.direction, instruction.distance)),
+        )
+        .unwrap();
+        assert!(polygon.is_simple());
+        assert_eq!(polygon.boundary_length().unwrap(), 38);
+        assert_eq!(polygon.enclosed_points().unwrap(), 62);
+    }
+
     #[test]
     fn test_repair_from_hex_code() {
         assert_eq!(
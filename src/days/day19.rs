@@ -2,11 +2,12 @@
 
 use std::cmp::Ordering;
 use std::collections::HashMap;
-use std::ops::RangeInclusive;
 use std::str::FromStr;
 
 use regex::Regex;
 
+use crate::framework::interval::{Interval, IntervalBox};
+use crate::framework::parse;
 use crate::framework::Day;
 use crate::prelude::*;
 
@@ -47,6 +48,15 @@ enum RatingCategory {
 }
 
 impl RatingCategory {
+    fn axis(self) -> usize {
+        match self {
+            Self::X => 0,
+            Self::M => 1,
+            Self::A => 2,
+            Self::S => 3,
+        }
+    }
+
     fn from_char(c: char) -> Result<Self> {
         match c {
             'x' => Ok(Self::X),
@@ -101,48 +111,21 @@ impl FromStr for Part {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
-struct PartRange {
-    x: RangeInclusive<u16>,
-    m: RangeInclusive<u16>,
-    a: RangeInclusive<u16>,
-    s: RangeInclusive<u16>,
-}
+struct PartRange(IntervalBox<u16, 4>);
 
 impl PartRange {
     fn new() -> Self {
-        Self {
-            x: 1..=4000,
-            m: 1..=4000,
-            a: 1..=4000,
-            s: 1..=4000,
-        }
-    }
-
-    fn get(&self, category: RatingCategory) -> &RangeInclusive<u16> {
-        match category {
-            RatingCategory::X => &self.x,
-            RatingCategory::M => &self.m,
-            RatingCategory::A => &self.a,
-            RatingCategory::S => &self.s,
-        }
+        Self(IntervalBox::cube(Interval::new(1, 4001)))
     }
 
-    fn with_set(&self, category: RatingCategory, range: RangeInclusive<u16>) -> Self {
-        let mut new = self.clone();
-        match category {
-            RatingCategory::X => new.x = range,
-            RatingCategory::M => new.m = range,
-            RatingCategory::A => new.a = range,
-            RatingCategory::S => new.s = range,
-        }
-        new
+    fn combinations(&self) -> u64 {
+        self.0.volume() as u64
     }
 
-    fn combinations(&self) -> u64 {
-        (self.x.end() - self.x.start() + 1) as u64
-            * (self.m.end() - self.m.start() + 1) as u64
-            * (self.a.end() - self.a.start() + 1) as u64
-            * (self.s.end() - self.s.start() + 1) as u64
+    /// Splits into the parts with a rating below `value`, and the parts at or above it.
+    fn split_at(&self, category: RatingCategory, value: u16) -> (Self, Self) {
+        let (below, above) = self.0.split_at(category.axis(), value);
+        (Self(below), Self(above))
     }
 }
 
@@ -186,44 +169,25 @@ impl Rule {
     fn split_on_rule(&self, range: &PartRange) -> Result<RuleSplitResult> {
         use RuleSplitResult::*;
 
-        let relevant_range = range.get(self.category);
-        let result = match self.comparison {
-            Ordering::Less => {
-                if *relevant_range.start() > self.value {
-                    NoMatch
-                } else if *relevant_range.end() < self.value {
-                    AllMatch(self.outcome.clone())
-                } else {
-                    let matched =
-                        range.with_set(self.category, *relevant_range.start()..=(self.value - 1));
-                    let unmatched =
-                        range.with_set(self.category, (self.value)..=*relevant_range.end());
-                    SomeMatch {
-                        matched,
-                        unmatched,
-                        outcome: self.outcome.clone(),
-                    }
-                }
-            }
+        let (matched, unmatched) = match self.comparison {
+            Ordering::Less => range.split_at(self.category, self.value),
             Ordering::Greater => {
-                if *relevant_range.end() < self.value {
-                    NoMatch
-                } else if *relevant_range.start() > self.value {
-                    AllMatch(self.outcome.clone())
-                } else {
-                    let matched =
-                        range.with_set(self.category, (self.value + 1)..=*relevant_range.end());
-                    let unmatched =
-                        range.with_set(self.category, *relevant_range.start()..=(self.value));
-                    SomeMatch {
-                        matched,
-                        unmatched,
-                        outcome: self.outcome.clone(),
-                    }
-                }
+                let (unmatched, matched) = range.split_at(self.category, self.value + 1);
+                (matched, unmatched)
             }
             Ordering::Equal => return Err(anyhow!("'=' rules not supported for range operations")),
         };
+        let result = if matched.0.is_empty() {
+            NoMatch
+        } else if unmatched.0.is_empty() {
+            AllMatch(self.outcome.clone())
+        } else {
+            SomeMatch {
+                matched,
+                unmatched,
+                outcome: self.outcome.clone(),
+            }
+        };
         Ok(result)
     }
 }
@@ -312,12 +276,24 @@ impl FromStr for Workflow {
     }
 }
 
-#[derive(Clone)]
+#[derive(Debug, Clone)]
 struct WorkflowSeries {
     workflows: HashMap<String, Workflow>,
 }
 
 impl WorkflowSeries {
+    /// Fails if two workflows have the same name.
+    fn from_workflows(workflows: Vec<Workflow>) -> Result<Self> {
+        let mut by_name = HashMap::with_capacity(workflows.len());
+        for workflow in workflows {
+            if by_name.contains_key(&workflow.name) {
+                return Err(anyhow!("Duplicate workflow: {}", workflow.name));
+            }
+            by_name.insert(workflow.name.clone(), workflow);
+        }
+        Ok(Self { workflows: by_name })
+    }
+
     fn follow(&self, part: &Part) -> Result<Outcome> {
         let mut outcome = Outcome::Workflow("in".to_string());
         while let Outcome::Workflow(workflow_name) = outcome {
@@ -381,17 +357,12 @@ impl FromStr for WorkflowSeries {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self> {
-        let workflows = s
-            .lines()
-            .map(|line| Workflow::from_str(line))
-            .map_ok(|workflow| (workflow.name.clone(), workflow))
-            .collect::<Result<_>>()?;
-
-        Ok(Self { workflows })
+        let workflows = s.lines().map(Workflow::from_str).collect::<Result<_>>()?;
+        Self::from_workflows(workflows)
     }
 }
 
-#[derive(Clone)]
+#[derive(Debug, Clone)]
 struct Input {
     workflows: WorkflowSeries,
     parts: Vec<Part>,
@@ -422,12 +393,13 @@ impl FromStr for Input {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self> {
-        let (workflows, parts) = s.split_once("\n\n").unwrap();
-        let workflows = workflows.parse()?;
-        let parts = parts
-            .lines()
-            .map(|line| line.parse())
-            .collect::<Result<_>>()?;
+        let [workflows, parts] = parse::sections(s)[..] else {
+            return Err(anyhow!(
+                "Expected workflows and parts, separated by a blank line"
+            ));
+        };
+        let workflows = WorkflowSeries::from_workflows(workflows.lines(Workflow::from_str)?)?;
+        let parts = parts.lines(Part::from_str)?;
 
         Ok(Self { workflows, parts })
     }
@@ -436,6 +408,8 @@ impl FromStr for Input {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::framework::fuzz;
+    use crate::framework::property::{self, ensure_eq, Rng};
 
     #[test]
     fn test_part1() {
@@ -450,28 +424,35 @@ mod test {
         );
     }
 
+    const SAMPLE_INPUT: &str = indoc! {"
+        px{a<2006:qkq,m>2090:A,rfg}
+        pv{a>1716:R,A}
+        lnx{m>1548:A,A}
+        rfg{s<537:gd,x>2440:R,A}
+        qs{s>3448:A,lnx}
+        qkq{x<1416:A,crn}
+        crn{x>2662:A,R}
+        in{s<1351:px,qqz}
+        qqz{s>2770:qs,m<1801:hdj,R}
+        gd{a>3333:R,R}
+        hdj{m>838:A,pv}
+
+        {x=787,m=2655,a=1222,s=2876}
+        {x=1679,m=44,a=2067,s=496}
+        {x=2036,m=264,a=79,s=2244}
+        {x=2461,m=1339,a=466,s=291}
+        {x=2127,m=1623,a=2188,s=1013}
+    "};
+
     fn sample_input() -> Input {
-        indoc! {"
-            px{a<2006:qkq,m>2090:A,rfg}
-            pv{a>1716:R,A}
-            lnx{m>1548:A,A}
-            rfg{s<537:gd,x>2440:R,A}
-            qs{s>3448:A,lnx}
-            qkq{x<1416:A,crn}
-            crn{x>2662:A,R}
-            in{s<1351:px,qqz}
-            qqz{s>2770:qs,m<1801:hdj,R}
-            gd{a>3333:R,R}
-            hdj{m>838:A,pv}
-
-            {x=787,m=2655,a=1222,s=2876}
-            {x=1679,m=44,a=2067,s=496}
-            {x=2036,m=264,a=79,s=2244}
-            {x=2461,m=1339,a=466,s=291}
-            {x=2127,m=1623,a=2188,s=1013}
-        "}
-        .parse()
-        .unwrap()
+        SAMPLE_INPUT.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parser_never_panics() {
+        fuzz::check_parser("Input", &[SAMPLE_INPUT], Input::from_str);
+        let (workflows, _) = SAMPLE_INPUT.split_once("\n\n").unwrap();
+        fuzz::check_parser("WorkflowSeries", &[workflows], WorkflowSeries::from_str);
     }
 
     #[test]
@@ -481,6 +462,13 @@ mod test {
         assert_eq!(input.workflows.workflows.len(), 11);
     }
 
+    #[test]
+    fn test_duplicate_workflows() {
+        let duplicated = "in{x>10:A,R}\nin{m<20:R,A}";
+        assert!(WorkflowSeries::from_str(duplicated).is_err());
+        assert!(Input::from_str(&format!("{duplicated}\n\n{{x=1,m=2,a=3,s=4}}")).is_err());
+    }
+
     #[test]
     fn test_workflow() {
         let workflow = Workflow::from_str("ex{x>10:one,m<20:two,a>30:R,A}").unwrap();
@@ -561,4 +549,67 @@ mod test {
             167409079868000
         );
     }
+
+    /// Workflows only send parts to workflows later in the list, so there are no loops. Ratings
+    /// go up to the returned maximum, rather than 4000, so every part can be checked.
+    fn random_workflows(rng: &mut Rng, size: usize) -> (WorkflowSeries, u16) {
+        let max_rating = rng.gen_range(1..=1 + size.min(5) as u16);
+        let names = std::iter::once("in".to_string())
+            .chain(
+                (0..rng.gen_range(0..=size.min(5)))
+                    .map(|i| format!("w{}", char::from(b'a' + i as u8))),
+            )
+            .collect_vec();
+        let random_outcome = |rng: &mut Rng, i: usize| {
+            let choice = rng.gen_range(0..=names.len() - i);
+            match choice {
+                0 => "A".to_string(),
+                1 => "R".to_string(),
+                _ => names[i + choice - 1].clone(),
+            }
+        };
+        let workflows = names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| {
+                let rules = (0..rng.gen_range(1..=3))
+                    .map(|_| {
+                        format!(
+                            "{}{}{}:{}",
+                            rng.choose(&['x', 'm', 'a', 's']),
+                            rng.choose(&['<', '>']),
+                            rng.gen_range(0..=max_rating + 1),
+                            random_outcome(rng, i)
+                        )
+                    })
+                    .join(",");
+                format!("{}{{{},{}}}", name, rules, random_outcome(rng, i))
+            })
+            .join("\n");
+        (workflows.parse().unwrap(), max_rating)
+    }
+
+    #[test]
+    fn test_range_and_part_workflows_agree() {
+        property::check(
+            "range and part workflows agree",
+            random_workflows,
+            |(workflows, max_rating)| {
+                let ratings = || 1..=*max_rating;
+                let mut accepted_parts = 0;
+                for (x, m, a, s) in itertools::iproduct!(ratings(), ratings(), ratings(), ratings())
+                {
+                    if workflows.follow(&Part { x, m, a, s })? == Outcome::Accept {
+                        accepted_parts += 1;
+                    }
+                }
+                let range = PartRange(IntervalBox::cube(Interval::new(1, max_rating + 1)));
+                ensure_eq(
+                    workflows.accepted_combinations(range, "in")?,
+                    accepted_parts,
+                    "accepted combinations",
+                )
+            },
+        );
+    }
 }
@@ -1,9 +1,10 @@
 // Day 20: Pulse Propagation
 
 use std::collections::{HashMap, VecDeque};
-use std::fmt::Write;
 use std::str::FromStr;
 
+use crate::framework::graph::{Graph, NodeShape, NodeStyle};
+use crate::framework::parse;
 use crate::framework::Day;
 use crate::prelude::*;
 
@@ -47,6 +48,11 @@ impl Day for Day20 {
             println!("{}", configuration.as_mermaid_diagram());
             return Ok(true);
         }
+        if name == "dot_diagram" {
+            let configuration = puzzle_input()?;
+            println!("{}", configuration.as_dot_diagram());
+            return Ok(true);
+        }
 
         return Ok(false);
     }
@@ -154,9 +160,7 @@ impl FromStr for ModuleDefinition {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self> {
-        let (name, destinations) = s
-            .split_once(" -> ")
-            .ok_or(anyhow!("Invalid module: {}", s))?;
+        let (name, destinations) = parse::edges(s)?;
         let first_char = name.chars().next();
         let module_type = match first_char {
             Some('%') => ModuleType::FlipFlop,
@@ -169,7 +173,7 @@ impl FromStr for ModuleDefinition {
             name[1..].to_string()
         };
         let destination_modules = destinations
-            .split(", ")
+            .into_iter()
             .map(|s| s.to_string())
             .collect::<Vec<_>>();
         Ok(ModuleDefinition {
@@ -186,46 +190,69 @@ struct ModuleConfiguration {
 }
 
 impl ModuleConfiguration {
-    fn into_state(self) -> ModuleConfigurationState {
-        let modules = self
-            .module_definitions
-            .iter()
-            .map(|(key, definition)| {
-                let inputs = self
-                    .module_definitions
-                    .values()
-                    .filter(|module| module.destination_modules.contains(&key))
-                    .map(|module| module.module_id.to_owned())
+    /// Every module, with an edge to each of its destinations. Destinations without a definition
+    /// (like "rx") are included as nodes with no outgoing edges.
+    fn graph(&self) -> Graph<String> {
+        Graph::from_adjacency(
+            self.module_definitions
+                .values()
+                .sorted_by_key(|definition| &definition.module_id)
+                .map(|definition| {
+                    (
+                        definition.module_id.to_owned(),
+                        definition.destination_modules.iter().cloned(),
+                    )
+                }),
+        )
+    }
+
+    fn into_state(mut self) -> ModuleConfigurationState {
+        let graph = self.graph();
+        let modules = graph
+            .nodes()
+            .filter_map(|(id, key)| {
+                let definition = self.module_definitions.remove(key)?;
+                let inputs = graph
+                    .predecessors(id)
+                    .map(|input| graph.node(input).to_owned())
                     .collect_vec();
-                (
-                    key.to_owned(),
-                    Module::from_definition(definition.to_owned(), &inputs),
-                )
+                Some((key.to_owned(), Module::from_definition(definition, &inputs)))
             })
             .collect();
         ModuleConfigurationState { modules }
     }
 
-    fn as_mermaid_diagram(&self) -> String {
-        let mut result = String::new();
-        writeln!(result, "graph TD;").unwrap();
-        for module_def in self.module_definitions.values() {
-            let module_id = &module_def.module_id;
-            let module_name = match module_def.module_type {
-                ModuleType::Broadcaster => None,
-                ModuleType::FlipFlop => Some(format!("%{}", module_id)),
-                ModuleType::Conjunction => Some(format!("&{}", module_id)),
-            };
-            let module_node = if let Some(module_name) = module_name {
-                format!("{}[{}]", module_id, module_name)
-            } else {
-                module_id.to_owned()
-            };
-            for destination in &module_def.destination_modules {
-                writeln!(result, "  {} --> {}", module_node, destination).unwrap();
+    /// Flip-flops are boxes and conjunctions are diamonds. A conjunction with only one input is
+    /// just an inverter, so it gets drawn as one. Destinations without a definition are plain text.
+    fn node_style(&self, graph: &Graph<String>, module_id: &str) -> NodeStyle {
+        let Some(definition) = self.module_definitions.get(module_id) else {
+            return NodeStyle::new(module_id, NodeShape::Plain);
+        };
+        match definition.module_type {
+            ModuleType::Broadcaster => NodeStyle::new(module_id, NodeShape::DoubleCircle),
+            ModuleType::FlipFlop => NodeStyle::new(format!("%{}", module_id), NodeShape::Box),
+            ModuleType::Conjunction => {
+                let input_count = graph
+                    .find(|node| node == module_id)
+                    .map_or(0, |id| graph.predecessors(id).count());
+                let shape = if input_count == 1 {
+                    NodeShape::InvertedTriangle
+                } else {
+                    NodeShape::Diamond
+                };
+                NodeStyle::new(format!("&{}", module_id), shape)
             }
         }
-        result
+    }
+
+    fn as_mermaid_diagram(&self) -> String {
+        let graph = self.graph();
+        graph.to_mermaid(|module_id| self.node_style(&graph, module_id), |_| None)
+    }
+
+    fn as_dot_diagram(&self) -> String {
+        let graph = self.graph();
+        graph.to_dot(|module_id| self.node_style(&graph, module_id), |_| None)
     }
 }
 
@@ -233,11 +260,10 @@ impl FromStr for ModuleConfiguration {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self> {
-        let module_definitions = s
-            .lines()
-            .map(|line| ModuleDefinition::from_str(line))
-            .map_ok(|line| (line.module_id.clone(), line))
-            .collect::<Result<_>>()?;
+        let module_definitions = parse::lines(s, ModuleDefinition::from_str)?
+            .into_iter()
+            .map(|definition| (definition.module_id.clone(), definition))
+            .collect();
         Ok(ModuleConfiguration { module_definitions })
     }
 }
@@ -312,6 +338,7 @@ impl ModuleConfigurationState {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::framework::fuzz;
 
     #[test]
     fn test_part1() {
@@ -332,6 +359,31 @@ mod test {
         assert_eq!(ModuleDefinition::from_str(input).unwrap(), expected);
     }
 
+    #[test]
+    fn test_parser_never_panics() {
+        let corpus = [
+            indoc! {"
+                broadcaster -> a, b, c
+                %a -> b
+                %b -> c
+                %c -> inv
+                &inv -> a
+            "},
+            indoc! {"
+                broadcaster -> a
+                %a -> inv, con
+                &inv -> b
+                %b -> con
+                &con -> output
+            "},
+        ];
+        fuzz::check_parser(
+            "ModuleConfiguration",
+            &corpus,
+            ModuleConfiguration::from_str,
+        );
+    }
+
     #[test]
     fn test_inverter() {
         let configuration = indoc! {"
@@ -447,4 +499,48 @@ mod test {
         assert_eq!(low, 4250);
         assert_eq!(high, 2750);
     }
+
+    #[test]
+    fn test_graph() {
+        let configuration = indoc! {"
+            broadcaster -> a
+            %a -> inv, con
+            &inv -> b
+            %b -> con
+            &con -> output
+        "}
+        .parse::<ModuleConfiguration>()
+        .unwrap();
+        let graph = configuration.graph();
+        assert_eq!(graph.node_count(), 6);
+        assert_eq!(graph.edge_count(), 6);
+        let con = graph.find(|module| module == "con").unwrap();
+        let inputs = graph
+            .predecessors(con)
+            .map(|input| graph.node(input).as_str())
+            .sorted()
+            .collect_vec();
+        assert_eq!(inputs, vec!["a", "b"]);
+        assert!(graph.toposort().is_ok());
+    }
+
+    #[test]
+    fn test_dot_diagram() {
+        let configuration = indoc! {"
+            broadcaster -> a
+            %a -> inv, con
+            &inv -> b
+            %b -> con
+            &con -> output
+        "}
+        .parse::<ModuleConfiguration>()
+        .unwrap();
+        let dot = configuration.as_dot_diagram();
+        assert!(dot.contains("[label=\"broadcaster\", shape=doublecircle];"));
+        assert!(dot.contains("[label=\"%a\", shape=box];"));
+        assert!(dot.contains("[label=\"&inv\", shape=invtriangle];"));
+        assert!(dot.contains("[label=\"&con\", shape=diamond];"));
+        assert!(dot.contains("[label=\"output\", shape=plaintext];"));
+        assert_eq!(dot.matches("->").count(), 6);
+    }
 }
@@ -66,6 +66,17 @@ impl GardenMap {
     }
 
     fn gardens_reachable(&self, num_steps: usize) -> usize {
+        // Anywhere we step to can be stepped back to, so it stays reachable every other step. The
+        // start is the exception, if it's boxed in by rocks.
+        let boxed_in = self
+            .start_position
+            .cardinal_neighbors()
+            .into_iter()
+            .all(|neighbor| self.get(neighbor) == Tile::Rock);
+        if boxed_in && num_steps > 0 {
+            return 0;
+        }
+
         let mut last_checkpoint = Instant::now();
 
         let mut visited_odd = HashSet::<IntVector>::new();
@@ -155,28 +166,35 @@ impl FromStr for GardenMap {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::framework::fuzz;
+    use crate::framework::property::{self, ensure_eq, Rng};
 
     #[test]
     fn test_part1() {
         assert_eq!(super::Day21.part1().unwrap().unwrap(), "3847".to_string(),);
     }
 
+    const SAMPLE_INPUT: &str = indoc! {"
+        ...........
+        .....###.#.
+        .###.##..#.
+        ..#.#...#..
+        ....#.#....
+        .##..S####.
+        .##..#...#.
+        .......##..
+        .##.#.####.
+        .##..##.##.
+        ...........
+    "};
+
     fn sample_input() -> GardenMap {
-        indoc! {"
-            ...........
-            .....###.#.
-            .###.##..#.
-            ..#.#...#..
-            ....#.#....
-            .##..S####.
-            .##..#...#.
-            .......##..
-            .##.#.####.
-            .##..##.##.
-            ...........
-        "}
-        .parse()
-        .unwrap()
+        SAMPLE_INPUT.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parser_never_panics() {
+        fuzz::check_parser("GardenMap", &[SAMPLE_INPUT], GardenMap::from_str);
     }
 
     #[test]
@@ -185,6 +203,21 @@ mod test {
         assert_eq!(map.gardens_reachable(6), 16);
     }
 
+    #[test]
+    fn test_boxed_in_start() {
+        let map: GardenMap = indoc! {"
+            ...
+            .#.
+            #S#
+            .#.
+        "}
+        .parse()
+        .unwrap();
+        assert_eq!(map.gardens_reachable(0), 1);
+        assert_eq!(map.gardens_reachable(1), 0);
+        assert_eq!(map.gardens_reachable(2), 0);
+    }
+
     #[test]
     fn test_infinite_gardens() {
         let map = sample_input();
@@ -196,4 +229,48 @@ mod test {
         // the below are still too slow to run!
         // assert_eq!(map.gardens_reachable(5000), 16733044);
     }
+
+    /// Steps every position at once, exactly as the puzzle describes it.
+    fn gardens_reachable_by_simulation(map: &GardenMap, num_steps: usize) -> usize {
+        let mut positions = HashSet::from([map.start_position]);
+        for _ in 0..num_steps {
+            positions = positions
+                .iter()
+                .flat_map(|position| position.cardinal_neighbors())
+                .filter(|neighbor| map.get(*neighbor) == Tile::GardenPlot)
+                .collect();
+        }
+        positions.len()
+    }
+
+    fn random_garden(rng: &mut Rng, size: usize) -> (GardenMap, usize) {
+        let width = rng.gen_range(1..=1 + size.min(6));
+        let height = rng.gen_range(1..=1 + size.min(6));
+        let mut tiles = (0..width * height)
+            .map(|_| if rng.chance(1, 4) { '#' } else { '.' })
+            .collect_vec();
+        tiles[rng.gen_range(0..=width * height - 1)] = 'S';
+        let map = tiles
+            .chunks(width)
+            .map(|row| row.iter().collect::<String>())
+            .join("\n")
+            .parse()
+            .unwrap();
+        (map, rng.gen_range(0..=size * 3))
+    }
+
+    #[test]
+    fn test_frontier_matches_simulation() {
+        property::check(
+            "frontier matches simulation",
+            random_garden,
+            |(map, num_steps)| {
+                ensure_eq(
+                    map.gardens_reachable(*num_steps),
+                    gardens_reachable_by_simulation(map, *num_steps),
+                    "gardens reachable",
+                )
+            },
+        );
+    }
 }
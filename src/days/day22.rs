@@ -1,11 +1,12 @@
 // Day 22: Sand Slabs
 
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::collections::{HashMap, HashSet};
 use std::fmt::Write;
-use std::ops::RangeInclusive;
 use std::str::FromStr;
 
-use crate::framework::grid::{GridShape, IntVector};
+use crate::framework::graph::{Graph, NodeShape, NodeStyle};
+use crate::framework::grid::IntVector;
+use crate::framework::grid3::{Axis3, AxisPlane, IntBox3, IntVector3};
 use crate::framework::Day;
 use crate::prelude::*;
 
@@ -33,62 +34,42 @@ impl Day for Day22 {
         Some(try_block(move || {
             let mut world = puzzle_input()?;
             world.apply_gravity();
-            let chain_reaction_total = world
-                .bricks
-                .par_iter()
-                .map(|(id, _)| world.chain_reaction_size(*id))
-                .sum::<usize>();
-            Ok(chain_reaction_total.to_string())
+            Ok(world.chain_reaction_total().to_string())
         }))
     }
-}
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-struct IntVector3 {
-    x: isize,
-    y: isize,
-    z: isize,
-}
-
-impl FromStr for IntVector3 {
-    type Err = Error;
 
-    fn from_str(s: &str) -> Result<Self> {
-        let parts = s
-            .split(',')
-            .map(|part| part.parse::<isize>().map_err(|err| anyhow!(err)))
-            .collect::<Result<Vec<_>>>()?;
-        if parts.len() != 3 {
-            return Err(anyhow!("Expected 3 parts, got {}", parts.len()));
+    fn run_script(&self, name: &str) -> Result<bool> {
+        let settled_world = || -> Result<World> {
+            let mut world = puzzle_input()?;
+            world.apply_gravity();
+            Ok(world)
+        };
+        match name {
+            "mermaid_diagram" => println!("{}", settled_world()?.as_mermaid_diagram()),
+            "dot_diagram" => println!("{}", settled_world()?.as_dot_diagram()),
+            _ => return Ok(false),
         }
-        Ok(Self {
-            x: parts[0],
-            y: parts[1],
-            z: parts[2],
-        })
+        Ok(true)
     }
 }
 
+/// Looking down from above, so each brick covers an area of the ground.
+const TOP_DOWN: AxisPlane = AxisPlane {
+    horizontal: Axis3::X,
+    vertical: Axis3::Y,
+    vertical_up: false,
+};
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-struct Brick(IntVector3, IntVector3);
+struct Brick(IntBox3);
 
 impl Brick {
     fn lowest_z(&self) -> isize {
-        self.0.z.min(self.1.z)
+        self.0.min.z
     }
 
     fn highest_z(&self) -> isize {
-        self.0.z.max(self.1.z)
-    }
-
-    fn x_range(&self) -> RangeInclusive<isize> {
-        self.0.x.min(self.1.x)..=self.0.x.max(self.1.x)
-    }
-    fn y_range(&self) -> RangeInclusive<isize> {
-        self.0.y.min(self.1.y)..=self.0.y.max(self.1.y)
-    }
-    fn z_range(&self) -> RangeInclusive<isize> {
-        self.0.z.min(self.1.z)..=self.0.z.max(self.1.z)
+        self.0.max.z
     }
 }
 
@@ -97,10 +78,11 @@ impl FromStr for Brick {
 
     fn from_str(s: &str) -> Result<Self> {
         let (a, b) = s.split_once('~').ok_or(anyhow!("Invalid Brick: {}", s))?;
-        Ok(Self(a.parse()?, b.parse()?))
+        Ok(Self(IntBox3::from_corners(a.parse()?, b.parse()?)))
     }
 }
 
+#[derive(Debug)]
 struct World {
     /// Should always be sorted by lowest_z in ascending order.
     bricks: Vec<(usize, Brick)>,
@@ -133,9 +115,7 @@ impl World {
         for (id, brick) in self.bricks.iter_mut() {
             let brick_lowest_z = brick.lowest_z();
             let brick_highest_z = brick.highest_z();
-            let horizontal_coords = (brick.0.x..=brick.1.x)
-                .flat_map(|x| (brick.0.y..=brick.1.y).map(move |y| IntVector::new(x, y)))
-                .collect_vec();
+            let horizontal_coords = TOP_DOWN.project_box(&brick.0).coord_iter().collect_vec();
 
             let (stop_z, bricks_underneath) = {
                 let bricks_underneath = horizontal_coords
@@ -157,14 +137,9 @@ impl World {
             };
 
             let z_diff = brick_lowest_z - stop_z;
-            brick.0.z -= z_diff;
-            brick.1.z -= z_diff;
+            brick.0 = brick.0.translate(IntVector3::new(0, 0, -z_diff));
             for coord in horizontal_coords {
-                let cube_position = IntVector3 {
-                    x: coord.x,
-                    y: coord.y,
-                    z: brick_highest_z - z_diff,
-                };
+                let cube_position = IntVector3::new(coord.x, coord.y, brick_highest_z - z_diff);
                 highest_grounded_point.insert(
                     coord,
                     BrickCube {
@@ -189,6 +164,7 @@ impl World {
         self.sort();
     }
 
+    #[cfg(test)]
     fn get_top_z(&self) -> Option<isize> {
         self.bricks.iter().map(|(_, brick)| brick.highest_z()).max()
     }
@@ -219,8 +195,67 @@ impl World {
             .count()
     }
 
+    /// The ground, with an edge to every brick resting on it, and an edge from each brick to
+    /// every brick it supports.
+    fn support_graph(&self) -> Graph<SupportNode> {
+        let grounded_bricks = self
+            .bricks
+            .iter()
+            .map(|(id, _)| *id)
+            .filter(|id| self.supported_by.get(id).is_none_or(HashSet::is_empty))
+            .map(SupportNode::Brick)
+            .collect_vec();
+        let supports = self.bricks.iter().map(|(id, _)| {
+            let supported_bricks = self
+                .brick_support
+                .get(id)
+                .into_iter()
+                .flatten()
+                .copied()
+                .sorted()
+                .map(SupportNode::Brick)
+                .collect_vec();
+            (SupportNode::Brick(*id), supported_bricks)
+        });
+        Graph::from_adjacency(
+            std::iter::once((SupportNode::Ground, grounded_bricks)).chain(supports),
+        )
+    }
+
+    /// A brick falls when it's disintegrated exactly when every path from the ground to it goes
+    /// through the disintegrated brick. So the chain reaction from each brick is everything it
+    /// dominates, apart from itself.
+    fn chain_reaction_sizes(&self) -> HashMap<usize, usize> {
+        let graph = self.support_graph();
+        let ground = graph
+            .find(|node| *node == SupportNode::Ground)
+            .expect("support graph should include the ground");
+        let sizes = graph.dominators(ground).subtree_sizes();
+        graph
+            .nodes()
+            .filter_map(|(node, support_node)| match support_node {
+                SupportNode::Ground => None,
+                SupportNode::Brick(id) => Some((*id, sizes[node].saturating_sub(1))),
+            })
+            .collect()
+    }
+
+    fn chain_reaction_total(&self) -> usize {
+        self.chain_reaction_sizes().values().sum()
+    }
+
+    fn as_mermaid_diagram(&self) -> String {
+        self.support_graph()
+            .to_mermaid(SupportNode::style, |_| None)
+    }
+
+    fn as_dot_diagram(&self) -> String {
+        self.support_graph().to_dot(SupportNode::style, |_| None)
+    }
+
+    #[cfg(test)]
     fn chain_reaction_size(&self, disintegrated_brick_id: usize) -> usize {
-        let mut queue = VecDeque::new();
+        let mut queue = std::collections::VecDeque::new();
         let mut fallen = HashSet::<usize>::new();
 
         let supported_bricks = match { self.brick_support.get(&disintegrated_brick_id) } {
@@ -264,87 +299,22 @@ impl World {
         fallen.len()
     }
 
-    fn debug_xz_plane(&self) -> String {
-        let visible_ids = ('A'..'Z').chain('a'..'z').chain('0'..'9').collect_vec();
+    fn debug_plane(&self, plane: AxisPlane) -> String {
         if self.bricks.is_empty() {
             return "[no bricks]".into();
         }
-        let max_x = self
+        let labeled_bricks = self
             .bricks
             .iter()
-            .map(|(_, brick)| brick.1.x.max(brick.0.x))
-            .max()
-            .unwrap_or(0);
-        let shape = GridShape {
-            width: max_x as usize + 1,
-            height: self.get_top_z().unwrap() as usize + 1,
-        };
-        shape.format_char_grid(shape.coord_iter().map(|coord| {
-            let x = coord.x;
-            // we want to render with 0 at the bottom
-            let z = shape.height as isize - coord.y - 1;
-            let brick_ids = self
-                .bricks
-                .iter()
-                .filter(|(_, brick)| brick.x_range().contains(&x) && brick.z_range().contains(&z))
-                .map(|(id, _)| id)
-                .collect_vec();
-
-            if brick_ids.len() > 1 {
-                '?' // indicates multiple bricks
-            } else if brick_ids.len() == 1 {
-                let id_idx = *brick_ids[0];
-                if id_idx >= visible_ids.len() {
-                    '#' // indicates a brick with an index too large to render in a single char
-                } else {
-                    visible_ids[id_idx]
-                }
-            } else {
-                '.'
-            }
-        }))
-    }
-
-    fn debug_yz_plane(&self) -> String {
-        if self.bricks.is_empty() {
-            return "[no bricks]".into();
-        }
-        let max_y = self
-            .bricks
-            .iter()
-            .map(|(_, brick)| brick.1.y.max(brick.0.y))
-            .max()
-            .unwrap_or(0);
-        let shape = GridShape {
-            width: max_y as usize + 1,
-            height: self.get_top_z().unwrap() as usize + 1,
-        };
-        shape.format_char_grid(shape.coord_iter().map(|coord| {
-            // "x" on the 2D plane is the "y" coordinate in the 3D world
-            let y = coord.x;
-            // we want to render with 0 at the bottom
-            let z = shape.height as isize - coord.y - 1;
-            let brick_ids = self
-                .bricks
-                .iter()
-                .filter(|(_, brick)| brick.y_range().contains(&y) && brick.z_range().contains(&z))
-                .map(|(id, _)| id)
-                .collect_vec();
-
-            if brick_ids.len() > 1 {
-                '?' // indicates multiple bricks
-            } else if brick_ids.len() == 1 {
-                friendlify_id(*brick_ids[0])
-            } else {
-                '.'
-            }
-        }))
+            .map(|(id, brick)| (brick.0, friendlify_id(*id)))
+            .collect_vec();
+        plane.format_char_grid(&labeled_bricks)
     }
 
     #[allow(dead_code)]
     fn debug(&self) -> String {
-        let xz_plane = self.debug_xz_plane();
-        let yz_plane = self.debug_yz_plane();
+        let xz_plane = self.debug_plane(AxisPlane::side_view(Axis3::X));
+        let yz_plane = self.debug_plane(AxisPlane::side_view(Axis3::Y));
         let mut lines = xz_plane.lines().zip(yz_plane.lines());
         let mut result = "".to_string();
         while let Some((xz_line, yz_line)) = lines.next() {
@@ -354,6 +324,21 @@ impl World {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum SupportNode {
+    Ground,
+    Brick(usize),
+}
+
+impl SupportNode {
+    fn style(&self) -> NodeStyle {
+        match self {
+            SupportNode::Ground => NodeStyle::new("ground", NodeShape::Plain),
+            SupportNode::Brick(id) => NodeStyle::new(id.to_string(), NodeShape::Box),
+        }
+    }
+}
+
 fn friendlify_id(id: usize) -> char {
     let visible_ids = ('A'..'Z').chain('a'..'z').chain('0'..'9').collect_vec();
     visible_ids.get(id).copied().unwrap_or('#')
@@ -376,6 +361,7 @@ impl FromStr for World {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::framework::fuzz;
 
     #[test]
     fn test_part1() {
@@ -387,18 +373,23 @@ mod test {
         assert_eq!(super::Day22.part2().unwrap().unwrap(), "47671".to_string(),);
     }
 
+    const SAMPLE_INPUT: &str = indoc! {"
+        1,0,1~1,2,1
+        0,0,2~2,0,2
+        0,2,3~2,2,3
+        0,0,4~0,2,4
+        2,0,5~2,2,5
+        0,1,6~2,1,6
+        1,1,8~1,1,9
+    "};
+
     fn sample_input() -> World {
-        indoc! {"
-            1,0,1~1,2,1
-            0,0,2~2,0,2
-            0,2,3~2,2,3
-            0,0,4~0,2,4
-            2,0,5~2,2,5
-            0,1,6~2,1,6
-            1,1,8~1,1,9
-        "}
-        .parse()
-        .unwrap()
+        SAMPLE_INPUT.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parser_never_panics() {
+        fuzz::check_parser("World", &[SAMPLE_INPUT], World::from_str);
     }
 
     #[test]
@@ -429,22 +420,9 @@ mod test {
         world.apply_gravity();
         let mut found = HashMap::<IntVector3, Vec<usize>>::new();
         for (id, brick) in world.bricks.iter() {
-            let cubes = (brick.0.z..=brick.1.z)
-                .flat_map(|z| {
-                    (brick.0.x..=brick.1.x).flat_map(move |x| {
-                        (brick.0.y..=brick.1.y).map(move |y| IntVector3 { x, y, z })
-                    })
-                })
-                .collect_vec();
+            let cubes = brick.0.voxels().collect_vec();
             for coord in cubes {
-                found
-                    .entry(IntVector3 {
-                        x: coord.x,
-                        y: coord.y,
-                        z: coord.z,
-                    })
-                    .or_default()
-                    .push(*id);
+                found.entry(coord).or_default().push(*id);
             }
         }
         let mut errors = Vec::new();
@@ -463,32 +441,16 @@ mod test {
         world.apply_gravity();
         let mut all_cubes = HashMap::<IntVector3, usize>::new();
         for (id, brick) in world.bricks.iter() {
-            let cubes = (brick.0.z..=brick.1.z)
-                .flat_map(|z| {
-                    (brick.0.x..=brick.1.x).flat_map(move |x| {
-                        (brick.0.y..=brick.1.y).map(move |y| IntVector3 { x, y, z })
-                    })
-                })
-                .collect_vec();
+            let cubes = brick.0.voxels().collect_vec();
             for coord in cubes {
                 all_cubes.insert(coord, *id);
             }
         }
         let mut floating_bricks: Vec<(usize, Brick)> = Vec::new();
         for (id, brick) in world.bricks.iter() {
-            let cubes = (brick.0.z..=brick.1.z)
-                .flat_map(|z| {
-                    (brick.0.x..=brick.1.x).flat_map(move |x| {
-                        (brick.0.y..=brick.1.y).map(move |y| IntVector3 { x, y, z })
-                    })
-                })
-                .collect_vec();
+            let cubes = brick.0.voxels().collect_vec();
             let brick_is_floating = cubes.iter().all(|coord| {
-                let below = IntVector3 {
-                    x: coord.x,
-                    y: coord.y,
-                    z: coord.z - 1,
-                };
+                let below = *coord - IntVector3::new(0, 0, 1);
                 below.z > 0 && !all_cubes.contains_key(&below)
             });
             if brick_is_floating {
@@ -509,4 +471,31 @@ mod test {
         assert_eq!(world.chain_reaction_size(0), 6); // A
         assert_eq!(world.chain_reaction_size(5), 1); // F
     }
+
+    #[test]
+    fn test_chain_reaction_from_dominators() {
+        let mut world = sample_input();
+        world.apply_gravity();
+        let sizes = world.chain_reaction_sizes();
+        for (id, _) in world.bricks.iter() {
+            assert_eq!(
+                sizes[id],
+                world.chain_reaction_size(*id),
+                "brick {}",
+                friendlify_id(*id)
+            );
+        }
+        assert_eq!(world.chain_reaction_total(), 7);
+    }
+
+    #[test]
+    fn test_dot_diagram() {
+        let mut world = sample_input();
+        world.apply_gravity();
+        let dot = world.as_dot_diagram();
+        assert!(dot.contains("[label=\"ground\", shape=plaintext];"));
+        assert!(dot.contains("[label=\"0\", shape=box];"));
+        // ground -> A, A -> B, C, B and C -> D, E, D and E -> F, F -> G
+        assert_eq!(dot.matches("->").count(), 10);
+    }
 }
@@ -0,0 +1,173 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::framework::prelude::*;
+
+const ROOT: usize = 0;
+
+/// A match found by `AhoCorasick`. `start` and `end` are byte offsets into the haystack.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Match<'a, V> {
+    pub start: usize,
+    pub end: usize,
+    pub value: &'a V,
+}
+
+#[derive(Debug, Clone, Default)]
+struct State {
+    next: HashMap<u8, usize>,
+    /// The state for the longest proper suffix of this one that's also in the trie
+    fail: usize,
+    /// Indices into `patterns` for every pattern ending here, longest first
+    outputs: Vec<usize>,
+}
+
+/// Finds every occurrence of a set of patterns in one pass over the haystack, including
+/// occurrences that overlap each other (like "two" and "one" in "twone").
+#[derive(Debug, Clone)]
+pub struct AhoCorasick<V> {
+    states: Vec<State>,
+    /// Length in bytes and value for each pattern
+    patterns: Vec<(usize, V)>,
+}
+
+impl<V> AhoCorasick<V> {
+    /// Fails on empty or repeated patterns.
+    pub fn new<P: AsRef<str>>(patterns: impl IntoIterator<Item = (P, V)>) -> Result<Self> {
+        let mut automaton = AhoCorasick {
+            states: vec![State::default()],
+            patterns: Vec::new(),
+        };
+        let mut seen = HashSet::new();
+        for (pattern, value) in patterns {
+            let pattern = pattern.as_ref();
+            if pattern.is_empty() {
+                return Err(anyhow!("patterns can't be empty"));
+            }
+            if !seen.insert(pattern.to_string()) {
+                return Err(anyhow!("repeated pattern: {:?}", pattern));
+            }
+            automaton.insert(pattern.as_bytes(), value);
+        }
+        automaton.link_failures();
+        Ok(automaton)
+    }
+
+    fn insert(&mut self, pattern: &[u8], value: V) {
+        let mut state = ROOT;
+        for &byte in pattern {
+            state = match self.states[state].next.get(&byte) {
+                Some(&next) => next,
+                None => {
+                    self.states.push(State::default());
+                    let next = self.states.len() - 1;
+                    self.states[state].next.insert(byte, next);
+                    next
+                }
+            };
+        }
+        self.states[state].outputs.push(self.patterns.len());
+        self.patterns.push((pattern.len(), value));
+    }
+
+    /// Breadth first, so a state's failure link is always finished before its children need it.
+    fn link_failures(&mut self) {
+        let mut queue = self.states[ROOT]
+            .next
+            .values()
+            .copied()
+            .collect::<VecDeque<_>>();
+        while let Some(state) = queue.pop_front() {
+            let children = self.states[state]
+                .next
+                .iter()
+                .map(|(&byte, &child)| (byte, child))
+                .collect_vec();
+            for (byte, child) in children {
+                let fail = if state == ROOT {
+                    ROOT
+                } else {
+                    self.transition(self.states[state].fail, byte)
+                };
+                self.states[child].fail = fail;
+                let inherited = self.states[fail].outputs.clone();
+                self.states[child].outputs.extend(inherited);
+                queue.push_back(child);
+            }
+        }
+    }
+
+    fn transition(&self, mut state: usize, byte: u8) -> usize {
+        loop {
+            if let Some(&next) = self.states[state].next.get(&byte) {
+                return next;
+            }
+            if state == ROOT {
+                return ROOT;
+            }
+            state = self.states[state].fail;
+        }
+    }
+
+    pub fn pattern_count(&self) -> usize {
+        self.patterns.len()
+    }
+
+    /// Every occurrence of every pattern, ordered by where they end. Matches ending at the same
+    /// place come longest first.
+    pub fn find_overlapping<'a>(&'a self, haystack: &'a str) -> impl Iterator<Item = Match<'a, V>> {
+        haystack
+            .bytes()
+            .enumerate()
+            .scan(ROOT, move |state, (i, byte)| {
+                *state = self.transition(*state, byte);
+                Some((i + 1, *state))
+            })
+            .flat_map(move |(end, state)| {
+                self.states[state].outputs.iter().map(move |&pattern| {
+                    let (len, value) = &self.patterns[pattern];
+                    Match {
+                        start: end - len,
+                        end,
+                        value,
+                    }
+                })
+            })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn spans<'a, V: 'a>(matches: impl Iterator<Item = Match<'a, V>>) -> Vec<(usize, usize)> {
+        matches.map(|it| (it.start, it.end)).collect()
+    }
+
+    #[test]
+    fn test_overlapping_matches() {
+        let automaton = AhoCorasick::new([("he", 1), ("she", 2), ("his", 3), ("hers", 4)]).unwrap();
+        let matches = automaton.find_overlapping("ushers").collect_vec();
+        assert_eq!(
+            matches.iter().map(|it| *it.value).collect_vec(),
+            vec![2, 1, 4]
+        );
+        assert_eq!(spans(matches.into_iter()), vec![(1, 4), (2, 4), (2, 6)]);
+        assert_eq!(automaton.pattern_count(), 4);
+    }
+
+    #[test]
+    fn test_nested_and_repeated_matches() {
+        let automaton = AhoCorasick::new([("aa", ()), ("a", ())]).unwrap();
+        assert_eq!(
+            spans(automaton.find_overlapping("baaa")),
+            vec![(1, 2), (1, 3), (2, 3), (2, 4), (3, 4)]
+        );
+        assert_eq!(automaton.find_overlapping("").count(), 0);
+    }
+
+    #[test]
+    fn test_invalid_patterns() {
+        assert!(AhoCorasick::new([("", 0)]).is_err());
+        assert!(AhoCorasick::new([("one", 1), ("one", 2)]).is_err());
+    }
+}
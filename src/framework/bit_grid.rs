@@ -0,0 +1,361 @@
+use std::fmt::Display;
+
+use crate::framework::grid::{GridShape, IntVector};
+use crate::framework::prelude::*;
+
+const WORD_BITS: usize = u64::BITS as usize;
+
+/// A grid of booleans, packed 64 to a word. Each row starts on a fresh word, so rows can be
+/// compared and combined a word at a time. Bits past the end of a row are always zero.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct BitGrid {
+    shape: GridShape,
+    words_per_row: usize,
+    words: Box<[u64]>,
+}
+
+impl BitGrid {
+    /// Every cell starts out unset.
+    pub fn new(shape: GridShape) -> Self {
+        let words_per_row = shape.width.div_ceil(WORD_BITS);
+        let words = vec![0; words_per_row * shape.height].into_boxed_slice();
+        BitGrid {
+            shape,
+            words_per_row,
+            words,
+        }
+    }
+
+    /// Takes cells row by row, like `Grid::new`.
+    pub fn from_cells(shape: GridShape, cells: impl IntoIterator<Item = bool>) -> Result<Self> {
+        let mut grid = Self::new(shape);
+        let mut count = 0;
+        for (index, cell) in cells.into_iter().enumerate() {
+            if index < grid.shape.area() && cell {
+                grid.set(grid.shape.coordinate_for_index(index), true);
+            }
+            count += 1;
+        }
+        if count != grid.shape.area() {
+            return Err(anyhow!(
+                "expected {} cells for a {}x{} grid, got {}",
+                grid.shape.area(),
+                grid.shape.width,
+                grid.shape.height,
+                count
+            ));
+        }
+        Ok(grid)
+    }
+
+    /// Sets each of `points`. Fails if any of them are out of bounds.
+    pub fn from_points(
+        shape: GridShape,
+        points: impl IntoIterator<Item = IntVector>,
+    ) -> Result<Self> {
+        let mut grid = Self::new(shape);
+        for point in points {
+            if !grid.shape.in_bounds(point) {
+                return Err(anyhow!("{:?} is out of bounds", point));
+            }
+            grid.set(point, true);
+        }
+        Ok(grid)
+    }
+
+    pub fn parse_char_grid(
+        input: &str,
+        parse_cell: impl FnMut(char) -> Result<bool>,
+    ) -> Result<Self> {
+        let (shape, chars) = GridShape::parse_char_grid(input)?;
+        let cells = chars
+            .iter()
+            .copied()
+            .map(parse_cell)
+            .collect::<Result<Vec<_>>>()?;
+        Self::from_cells(shape, cells)
+    }
+
+    pub fn shape(&self) -> &GridShape {
+        &self.shape
+    }
+
+    fn word_and_mask(&self, coord: IntVector) -> (usize, u64) {
+        let (x, y) = (coord.x as usize, coord.y as usize);
+        (y * self.words_per_row + x / WORD_BITS, 1 << (x % WORD_BITS))
+    }
+
+    /// Returns `None` if out of bounds.
+    pub fn get(&self, coord: IntVector) -> Option<bool> {
+        if !self.shape.in_bounds(coord) {
+            return None;
+        }
+        let (word, mask) = self.word_and_mask(coord);
+        Some(self.words[word] & mask != 0)
+    }
+
+    /// Like `get`, but out of bounds counts as unset.
+    pub fn is_set(&self, coord: IntVector) -> bool {
+        self.get(coord).unwrap_or(false)
+    }
+
+    /// Panics if out of bounds.
+    pub fn set(&mut self, coord: IntVector, value: bool) {
+        assert!(
+            self.shape.in_bounds(coord),
+            "{:?} is out of bounds for a {}x{} grid",
+            coord,
+            self.shape.width,
+            self.shape.height
+        );
+        let (word, mask) = self.word_and_mask(coord);
+        if value {
+            self.words[word] |= mask;
+        } else {
+            self.words[word] &= !mask;
+        }
+    }
+
+    /// The packed words for row `y`, with `x` in bit `x % 64` of word `x / 64`.
+    pub fn row(&self, y: usize) -> &[u64] {
+        let start = y * self.words_per_row;
+        &self.words[start..start + self.words_per_row]
+    }
+
+    /// Column `x` packed the same way as a row, with `y` standing in for `x`.
+    pub fn column(&self, x: usize) -> Vec<u64> {
+        let mut column = vec![0; self.shape.height.div_ceil(WORD_BITS)];
+        let (offset, bit) = (x / WORD_BITS, x % WORD_BITS);
+        for y in 0..self.shape.height {
+            let value = (self.row(y)[offset] >> bit) & 1;
+            column[y / WORD_BITS] |= value << (y % WORD_BITS);
+        }
+        column
+    }
+
+    /// How many cells differ between two rows.
+    pub fn row_difference(&self, a: usize, b: usize) -> usize {
+        self.row(a)
+            .iter()
+            .zip(self.row(b))
+            .map(|(a, b)| (a ^ b).count_ones() as usize)
+            .sum()
+    }
+
+    /// Swaps rows and columns, so column comparisons can be done as row comparisons.
+    pub fn transpose(&self) -> Self {
+        let shape = GridShape {
+            width: self.shape.height,
+            height: self.shape.width,
+        };
+        let mut transposed = Self::new(shape);
+        for x in 0..self.shape.width {
+            let start = x * transposed.words_per_row;
+            transposed.words[start..start + transposed.words_per_row]
+                .copy_from_slice(&self.column(x));
+        }
+        transposed
+    }
+
+    pub fn count_ones(&self) -> usize {
+        self.words
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    pub fn count_row(&self, y: usize) -> usize {
+        self.row(y)
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    pub fn count_column(&self, x: usize) -> usize {
+        self.column(x)
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    /// Every set cell, left to right, top to bottom.
+    pub fn iter_ones(&self) -> impl Iterator<Item = IntVector> + '_ {
+        (0..self.shape.height).flat_map(move |y| {
+            self.row(y).iter().enumerate().flat_map(move |(i, &word)| {
+                let mut remaining = word;
+                std::iter::from_fn(move || {
+                    if remaining == 0 {
+                        return None;
+                    }
+                    let bit = remaining.trailing_zeros() as usize;
+                    remaining &= remaining - 1;
+                    Some(IntVector::new((i * WORD_BITS + bit) as isize, y as isize))
+                })
+            })
+        })
+    }
+
+    fn combine(&self, other: &Self, op: impl Fn(u64, u64) -> u64) -> Self {
+        assert_eq!(
+            self.shape, other.shape,
+            "can't combine grids of different shapes"
+        );
+        let words = self
+            .words
+            .iter()
+            .zip(other.words.iter())
+            .map(|(&a, &b)| op(a, b))
+            .collect();
+        BitGrid {
+            shape: self.shape.clone(),
+            words_per_row: self.words_per_row,
+            words,
+        }
+    }
+
+    /// Panics if the shapes differ, as do the other set operations.
+    pub fn union(&self, other: &Self) -> Self {
+        self.combine(other, |a, b| a | b)
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        self.combine(other, |a, b| a & b)
+    }
+
+    /// Everything set in `self` but not in `other`.
+    pub fn difference(&self, other: &Self) -> Self {
+        self.combine(other, |a, b| a & !b)
+    }
+
+    pub fn symmetric_difference(&self, other: &Self) -> Self {
+        self.combine(other, |a, b| a ^ b)
+    }
+
+    pub fn complement(&self) -> Self {
+        let mut result = self.clone();
+        if self.words_per_row == 0 {
+            // zero-width rows have no bits to flip
+            return result;
+        }
+        let tail_bits = self.shape.width % WORD_BITS;
+        let last_word_mask = if tail_bits == 0 {
+            u64::MAX
+        } else {
+            (1 << tail_bits) - 1
+        };
+        for row in result.words.chunks_mut(self.words_per_row) {
+            for word in row.iter_mut() {
+                *word = !*word;
+            }
+            if let Some(last) = row.last_mut() {
+                *last &= last_word_mask;
+            }
+        }
+        result
+    }
+}
+
+impl Display for BitGrid {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let chars = self
+            .shape
+            .coord_iter()
+            .map(|coord| if self.is_set(coord) { '#' } else { '.' });
+        f.write_str(&self.shape.format_char_grid(chars))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn sample() -> BitGrid {
+        BitGrid::parse_char_grid(
+            indoc! {"
+                #..#
+                .##.
+                #..#
+            "},
+            |c| Ok(c == '#'),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_get_set() {
+        let mut grid = sample();
+        assert_eq!(grid.get(IntVector::new(0, 0)), Some(true));
+        assert_eq!(grid.get(IntVector::new(1, 0)), Some(false));
+        assert_eq!(grid.get(IntVector::new(4, 0)), None);
+        assert!(!grid.is_set(IntVector::new(-1, 0)));
+        grid.set(IntVector::new(1, 0), true);
+        grid.set(IntVector::new(0, 0), false);
+        assert_eq!(grid.row(0), &[0b1010]);
+        assert_eq!(grid.count_ones(), 6);
+    }
+
+    #[test]
+    fn test_rows_and_columns() {
+        let grid = sample();
+        assert_eq!(grid.row_difference(0, 2), 0);
+        assert_eq!(grid.row_difference(0, 1), 4);
+        assert_eq!(grid.column(0), vec![0b101]);
+        assert_eq!(grid.count_row(1), 2);
+        assert_eq!(grid.count_column(1), 1);
+
+        let transposed = grid.transpose();
+        assert_eq!(
+            transposed.shape(),
+            &GridShape {
+                width: 3,
+                height: 4
+            }
+        );
+        assert_eq!(transposed.row(3), &[0b101]);
+        assert_eq!(transposed.transpose(), grid);
+    }
+
+    #[test]
+    fn test_wide_rows() {
+        let shape = GridShape {
+            width: 130,
+            height: 70,
+        };
+        let points = [
+            IntVector::new(0, 0),
+            IntVector::new(64, 1),
+            IntVector::new(129, 69),
+        ];
+        let grid = BitGrid::from_points(shape.clone(), points).unwrap();
+        assert_eq!(grid.iter_ones().collect_vec(), points.to_vec());
+        assert_eq!(grid.row(1), &[0, 1, 0]);
+        assert_eq!(grid.column(129), vec![0, 1 << 5]);
+        assert_eq!(grid.transpose().iter_ones().count(), 3);
+        assert_eq!(grid.complement().count_ones(), 130 * 70 - 3);
+        assert!(BitGrid::from_points(shape, [IntVector::new(130, 0)]).is_err());
+    }
+
+    #[test]
+    fn test_set_operations() {
+        let a = sample();
+        let b = a.complement();
+        assert_eq!(b.to_string(), ".##.\n#..#\n.##.\n");
+        assert_eq!(a.union(&b).count_ones(), 12);
+        assert_eq!(a.intersection(&b).count_ones(), 0);
+        assert_eq!(a.difference(&b), a);
+        assert_eq!(a.symmetric_difference(&a).count_ones(), 0);
+        assert!(BitGrid::from_cells(a.shape().clone(), [true; 5]).is_err());
+    }
+
+    #[test]
+    fn test_zero_width() {
+        let grid = BitGrid::new(GridShape {
+            width: 0,
+            height: 3,
+        });
+        assert_eq!(grid.row(2), &[] as &[u64]);
+        assert_eq!(grid.complement(), grid);
+        assert_eq!(grid.union(&grid.complement()).count_ones(), 0);
+        assert_eq!(grid.transpose().shape().height, 0);
+    }
+}
@@ -0,0 +1,199 @@
+use std::ops::Range;
+
+use crate::framework::grid::{GridShape, IntVector};
+use crate::framework::prelude::*;
+
+/// Squashes one axis down to its interesting coordinates. Every breakpoint gets a compressed cell
+/// of its own, and each gap between breakpoints collapses into a single cell.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AxisCompression {
+    ranges: Vec<Range<isize>>,
+}
+
+impl AxisCompression {
+    pub fn from_breakpoints(breakpoints: impl IntoIterator<Item = isize>) -> Self {
+        let coordinates_in_order = breakpoints
+            .into_iter()
+            .sorted_unstable()
+            .dedup()
+            .collect_vec();
+
+        let mut ranges = Vec::<Range<isize>>::new();
+        let mut prev_coord: Option<isize> = None;
+        for coord in coordinates_in_order {
+            if let Some(prev_coord) = prev_coord {
+                if coord - prev_coord > 1 {
+                    // gap
+                    ranges.push(prev_coord + 1..coord);
+                }
+            }
+            ranges.push(coord..coord + 1);
+            prev_coord = Some(coord);
+        }
+        Self { ranges }
+    }
+
+    /// The number of compressed cells.
+    pub fn len(&self) -> usize {
+        self.ranges.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// The compressed cell containing `coord`, or `None` if it's outside every breakpoint.
+    pub fn compress(&self, coord: isize) -> Option<usize> {
+        let index = self.ranges.partition_point(|range| range.end <= coord);
+        self.ranges
+            .get(index)
+            .filter(|range| range.contains(&coord))
+            .map(|_| index)
+    }
+
+    /// The real coordinates that a compressed cell stands for.
+    pub fn range(&self, index: usize) -> Range<isize> {
+        self.ranges[index].clone()
+    }
+
+    pub fn ranges(&self) -> &[Range<isize>] {
+        &self.ranges
+    }
+
+    /// Every compressed cell covering `start..=end`, in order from `start` to `end`.
+    pub fn compress_span(&self, start: isize, end: isize) -> Option<Vec<usize>> {
+        let start = self.compress(start)?;
+        let end = self.compress(end)?;
+        Some(if start <= end {
+            (start..=end).collect()
+        } else {
+            (end..=start).rev().collect()
+        })
+    }
+}
+
+/// Compresses both axes of a 2D plane, so that huge coordinates fit in a small `GridShape`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoordinateCompression {
+    pub columns: AxisCompression,
+    pub rows: AxisCompression,
+}
+
+impl CoordinateCompression {
+    pub fn from_points(points: impl IntoIterator<Item = IntVector>) -> Self {
+        let (xs, ys): (Vec<isize>, Vec<isize>) =
+            points.into_iter().map(|point| (point.x, point.y)).unzip();
+        Self {
+            columns: AxisCompression::from_breakpoints(xs),
+            rows: AxisCompression::from_breakpoints(ys),
+        }
+    }
+
+    pub fn shape(&self) -> GridShape {
+        GridShape {
+            width: self.columns.len(),
+            height: self.rows.len(),
+        }
+    }
+
+    pub fn compress_point(&self, point: IntVector) -> Option<IntVector> {
+        let x = self.columns.compress(point.x)?;
+        let y = self.rows.compress(point.y)?;
+        Some(IntVector::new(x as isize, y as isize))
+    }
+
+    /// Every compressed cell along an axis-aligned segment, both ends included, in order from
+    /// `start` to `end`.
+    pub fn compress_segment(&self, start: IntVector, end: IntVector) -> Result<Vec<IntVector>> {
+        let out_of_bounds = || anyhow!("Segment {:?} -> {:?} is out of bounds", start, end);
+        if start.y == end.y {
+            let y = self.rows.compress(start.y).ok_or_else(out_of_bounds)?;
+            let xs = self
+                .columns
+                .compress_span(start.x, end.x)
+                .ok_or_else(out_of_bounds)?;
+            Ok(xs
+                .into_iter()
+                .map(|x| IntVector::new(x as isize, y as isize))
+                .collect())
+        } else if start.x == end.x {
+            let x = self.columns.compress(start.x).ok_or_else(out_of_bounds)?;
+            let ys = self
+                .rows
+                .compress_span(start.y, end.y)
+                .ok_or_else(out_of_bounds)?;
+            Ok(ys
+                .into_iter()
+                .map(|y| IntVector::new(x as isize, y as isize))
+                .collect())
+        } else {
+            Err(anyhow!(
+                "Segment {:?} -> {:?} isn't axis-aligned",
+                start,
+                end
+            ))
+        }
+    }
+
+    /// The real area that a compressed cell stands for.
+    pub fn cell_area(&self, compressed: IntVector) -> usize {
+        self.columns.range(compressed.x as usize).len()
+            * self.rows.range(compressed.y as usize).len()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_axis_compression() {
+        let axis = AxisCompression::from_breakpoints([10, 0, 1, 10, 4]);
+        assert_eq!(axis.ranges(), &[0..1, 1..2, 2..4, 4..5, 5..10, 10..11]);
+        assert_eq!(axis.compress(3), Some(2));
+        assert_eq!(axis.compress(10), Some(5));
+        assert_eq!(axis.compress(-1), None);
+        assert_eq!(axis.compress(11), None);
+        assert_eq!(axis.compress_span(10, 3), Some(vec![5, 4, 3, 2]));
+    }
+
+    #[test]
+    fn test_coordinate_compression() {
+        let compression = CoordinateCompression::from_points([
+            IntVector::new(0, 0),
+            IntVector::new(100, 0),
+            IntVector::new(100, 5),
+        ]);
+        assert_eq!(
+            compression.shape(),
+            GridShape {
+                width: 3,
+                height: 3
+            }
+        );
+        assert_eq!(
+            compression.compress_point(IntVector::new(50, 3)),
+            Some(IntVector::new(1, 1))
+        );
+        assert_eq!(compression.cell_area(IntVector::new(1, 1)), 99 * 4);
+        assert_eq!(
+            compression
+                .compress_segment(IntVector::new(100, 5), IntVector::new(100, 0))
+                .unwrap(),
+            vec![
+                IntVector::new(2, 2),
+                IntVector::new(2, 1),
+                IntVector::new(2, 0)
+            ]
+        );
+        assert!(compression
+            .compress_segment(IntVector::new(0, 0), IntVector::new(100, 5))
+            .is_err());
+        let total_area: usize = compression
+            .shape()
+            .coord_iter()
+            .map(|cell| compression.cell_area(cell))
+            .sum();
+        assert_eq!(total_area, 101 * 6);
+    }
+}
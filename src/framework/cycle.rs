@@ -0,0 +1,215 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::framework::prelude::*;
+
+/// A sequence of states that eventually repeats: some number of states leading in (the tail),
+/// followed by a loop that goes on forever.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cycle<S> {
+    /// Every state up to the first repeat, starting with the initial state.
+    pub history: Vec<S>,
+    pub tail_length: usize,
+    pub period: usize,
+}
+
+impl<S> Cycle<S> {
+    /// The states before the loop starts.
+    pub fn tail(&self) -> &[S] {
+        &self.history[..self.tail_length]
+    }
+
+    /// One trip around the loop.
+    pub fn looping(&self) -> &[S] {
+        &self.history[self.tail_length..]
+    }
+
+    /// Where step `n` lands in `history`.
+    pub fn index_after(&self, n: usize) -> usize {
+        if n < self.tail_length {
+            n
+        } else {
+            self.tail_length + (n - self.tail_length) % self.period
+        }
+    }
+
+    /// The state after `n` steps, where 0 steps is the initial state.
+    pub fn state_after(&self, n: usize) -> &S {
+        &self.history[self.index_after(n)]
+    }
+}
+
+enum Search<S> {
+    Found(Cycle<S>),
+    /// Ran out of steps before anything repeated; this is the state we ended on.
+    Exhausted(S),
+}
+
+/// Remembers the step at which each state was first seen.
+trait SeenStates<S> {
+    fn get(&self, state: &S) -> Option<usize>;
+    fn insert(&mut self, state: &S, step: usize);
+}
+
+impl<S: Clone + Eq + Hash> SeenStates<S> for HashMap<S, usize> {
+    fn get(&self, state: &S) -> Option<usize> {
+        HashMap::get(self, state).copied()
+    }
+
+    fn insert(&mut self, state: &S, step: usize) {
+        HashMap::insert(self, state.clone(), step);
+    }
+}
+
+/// For states that can be numbered densely, a flat table instead of hashing every state.
+struct IndexedStates<F> {
+    first_seen: Vec<Option<usize>>,
+    index_of: F,
+}
+
+impl<S, F: Fn(&S) -> usize> SeenStates<S> for IndexedStates<F> {
+    fn get(&self, state: &S) -> Option<usize> {
+        self.first_seen[(self.index_of)(state)]
+    }
+
+    fn insert(&mut self, state: &S, step: usize) {
+        self.first_seen[(self.index_of)(state)] = Some(step);
+    }
+}
+
+fn search<S>(
+    initial: S,
+    max_steps: usize,
+    mut seen_states: impl SeenStates<S>,
+    mut step: impl FnMut(&S) -> Result<S>,
+) -> Result<Search<S>> {
+    let mut history = Vec::<S>::new();
+    let mut current = initial;
+    loop {
+        if let Some(tail_length) = seen_states.get(&current) {
+            return Ok(Search::Found(Cycle {
+                period: history.len() - tail_length,
+                tail_length,
+                history,
+            }));
+        }
+        if history.len() == max_steps {
+            return Ok(Search::Exhausted(current));
+        }
+        let next = step(&current)?;
+        seen_states.insert(&current, history.len());
+        history.push(current);
+        current = next;
+    }
+}
+
+/// Steps from `initial` until a state repeats. Fails if nothing repeats within `max_steps`.
+/// Nothing calls this yet: day08 numbers its states and uses `find_cycle_by_index`.
+#[allow(dead_code)]
+pub fn find_cycle<S: Clone + Eq + Hash>(
+    initial: S,
+    max_steps: usize,
+    step: impl FnMut(&S) -> Result<S>,
+) -> Result<Cycle<S>> {
+    match search(initial, max_steps, HashMap::new(), step)? {
+        Search::Found(cycle) => Ok(cycle),
+        Search::Exhausted(_) => Err(anyhow!("No cycle found within {} steps", max_steps)),
+    }
+}
+
+/// Like `find_cycle`, for when every state has its own index below `state_count`: seen states
+/// go in a flat table rather than a hash map. With only `state_count` possible states, a cycle
+/// always turns up.
+pub fn find_cycle_by_index<S>(
+    initial: S,
+    state_count: usize,
+    index_of: impl Fn(&S) -> usize,
+    mut step: impl FnMut(&S) -> Result<S>,
+) -> Result<Cycle<S>> {
+    let in_range = |state: &S| {
+        let index = index_of(state);
+        if index < state_count {
+            Ok(())
+        } else {
+            Err(anyhow!(
+                "State index {} out of range for {} states",
+                index,
+                state_count
+            ))
+        }
+    };
+    in_range(&initial)?;
+    let seen_states = IndexedStates {
+        first_seen: vec![None; state_count],
+        index_of: &index_of,
+    };
+    let checked_step = |state: &S| {
+        let next = step(state)?;
+        in_range(&next)?;
+        Ok(next)
+    };
+    match search(initial, state_count, seen_states, checked_step)? {
+        Search::Found(cycle) => Ok(cycle),
+        Search::Exhausted(_) => unreachable!("{} states can't all be different", state_count + 1),
+    }
+}
+
+/// The state after `n` steps from `initial`. Once a state repeats, the rest of the steps are
+/// skipped, so `n` can be in the billions as long as the cycle shows up early.
+pub fn state_after<S: Clone + Eq + Hash>(
+    initial: S,
+    n: usize,
+    step: impl FnMut(&S) -> Result<S>,
+) -> Result<S> {
+    match search(initial, n, HashMap::new(), step)? {
+        Search::Found(cycle) => Ok(cycle.state_after(n).clone()),
+        Search::Exhausted(state) => Ok(state),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// 0, 1, 2, 3, 4, 2, 3, 4, ...
+    fn step(state: &u32) -> Result<u32> {
+        Ok(if *state == 4 { 2 } else { state + 1 })
+    }
+
+    #[test]
+    fn test_find_cycle() {
+        let cycle = find_cycle(0, 100, step).unwrap();
+        assert_eq!(cycle.tail(), &[0, 1]);
+        assert_eq!(cycle.looping(), &[2, 3, 4]);
+        assert_eq!(cycle.tail_length, 2);
+        assert_eq!(cycle.period, 3);
+        assert_eq!(*cycle.state_after(1), 1);
+        assert_eq!(*cycle.state_after(5), 2);
+        assert_eq!(*cycle.state_after(1_000_000_000), 4);
+
+        assert!(find_cycle(0, 4, step).is_err());
+        assert!(find_cycle(0, 5, step).is_ok());
+    }
+
+    #[test]
+    fn test_find_cycle_by_index() {
+        let index_of = |state: &u32| *state as usize;
+        assert_eq!(
+            find_cycle_by_index(0, 5, index_of, step).unwrap(),
+            find_cycle(0, 100, step).unwrap()
+        );
+        assert!(find_cycle_by_index(0, 4, index_of, step).is_err());
+        assert!(find_cycle_by_index(7, 5, index_of, step).is_err());
+    }
+
+    #[test]
+    fn test_state_after() {
+        assert_eq!(state_after(0, 0, step).unwrap(), 0);
+        assert_eq!(state_after(0, 3, step).unwrap(), 3);
+        for n in 0..20 {
+            let brute_force = (0..n).fold(0, |state, _| step(&state).unwrap());
+            assert_eq!(state_after(0, n, step).unwrap(), brute_force, "n = {}", n);
+        }
+        assert_eq!(state_after(0, 1_000_000_000, step).unwrap(), 4);
+    }
+}
@@ -0,0 +1,198 @@
+//! Fuzzing for puzzle parsers. Starting from a corpus of known-good inputs (usually the examples),
+//! `check_parser` feeds a parser lots of randomly mangled variations and fails if it ever panics.
+//! Returning an error is fine; that's what malformed input should do. Like `property`, the runs
+//! are deterministic, and `FUZZ_SEED` and `FUZZ_CASES` change the seed and the number of inputs.
+
+use std::fmt::Debug;
+use std::panic::{self, AssertUnwindSafe};
+
+use crate::framework::prelude::*;
+use crate::framework::property::Rng;
+
+const DEFAULT_SEED: u64 = 0xf022;
+const DEFAULT_CASES: usize = 500;
+
+/// Inputs that trip up parsers in general, tried before any mutations.
+const EDGE_CASES: &[&str] = &["", "\n", "\n\n", " ", "\r\n", ":", "->", "0", "-", "é"];
+
+/// Characters that tend to matter to parsers: separators, signs, digits and the puzzles' symbols.
+const INTERESTING_CHARS: &[char] = &[
+    '\n', '\r', ' ', ':', ';', ',', '|', '-', '+', '>', '<', '=', '~', '(', ')', '{', '}', '#',
+    '.', '%', '&', '*', 'S', 'A', 'Z', 'x', '0', '1', '9', 'é',
+];
+
+/// Numbers that tend to overflow or divide by zero.
+const INTERESTING_NUMBERS: &[&str] = &[
+    "0",
+    "-1",
+    "255",
+    "65536",
+    "4294967296",
+    "18446744073709551616",
+    "99999999999999999999999999",
+];
+
+fn env_or<T: std::str::FromStr>(name: &str, default: T) -> T {
+    std::env::var(name)
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(default)
+}
+
+fn mutate(rng: &mut Rng, input: &str) -> String {
+    let mut chars = input.chars().collect_vec();
+    for _ in 0..rng.gen_range(1..=4) {
+        let len = chars.len();
+        let position = rng.gen_range(0..=len);
+        let span_end = rng.gen_range(position..=len.min(position + 8));
+        match rng.gen_range(0..=6) {
+            0 => {
+                chars.drain(position..span_end);
+            }
+            1 => chars.insert(position, *rng.choose(INTERESTING_CHARS)),
+            2 if position < len => chars[position] = *rng.choose(INTERESTING_CHARS),
+            3 => {
+                let span = chars[position..span_end].to_vec();
+                chars.splice(position..position, span);
+            }
+            4 => chars.truncate(position),
+            5 => {
+                let number = rng.choose(INTERESTING_NUMBERS);
+                chars.splice(position..span_end, number.chars());
+            }
+            _ => {
+                // shuffle whole lines around, since a lot of parsers care about line structure
+                let mut lines = chars
+                    .split(|c| *c == '\n')
+                    .map(<[char]>::to_vec)
+                    .collect_vec();
+                let from = rng.gen_range(0..=lines.len() - 1);
+                let line = lines.remove(from);
+                if rng.chance(1, 2) {
+                    let to = rng.gen_range(0..=lines.len());
+                    lines.insert(to, line);
+                }
+                chars = lines.join(&'\n');
+            }
+        }
+    }
+    chars.into_iter().collect()
+}
+
+fn panics<T>(parse: &impl Fn(&str) -> Result<T>, input: &str) -> bool {
+    panic::catch_unwind(AssertUnwindSafe(|| {
+        let _ = parse(input);
+    }))
+    .is_err()
+}
+
+/// Greedily removes lines, then characters, as long as the input still panics, so the report
+/// shows something small.
+fn shrink<T>(parse: &impl Fn(&str) -> Result<T>, input: &str) -> String {
+    let mut lines = input.split('\n').collect_vec();
+    let mut i = 0;
+    while i < lines.len() {
+        let mut candidate = lines.clone();
+        candidate.remove(i);
+        if panics(parse, &candidate.join("\n")) {
+            lines = candidate;
+        } else {
+            i += 1;
+        }
+    }
+    let mut chars = lines.join("\n").chars().collect_vec();
+    let mut i = 0;
+    while i < chars.len() {
+        let mut candidate = chars.clone();
+        candidate.remove(i);
+        if panics(parse, &candidate.iter().collect::<String>()) {
+            chars = candidate;
+        } else {
+            i += 1;
+        }
+    }
+    chars.into_iter().collect()
+}
+
+/// Panics (once, with a shrunk example) if `parse` panics on the corpus, on some edge cases, or
+/// on any of the mutated inputs.
+pub fn check_parser<T: Debug>(name: &str, corpus: &[&str], parse: impl Fn(&str) -> Result<T>) {
+    let seed = env_or("FUZZ_SEED", DEFAULT_SEED);
+    let cases = env_or("FUZZ_CASES", DEFAULT_CASES);
+    let mut rng = Rng::new(seed);
+
+    let fixed_inputs = corpus
+        .iter()
+        .chain(EDGE_CASES)
+        .map(|input| input.to_string());
+    let mutated_inputs = (0..cases)
+        .map(|_| {
+            let base = rng.choose(corpus).to_string();
+            mutate(&mut rng, &base)
+        })
+        .collect_vec();
+
+    let failure = fixed_inputs
+        .chain(mutated_inputs)
+        .find(|input| panics(&parse, input))
+        .map(|input| shrink(&parse, &input));
+
+    if let Some(input) = failure {
+        let message = panic::catch_unwind(AssertUnwindSafe(|| parse(&input)))
+            .err()
+            .and_then(|payload| {
+                payload
+                    .downcast_ref::<String>()
+                    .cloned()
+                    .or_else(|| payload.downcast_ref::<&str>().map(|it| it.to_string()))
+            })
+            .unwrap_or_default();
+        panic!(
+            "{} parser panicked (seed {:#x}): {}\ninput: {:?}",
+            name, seed, message, input
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn fragile_parse(input: &str) -> Result<u32> {
+        let numbers = input
+            .split(',')
+            .map(|number| number.trim().parse::<u32>())
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(numbers[0] / numbers[1])
+    }
+
+    #[test]
+    fn test_mutations_stay_close() {
+        let mut rng = Rng::new(1);
+        for _ in 0..100 {
+            let mutated = mutate(&mut rng, "1 2 3\n4 5 6");
+            assert!(mutated.chars().count() < 100, "{:?}", mutated);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "fragile parser panicked")]
+    fn test_finds_panics() {
+        check_parser("fragile", &["10, 2"], fragile_parse);
+    }
+
+    #[test]
+    fn test_shrink() {
+        assert_eq!(shrink(&fragile_parse, "1, 0"), "1,0");
+    }
+
+    #[test]
+    fn test_robust_parser() {
+        check_parser("robust", &["10, 2"], |input| {
+            input
+                .split(',')
+                .map(|number| Ok(number.trim().parse::<u32>()?))
+                .collect::<Result<Vec<_>>>()
+        });
+    }
+}
@@ -0,0 +1,694 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::fmt::Write;
+use std::hash::Hash;
+
+use crate::framework::prelude::*;
+
+/// Index of a node in a `Graph`. Nodes are never removed, so these stay valid.
+pub type NodeId = usize;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Edge<E> {
+    pub from: NodeId,
+    pub to: NodeId,
+    pub weight: E,
+}
+
+/// A directed graph stored as adjacency lists, tracking edges in both directions so that
+/// predecessors are as cheap to look up as successors.
+#[derive(Debug, Clone)]
+pub struct Graph<N, E = ()> {
+    nodes: Vec<N>,
+    edges: Vec<Edge<E>>,
+    /// Indices into `edges`, per node
+    outgoing: Vec<Vec<usize>>,
+    /// Indices into `edges`, per node
+    incoming: Vec<Vec<usize>>,
+}
+
+impl<N, E> Default for Graph<N, E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<N, E> Graph<N, E> {
+    pub fn new() -> Self {
+        Self {
+            nodes: vec![],
+            edges: vec![],
+            outgoing: vec![],
+            incoming: vec![],
+        }
+    }
+
+    pub fn add_node(&mut self, node: N) -> NodeId {
+        self.nodes.push(node);
+        self.outgoing.push(vec![]);
+        self.incoming.push(vec![]);
+        self.nodes.len() - 1
+    }
+
+    pub fn add_edge(&mut self, from: NodeId, to: NodeId, weight: E) {
+        self.outgoing[from].push(self.edges.len());
+        self.incoming[to].push(self.edges.len());
+        self.edges.push(Edge { from, to, weight });
+    }
+
+    /// Builds a graph from each node and its outgoing edges, like `a -> b, c`. Targets that never
+    /// appear as a source still get a node.
+    pub fn from_weighted_adjacency<T>(adjacency: impl IntoIterator<Item = (N, T)>) -> Self
+    where
+        N: Hash + Eq + Clone,
+        T: IntoIterator<Item = (N, E)>,
+    {
+        let mut graph = Self::new();
+        let mut ids = HashMap::<N, NodeId>::new();
+        let mut get_or_add = |graph: &mut Self, node: N| {
+            *ids.entry(node.clone())
+                .or_insert_with(|| graph.add_node(node))
+        };
+        for (node, targets) in adjacency {
+            let from = get_or_add(&mut graph, node);
+            for (target, weight) in targets {
+                let to = get_or_add(&mut graph, target);
+                graph.add_edge(from, to, weight);
+            }
+        }
+        graph
+    }
+
+    pub fn node(&self, id: NodeId) -> &N {
+        &self.nodes[id]
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn edge_count(&self) -> usize {
+        self.edges.len()
+    }
+
+    pub fn nodes(&self) -> impl Iterator<Item = (NodeId, &N)> + '_ {
+        self.nodes.iter().enumerate()
+    }
+
+    pub fn edges(&self) -> &[Edge<E>] {
+        &self.edges
+    }
+
+    /// The first node matching `predicate`. This is a linear scan.
+    pub fn find(&self, predicate: impl FnMut(&N) -> bool) -> Option<NodeId> {
+        self.nodes.iter().position(predicate)
+    }
+
+    pub fn out_edges(&self, id: NodeId) -> impl Iterator<Item = &Edge<E>> + '_ {
+        self.outgoing[id].iter().map(|&edge| &self.edges[edge])
+    }
+
+    pub fn in_edges(&self, id: NodeId) -> impl Iterator<Item = &Edge<E>> + '_ {
+        self.incoming[id].iter().map(|&edge| &self.edges[edge])
+    }
+
+    pub fn successors(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        self.out_edges(id).map(|edge| edge.to)
+    }
+
+    pub fn predecessors(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        self.in_edges(id).map(|edge| edge.from)
+    }
+
+    /// Kahn's algorithm. Fails if the graph has a cycle.
+    pub fn toposort(&self) -> Result<Vec<NodeId>> {
+        let mut in_degrees = self.incoming.iter().map(|edges| edges.len()).collect_vec();
+        let mut queue: VecDeque<NodeId> = (0..self.node_count())
+            .filter(|&id| in_degrees[id] == 0)
+            .collect();
+        let mut order = Vec::with_capacity(self.node_count());
+        while let Some(id) = queue.pop_front() {
+            order.push(id);
+            for successor in self.successors(id) {
+                in_degrees[successor] -= 1;
+                if in_degrees[successor] == 0 {
+                    queue.push_back(successor);
+                }
+            }
+        }
+        if order.len() != self.node_count() {
+            return Err(anyhow!(
+                "Graph has a cycle; only {} of {} nodes could be sorted",
+                order.len(),
+                self.node_count()
+            ));
+        }
+        Ok(order)
+    }
+
+    /// Tarjan's algorithm, without recursion so that long chains don't overflow the stack.
+    /// Components come out in reverse topological order: nothing in a component has an edge to a
+    /// component that comes after it.
+    pub fn strongly_connected_components(&self) -> Vec<Vec<NodeId>> {
+        struct Tarjan {
+            index: Vec<Option<usize>>,
+            lowlink: Vec<usize>,
+            on_stack: Vec<bool>,
+            stack: Vec<NodeId>,
+            /// (node, how many of its outgoing edges we've looked at)
+            call_stack: Vec<(NodeId, usize)>,
+            next_index: usize,
+        }
+
+        impl Tarjan {
+            fn visit(&mut self, node: NodeId) {
+                self.index[node] = Some(self.next_index);
+                self.lowlink[node] = self.next_index;
+                self.next_index += 1;
+                self.stack.push(node);
+                self.on_stack[node] = true;
+                self.call_stack.push((node, 0));
+            }
+        }
+
+        let node_count = self.node_count();
+        let mut tarjan = Tarjan {
+            index: vec![None; node_count],
+            lowlink: vec![0; node_count],
+            on_stack: vec![false; node_count],
+            stack: vec![],
+            call_stack: vec![],
+            next_index: 0,
+        };
+        let mut components = Vec::<Vec<NodeId>>::new();
+
+        for root in 0..node_count {
+            if tarjan.index[root].is_some() {
+                continue;
+            }
+            tarjan.visit(root);
+
+            while let Some(&mut (node, ref mut position)) = tarjan.call_stack.last_mut() {
+                if let Some(&edge) = self.outgoing[node].get(*position) {
+                    *position += 1;
+                    let next = self.edges[edge].to;
+                    match tarjan.index[next] {
+                        None => tarjan.visit(next),
+                        Some(next_index) if tarjan.on_stack[next] => {
+                            tarjan.lowlink[node] = tarjan.lowlink[node].min(next_index);
+                        }
+                        Some(_) => {}
+                    }
+                    continue;
+                }
+
+                tarjan.call_stack.pop();
+                if let Some(&(parent, _)) = tarjan.call_stack.last() {
+                    tarjan.lowlink[parent] = tarjan.lowlink[parent].min(tarjan.lowlink[node]);
+                }
+                if Some(tarjan.lowlink[node]) == tarjan.index[node] {
+                    let mut component = vec![];
+                    while let Some(member) = tarjan.stack.pop() {
+                        tarjan.on_stack[member] = false;
+                        component.push(member);
+                        if member == node {
+                            break;
+                        }
+                    }
+                    components.push(component);
+                }
+            }
+        }
+        components
+    }
+
+    /// Which nodes can be reached from any of `starts` (including the starts themselves),
+    /// indexed by `NodeId`.
+    pub fn reachable_from(&self, starts: impl IntoIterator<Item = NodeId>) -> Vec<bool> {
+        self.flood(starts, |id| self.successors(id).collect())
+    }
+
+    /// Which nodes can reach any of `targets` (including the targets themselves), indexed by
+    /// `NodeId`.
+    pub fn reaching(&self, targets: impl IntoIterator<Item = NodeId>) -> Vec<bool> {
+        self.flood(targets, |id| self.predecessors(id).collect())
+    }
+
+    fn flood(
+        &self,
+        starts: impl IntoIterator<Item = NodeId>,
+        next: impl Fn(NodeId) -> Vec<NodeId>,
+    ) -> Vec<bool> {
+        let mut visited = vec![false; self.node_count()];
+        let mut stack = starts.into_iter().collect_vec();
+        while let Some(id) = stack.pop() {
+            if visited[id] {
+                continue;
+            }
+            visited[id] = true;
+            stack.extend(next(id).into_iter().filter(|&next| !visited[next]));
+        }
+        visited
+    }
+
+    /// Nodes in reverse postorder of a depth-first search from `root`. Only reachable nodes are
+    /// included.
+    fn reverse_postorder(&self, root: NodeId) -> Vec<NodeId> {
+        let mut visited = vec![false; self.node_count()];
+        let mut postorder = vec![];
+        let mut call_stack = vec![(root, 0)];
+        visited[root] = true;
+        while let Some(frame) = call_stack.last_mut() {
+            let node = frame.0;
+            if let Some(&edge) = self.outgoing[node].get(frame.1) {
+                frame.1 += 1;
+                let next = self.edges[edge].to;
+                if !visited[next] {
+                    visited[next] = true;
+                    call_stack.push((next, 0));
+                }
+            } else {
+                call_stack.pop();
+                postorder.push(node);
+            }
+        }
+        postorder.reverse();
+        postorder
+    }
+
+    /// The dominator tree from `root`, using the Cooper-Harvey-Kennedy algorithm. A node `a`
+    /// dominates `b` if every path from the root to `b` goes through `a`.
+    pub fn dominators(&self, root: NodeId) -> Dominators {
+        let order = self.reverse_postorder(root);
+        let mut position = vec![usize::MAX; self.node_count()];
+        for (i, &node) in order.iter().enumerate() {
+            position[node] = i;
+        }
+        let mut idom: Vec<Option<NodeId>> = vec![None; self.node_count()];
+        idom[root] = Some(root);
+
+        let intersect = |idom: &[Option<NodeId>], mut a: NodeId, mut b: NodeId| {
+            while a != b {
+                while position[a] > position[b] {
+                    a = idom[a].unwrap();
+                }
+                while position[b] > position[a] {
+                    b = idom[b].unwrap();
+                }
+            }
+            a
+        };
+
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for &node in order.iter().skip(1) {
+                let new_idom = self
+                    .predecessors(node)
+                    .filter(|&predecessor| idom[predecessor].is_some())
+                    .reduce(|a, b| intersect(&idom, a, b));
+                if new_idom.is_some() && idom[node] != new_idom {
+                    idom[node] = new_idom;
+                    changed = true;
+                }
+            }
+        }
+        idom[root] = None;
+
+        Dominators { root, idom, order }
+    }
+
+    /// Dijkstra's algorithm. Returns the distance to every node from `start`, or `None` for
+    /// unreachable nodes.
+    pub fn shortest_paths(&self, start: NodeId, cost: impl Fn(&E) -> u64) -> Vec<Option<u64>> {
+        let mut distances: Vec<Option<u64>> = vec![None; self.node_count()];
+        let mut queue = BinaryHeap::new();
+        queue.push(Reverse((0, start)));
+        while let Some(Reverse((distance, id))) = queue.pop() {
+            if distances[id].is_some() {
+                continue;
+            }
+            distances[id] = Some(distance);
+            for edge in self.out_edges(id) {
+                if distances[edge.to].is_none() {
+                    queue.push(Reverse((distance + cost(&edge.weight), edge.to)));
+                }
+            }
+        }
+        distances
+    }
+}
+
+impl<N> Graph<N, ()> {
+    /// Like [`Graph::from_weighted_adjacency`], for graphs without edge weights.
+    pub fn from_adjacency<T>(adjacency: impl IntoIterator<Item = (N, T)>) -> Self
+    where
+        N: Hash + Eq + Clone,
+        T: IntoIterator<Item = N>,
+    {
+        Self::from_weighted_adjacency(
+            adjacency
+                .into_iter()
+                .map(|(node, targets)| (node, targets.into_iter().map(|target| (target, ())))),
+        )
+    }
+}
+
+/// Shapes that both Graphviz and Mermaid can draw, more or less.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeShape {
+    Box,
+    Rounded,
+    Circle,
+    DoubleCircle,
+    Diamond,
+    Hexagon,
+    /// Mermaid has no triangles, so this is an upside-down trapezoid there.
+    InvertedTriangle,
+    /// Just the label.
+    Plain,
+}
+
+impl NodeShape {
+    /// The `shape` (and `style`, if needed) attributes for DOT.
+    fn dot_attributes(&self) -> &'static str {
+        match self {
+            NodeShape::Box => "shape=box",
+            NodeShape::Rounded => "shape=box, style=rounded",
+            NodeShape::Circle => "shape=circle",
+            NodeShape::DoubleCircle => "shape=doublecircle",
+            NodeShape::Diamond => "shape=diamond",
+            NodeShape::Hexagon => "shape=hexagon",
+            NodeShape::InvertedTriangle => "shape=invtriangle",
+            NodeShape::Plain => "shape=plaintext",
+        }
+    }
+
+    /// The brackets that go around a node's label in Mermaid.
+    fn mermaid_brackets(&self) -> (&'static str, &'static str) {
+        match self {
+            NodeShape::Box => ("[", "]"),
+            NodeShape::Rounded => ("(", ")"),
+            NodeShape::Circle => ("((", "))"),
+            NodeShape::DoubleCircle => ("(((", ")))"),
+            NodeShape::Diamond => ("{", "}"),
+            NodeShape::Hexagon => ("{{", "}}"),
+            NodeShape::InvertedTriangle => ("[\\", "/]"),
+            NodeShape::Plain => (">", "]"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeStyle {
+    pub label: String,
+    pub shape: NodeShape,
+}
+
+impl NodeStyle {
+    pub fn new(label: impl Into<String>, shape: NodeShape) -> Self {
+        Self {
+            label: label.into(),
+            shape,
+        }
+    }
+}
+
+impl<N, E> Graph<N, E> {
+    /// Graphviz source, for rendering with something like `dot -Tsvg`. Nodes are named by their
+    /// `NodeId`, so labels can contain anything.
+    pub fn to_dot(
+        &self,
+        style: impl Fn(&N) -> NodeStyle,
+        edge_label: impl Fn(&E) -> Option<String>,
+    ) -> String {
+        let mut result = String::new();
+        writeln!(result, "digraph {{").unwrap();
+        for (id, node) in self.nodes() {
+            let NodeStyle { label, shape } = style(node);
+            writeln!(
+                result,
+                "  n{} [label={:?}, {}];",
+                id,
+                label,
+                shape.dot_attributes()
+            )
+            .unwrap();
+        }
+        for edge in self.edges.iter() {
+            match edge_label(&edge.weight) {
+                Some(label) => {
+                    writeln!(
+                        result,
+                        "  n{} -> n{} [label={:?}];",
+                        edge.from, edge.to, label
+                    )
+                }
+                None => writeln!(result, "  n{} -> n{};", edge.from, edge.to),
+            }
+            .unwrap();
+        }
+        writeln!(result, "}}").unwrap();
+        result
+    }
+
+    /// A Mermaid flowchart, with the same node and edge labels as [`Graph::to_dot`].
+    pub fn to_mermaid(
+        &self,
+        style: impl Fn(&N) -> NodeStyle,
+        edge_label: impl Fn(&E) -> Option<String>,
+    ) -> String {
+        let escape = |label: &str| label.replace('"', "#quot;");
+        let mut result = String::new();
+        writeln!(result, "graph TD;").unwrap();
+        for (id, node) in self.nodes() {
+            let NodeStyle { label, shape } = style(node);
+            let (open, close) = shape.mermaid_brackets();
+            writeln!(result, "  n{}{}\"{}\"{}", id, open, escape(&label), close).unwrap();
+        }
+        for edge in self.edges.iter() {
+            match edge_label(&edge.weight) {
+                Some(label) => writeln!(
+                    result,
+                    "  n{} -->|\"{}\"| n{}",
+                    edge.from,
+                    escape(&label),
+                    edge.to
+                ),
+                None => writeln!(result, "  n{} --> n{}", edge.from, edge.to),
+            }
+            .unwrap();
+        }
+        result
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dominators {
+    root: NodeId,
+    idom: Vec<Option<NodeId>>,
+    /// Reachable nodes, parents before children
+    order: Vec<NodeId>,
+}
+
+impl Dominators {
+    pub fn root(&self) -> NodeId {
+        self.root
+    }
+
+    /// `None` for the root, and for nodes that can't be reached from it.
+    pub fn immediate_dominator(&self, node: NodeId) -> Option<NodeId> {
+        self.idom[node]
+    }
+
+    /// Every node dominates itself.
+    pub fn dominates(&self, a: NodeId, b: NodeId) -> bool {
+        if !self.is_reachable(b) {
+            return false;
+        }
+        let mut current = Some(b);
+        while let Some(node) = current {
+            if node == a {
+                return true;
+            }
+            current = self.idom[node];
+        }
+        false
+    }
+
+    pub fn is_reachable(&self, node: NodeId) -> bool {
+        node == self.root || self.idom[node].is_some()
+    }
+
+    /// For each node, how many nodes it dominates, including itself. Unreachable nodes get 0.
+    pub fn subtree_sizes(&self) -> Vec<usize> {
+        let mut sizes = vec![0; self.idom.len()];
+        for &node in self.order.iter().rev() {
+            sizes[node] += 1;
+            if let Some(parent) = self.idom[node] {
+                sizes[parent] += sizes[node];
+            }
+        }
+        sizes
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn sample_graph() -> Graph<char> {
+        // a -> b -> c -> b, c -> d, a -> e -> d, f
+        Graph::from_adjacency([
+            ('a', vec!['b', 'e']),
+            ('b', vec!['c']),
+            ('c', vec!['b', 'd']),
+            ('e', vec!['d']),
+            ('f', vec![]),
+        ])
+    }
+
+    fn id(graph: &Graph<char>, label: char) -> NodeId {
+        graph.find(|&node| node == label).unwrap()
+    }
+
+    #[test]
+    fn test_build() {
+        let graph = sample_graph();
+        assert_eq!(graph.node_count(), 6);
+        assert_eq!(graph.edge_count(), 6);
+        let d = id(&graph, 'd');
+        let predecessors = graph
+            .predecessors(d)
+            .map(|node| *graph.node(node))
+            .sorted()
+            .collect_vec();
+        assert_eq!(predecessors, vec!['c', 'e']);
+    }
+
+    #[test]
+    fn test_toposort() {
+        let graph = sample_graph();
+        assert!(graph.toposort().is_err());
+
+        let acyclic =
+            Graph::from_adjacency([('a', vec!['c', 'b']), ('b', vec!['c']), ('d', vec![])]);
+        let order = acyclic
+            .toposort()
+            .unwrap()
+            .into_iter()
+            .map(|node| *acyclic.node(node))
+            .collect_vec();
+        assert_eq!(order, vec!['a', 'd', 'b', 'c']);
+    }
+
+    #[test]
+    fn test_strongly_connected_components() {
+        let graph = sample_graph();
+        let components = graph
+            .strongly_connected_components()
+            .into_iter()
+            .map(|component| {
+                component
+                    .into_iter()
+                    .map(|node| *graph.node(node))
+                    .sorted()
+                    .collect::<String>()
+            })
+            .collect_vec();
+        assert_eq!(components, vec!["d", "bc", "e", "a", "f"]);
+    }
+
+    #[test]
+    fn test_reachability() {
+        let graph = sample_graph();
+        let reachable = graph.reachable_from([id(&graph, 'b')]);
+        let reachable_labels: String = graph
+            .nodes()
+            .filter(|(node, _)| reachable[*node])
+            .map(|(_, label)| *label)
+            .collect();
+        assert_eq!(reachable_labels, "bcd");
+
+        let reaching = graph.reaching([id(&graph, 'e')]);
+        let reaching_labels: String = graph
+            .nodes()
+            .filter(|(node, _)| reaching[*node])
+            .map(|(_, label)| *label)
+            .collect();
+        assert_eq!(reaching_labels, "ae");
+    }
+
+    #[test]
+    fn test_dominators() {
+        let graph = sample_graph();
+        let [a, b, c, d, e, f] = ['a', 'b', 'c', 'd', 'e', 'f'].map(|label| id(&graph, label));
+        let dominators = graph.dominators(a);
+        assert_eq!(dominators.immediate_dominator(a), None);
+        assert_eq!(dominators.immediate_dominator(c), Some(b));
+        assert_eq!(dominators.immediate_dominator(d), Some(a));
+        assert_eq!(dominators.immediate_dominator(f), None);
+        assert!(dominators.dominates(b, c));
+        assert!(!dominators.dominates(e, d));
+        assert!(!dominators.dominates(a, f));
+        let sizes = dominators.subtree_sizes();
+        assert_eq!(
+            [a, b, c, d, e, f].map(|node| sizes[node]),
+            [5, 2, 1, 1, 1, 0]
+        );
+    }
+
+    #[test]
+    fn test_shortest_paths() {
+        let graph = Graph::from_weighted_adjacency([
+            ('a', vec![('b', 7), ('c', 2)]),
+            ('c', vec![('b', 3)]),
+            ('b', vec![('d', 1)]),
+        ]);
+        let distances = graph.shortest_paths(graph.find(|&node| node == 'a').unwrap(), |&w| w);
+        let by_label: HashMap<char, Option<u64>> = graph
+            .nodes()
+            .map(|(node, label)| (*label, distances[node]))
+            .collect();
+        assert_eq!(by_label[&'b'], Some(5));
+        assert_eq!(by_label[&'d'], Some(6));
+    }
+
+    #[test]
+    fn test_exports() {
+        let graph = Graph::from_weighted_adjacency([
+            ("a", vec![("b \"quoted\"", 'L')]),
+            ("b \"quoted\"", vec![("a", 'R')]),
+        ]);
+        let style = |node: &&str| {
+            let shape = if *node == "a" {
+                NodeShape::DoubleCircle
+            } else {
+                NodeShape::InvertedTriangle
+            };
+            NodeStyle::new(*node, shape)
+        };
+        let edge_label = |direction: &char| Some(direction.to_string());
+        assert_eq!(
+            graph.to_dot(style, edge_label),
+            indoc! {r#"
+                digraph {
+                  n0 [label="a", shape=doublecircle];
+                  n1 [label="b \"quoted\"", shape=invtriangle];
+                  n0 -> n1 [label="L"];
+                  n1 -> n0 [label="R"];
+                }
+            "#}
+        );
+        assert_eq!(
+            graph.to_mermaid(style, |_| None),
+            indoc! {r#"
+                graph TD;
+                  n0((("a")))
+                  n1[\"b #quot;quoted#quot;"/]
+                  n0 --> n1
+                  n1 --> n0
+            "#}
+        );
+    }
+}
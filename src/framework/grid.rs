@@ -1,31 +1,92 @@
 use crate::framework::prelude::*;
-use std::ops::{Add, AddAssign, Mul};
+use std::ops::{Add, AddAssign, Mul, Sub};
 
 /// Value can be used as a u8 bitmask
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 pub enum Direction {
-    North = 0b0001,
-    South = 0b0010,
-    East = 0b0100,
-    West = 0b1000,
+    North = 0b0000_0001,
+    South = 0b0000_0010,
+    East = 0b0000_0100,
+    West = 0b0000_1000,
+    NorthEast = 0b0001_0000,
+    NorthWest = 0b0010_0000,
+    SouthEast = 0b0100_0000,
+    SouthWest = 0b1000_0000,
 }
 
+#[allow(dead_code)]
 impl Direction {
+    pub const CARDINALS: [Direction; 4] = [
+        Direction::North,
+        Direction::East,
+        Direction::South,
+        Direction::West,
+    ];
+
+    /// Every direction, clockwise starting from north.
+    pub const ALL: [Direction; 8] = [
+        Direction::North,
+        Direction::NorthEast,
+        Direction::East,
+        Direction::SouthEast,
+        Direction::South,
+        Direction::SouthWest,
+        Direction::West,
+        Direction::NorthWest,
+    ];
+
     pub fn opposite(self) -> Self {
         match self {
             Direction::North => Direction::South,
             Direction::South => Direction::North,
             Direction::East => Direction::West,
             Direction::West => Direction::East,
+            Direction::NorthEast => Direction::SouthWest,
+            Direction::NorthWest => Direction::SouthEast,
+            Direction::SouthEast => Direction::NorthWest,
+            Direction::SouthWest => Direction::NorthEast,
         }
     }
 
+    pub fn is_cardinal(self) -> bool {
+        matches!(
+            self,
+            Direction::North | Direction::South | Direction::East | Direction::West
+        )
+    }
+
+    /// Rotates clockwise by `steps` eighths of a turn. Negative steps rotate counter-clockwise.
+    pub fn rotate_45(self, steps: isize) -> Self {
+        let index = Direction::ALL
+            .iter()
+            .position(|&direction| direction == self)
+            .unwrap() as isize;
+        Direction::ALL[(index + steps).rem_euclid(8) as usize]
+    }
+
+    /// Rotates clockwise by `steps` quarter turns. Negative steps rotate counter-clockwise.
+    pub fn rotate_90(self, steps: isize) -> Self {
+        self.rotate_45(steps * 2)
+    }
+
+    pub fn turn_right(self) -> Self {
+        self.rotate_90(1)
+    }
+
+    pub fn turn_left(self) -> Self {
+        self.rotate_90(-1)
+    }
+
     pub fn as_vector(&self) -> IntVector {
         match self {
             Direction::North => NORTH,
             Direction::South => SOUTH,
             Direction::East => EAST,
             Direction::West => WEST,
+            Direction::NorthEast => NORTH + EAST,
+            Direction::NorthWest => NORTH + WEST,
+            Direction::SouthEast => SOUTH + EAST,
+            Direction::SouthWest => SOUTH + WEST,
         }
     }
 }
@@ -40,13 +101,10 @@ impl TryFrom<IntVector> for Direction {
     type Error = anyhow::Error;
 
     fn try_from(vector: IntVector) -> Result<Self> {
-        match vector {
-            NORTH => Ok(Direction::North),
-            SOUTH => Ok(Direction::South),
-            EAST => Ok(Direction::East),
-            WEST => Ok(Direction::West),
-            _ => Err(anyhow!("invalid direction vector: {:?}", vector)),
-        }
+        Direction::ALL
+            .into_iter()
+            .find(|direction| direction.as_vector() == vector)
+            .ok_or_else(|| anyhow!("invalid direction vector: {:?}", vector))
     }
 }
 
@@ -61,6 +119,7 @@ pub const SOUTH: IntVector = IntVector::new(0, 1);
 pub const EAST: IntVector = IntVector::new(1, 0);
 pub const WEST: IntVector = IntVector::new(-1, 0);
 
+#[allow(dead_code)]
 impl IntVector {
     pub const fn new(x: isize, y: isize) -> Self {
         Self { x, y }
@@ -84,6 +143,33 @@ impl IntVector {
         ]
     }
 
+    /// All eight surrounding coordinates, including diagonals.
+    pub fn all_neighbors(self) -> Vec<Self> {
+        Direction::ALL
+            .iter()
+            .map(|direction| self + direction.as_vector())
+            .collect()
+    }
+
+    pub fn all_neighbors_with_directions(self) -> Vec<(Self, Direction)> {
+        Direction::ALL
+            .iter()
+            .map(|&direction| (self + direction.as_vector(), direction))
+            .collect()
+    }
+
+    /// Rotates clockwise around the origin by `steps` quarter turns.
+    /// Negative steps rotate counter-clockwise.
+    pub fn rotate_90(&self, steps: isize) -> IntVector {
+        match steps.rem_euclid(4) {
+            0 => *self,
+            // y points south, so clockwise takes north (0, -1) to east (1, 0)
+            1 => IntVector::new(-self.y, self.x),
+            2 => self.inverse(),
+            _ => IntVector::new(self.y, -self.x),
+        }
+    }
+
     pub fn inverse(&self) -> IntVector {
         IntVector::new(-self.x, -self.y)
     }
@@ -104,6 +190,17 @@ impl Add for IntVector {
     }
 }
 
+impl Sub for IntVector {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self {
+            x: self.x - rhs.x,
+            y: self.y - rhs.y,
+        }
+    }
+}
+
 impl AddAssign for IntVector {
     fn add_assign(&mut self, rhs: Self) {
         *self = *self + rhs;
@@ -140,6 +237,9 @@ impl GridShape {
     pub fn parse_char_grid(input: &str) -> Result<(GridShape, Box<[char]>)> {
         let lines = input.lines().collect::<Vec<_>>();
         let width = lines.first().ok_or(anyhow!("empty grid"))?.chars().count();
+        if width == 0 {
+            return Err(anyhow!("empty first line in grid"));
+        }
         let chars: Vec<char> = lines
             .iter()
             .map(|line| {
@@ -196,6 +296,81 @@ impl GridShape {
     }
 }
 
+/// A dense grid of cells, stored row by row.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct Grid<T> {
+    pub shape: GridShape,
+    pub cells: Box<[T]>,
+}
+
+#[allow(dead_code)]
+impl<T> Grid<T> {
+    pub fn new(shape: GridShape, cells: Box<[T]>) -> Result<Self> {
+        if cells.len() != shape.area() {
+            return Err(anyhow!(
+                "expected {} cells for a {}x{} grid, got {}",
+                shape.area(),
+                shape.width,
+                shape.height,
+                cells.len()
+            ));
+        }
+        Ok(Grid { shape, cells })
+    }
+
+    pub fn filled(shape: GridShape, value: T) -> Self
+    where
+        T: Clone,
+    {
+        let cells = vec![value; shape.area()].into_boxed_slice();
+        Grid { shape, cells }
+    }
+
+    pub fn parse_char_grid(input: &str, parse_cell: impl FnMut(char) -> Result<T>) -> Result<Self> {
+        let (shape, chars) = GridShape::parse_char_grid(input)?;
+        let cells = chars
+            .iter()
+            .copied()
+            .map(parse_cell)
+            .collect::<Result<_>>()?;
+        Ok(Grid { shape, cells })
+    }
+
+    /// Returns `None` if out of bounds.
+    pub fn get(&self, coord: IntVector) -> Option<&T> {
+        if self.shape.in_bounds(coord) {
+            Some(&self.cells[self.shape.arr_index(coord)])
+        } else {
+            None
+        }
+    }
+
+    /// Panics if out of bounds.
+    pub fn set(&mut self, coord: IntVector, value: T) {
+        let index = self.shape.arr_index(coord);
+        self.cells[index] = value;
+    }
+
+    /// Iterates over every cell with its coordinate, left to right, top to bottom.
+    pub fn iter(&self) -> impl Iterator<Item = (IntVector, &T)> + '_ {
+        self.cells
+            .iter()
+            .enumerate()
+            .map(|(index, cell)| (self.shape.coordinate_for_index(index), cell))
+    }
+
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> Grid<U> {
+        Grid {
+            shape: self.shape.clone(),
+            cells: self.cells.iter().map(f).collect(),
+        }
+    }
+
+    pub fn format_char_grid(&self, to_char: impl FnMut(&T) -> char) -> String {
+        self.shape.format_char_grid(self.cells.iter().map(to_char))
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
 pub struct SignedGridShape {
     pub top_left: IntVector,
@@ -224,6 +399,44 @@ impl SignedGridShape {
         })
     }
 
+    /// A shape containing only the given coordinate.
+    pub fn from_point(coord: IntVector) -> Self {
+        SignedGridShape::new(coord, coord)
+    }
+
+    pub fn width(&self) -> usize {
+        (self.bottom_right.x - self.top_left.x + 1) as usize
+    }
+
+    pub fn height(&self) -> usize {
+        (self.bottom_right.y - self.top_left.y + 1) as usize
+    }
+
+    pub fn area(&self) -> usize {
+        self.width() * self.height()
+    }
+
+    /// Grows the shape (if necessary) so that `coord` is in bounds.
+    pub fn expand_to_include(&mut self, coord: IntVector) {
+        self.top_left.x = self.top_left.x.min(coord.x);
+        self.top_left.y = self.top_left.y.min(coord.y);
+        self.bottom_right.x = self.bottom_right.x.max(coord.x);
+        self.bottom_right.y = self.bottom_right.y.max(coord.y);
+    }
+
+    /// Panics if coordinate is out of bounds.
+    pub fn arr_index(&self, coord: IntVector) -> usize {
+        self.as_grid_shape().arr_index(coord - self.top_left)
+    }
+
+    /// The equivalent unsigned shape, with `top_left` moved to the origin.
+    pub fn as_grid_shape(&self) -> GridShape {
+        GridShape {
+            width: self.width(),
+            height: self.height(),
+        }
+    }
+
     pub fn format_char_grid(&self, chars: impl IntoIterator<Item = char>) -> String {
         let mut result = String::new();
         let mut chars = chars.into_iter().peekable();
@@ -245,6 +458,35 @@ impl SignedGridShape {
 mod test {
     use super::*;
 
+    #[test]
+    fn test_direction_rotation() {
+        assert_eq!(Direction::North.turn_right(), Direction::East);
+        assert_eq!(Direction::North.turn_left(), Direction::West);
+        assert_eq!(Direction::NorthEast.rotate_45(3), Direction::South);
+        assert_eq!(Direction::SouthWest.rotate_90(-3), Direction::NorthWest);
+        assert_eq!(Direction::NorthWest.opposite(), Direction::SouthEast);
+        for direction in Direction::ALL {
+            assert_eq!(
+                Direction::try_from(direction.as_vector()).unwrap(),
+                direction
+            );
+            assert_eq!(
+                direction.as_vector().rotate_90(1),
+                direction.turn_right().as_vector()
+            );
+        }
+        assert!(Direction::try_from(IntVector::new(2, 0)).is_err());
+    }
+
+    #[test]
+    fn test_all_neighbors() {
+        let neighbors = IntVector::new(0, 0).all_neighbors();
+        assert_eq!(neighbors.len(), 8);
+        assert!(neighbors.contains(&IntVector::new(-1, -1)));
+        assert!(neighbors.contains(&IntVector::new(1, 1)));
+        assert!(!neighbors.contains(&IntVector::new(0, 0)));
+    }
+
     #[test]
     fn test_manhattan_distance() {
         assert_eq!(
@@ -256,4 +498,20 @@ mod test {
             15
         );
     }
+
+    #[test]
+    fn test_parse_char_grid() {
+        let (shape, chars) = GridShape::parse_char_grid("ab\ncd\n").unwrap();
+        assert_eq!(
+            shape,
+            GridShape {
+                width: 2,
+                height: 2
+            }
+        );
+        assert_eq!(chars.iter().collect::<String>(), "abcd");
+        assert!(GridShape::parse_char_grid("").is_err());
+        assert!(GridShape::parse_char_grid("\nab").is_err());
+        assert!(GridShape::parse_char_grid("ab\nc").is_err());
+    }
 }
@@ -0,0 +1,300 @@
+use crate::framework::grid::{GridShape, IntVector, SignedGridShape};
+use crate::framework::prelude::*;
+use std::ops::{Add, AddAssign, Mul, RangeInclusive, Sub};
+use std::str::FromStr;
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Axis3 {
+    X,
+    Y,
+    Z,
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct IntVector3 {
+    pub x: isize,
+    pub y: isize,
+    pub z: isize,
+}
+
+impl IntVector3 {
+    pub const fn new(x: isize, y: isize, z: isize) -> Self {
+        Self { x, y, z }
+    }
+
+    pub fn get(&self, axis: Axis3) -> isize {
+        match axis {
+            Axis3::X => self.x,
+            Axis3::Y => self.y,
+            Axis3::Z => self.z,
+        }
+    }
+
+    pub fn with(&self, axis: Axis3, value: isize) -> Self {
+        let mut result = *self;
+        match axis {
+            Axis3::X => result.x = value,
+            Axis3::Y => result.y = value,
+            Axis3::Z => result.z = value,
+        }
+        result
+    }
+
+    pub fn manhattan_distance(&self, other: Self) -> usize {
+        ((self.x - other.x).abs() + (self.y - other.y).abs() + (self.z - other.z).abs()) as usize
+    }
+
+    fn component_min(self, other: Self) -> Self {
+        Self::new(
+            self.x.min(other.x),
+            self.y.min(other.y),
+            self.z.min(other.z),
+        )
+    }
+
+    fn component_max(self, other: Self) -> Self {
+        Self::new(
+            self.x.max(other.x),
+            self.y.max(other.y),
+            self.z.max(other.z),
+        )
+    }
+}
+
+impl Add for IntVector3 {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self::new(self.x + rhs.x, self.y + rhs.y, self.z + rhs.z)
+    }
+}
+
+impl AddAssign for IntVector3 {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub for IntVector3 {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self::new(self.x - rhs.x, self.y - rhs.y, self.z - rhs.z)
+    }
+}
+
+impl Mul<isize> for IntVector3 {
+    type Output = Self;
+
+    fn mul(self, rhs: isize) -> Self::Output {
+        Self::new(self.x * rhs, self.y * rhs, self.z * rhs)
+    }
+}
+
+/// Parses `x,y,z`
+impl FromStr for IntVector3 {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let parts = s
+            .split(',')
+            .map(|part| part.trim().parse::<isize>().map_err(|err| anyhow!(err)))
+            .collect::<Result<Vec<_>>>()?;
+        if parts.len() != 3 {
+            return Err(anyhow!("Expected 3 parts, got {}", parts.len()));
+        }
+        Ok(Self::new(parts[0], parts[1], parts[2]))
+    }
+}
+
+/// An axis-aligned box of voxels. Both corners are inclusive.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct IntBox3 {
+    pub min: IntVector3,
+    pub max: IntVector3,
+}
+
+impl IntBox3 {
+    /// The corners can be given in any order.
+    pub fn from_corners(a: IntVector3, b: IntVector3) -> Self {
+        Self {
+            min: a.component_min(b),
+            max: a.component_max(b),
+        }
+    }
+
+    pub fn range(&self, axis: Axis3) -> RangeInclusive<isize> {
+        self.min.get(axis)..=self.max.get(axis)
+    }
+
+    /// The number of voxels along the given axis.
+    pub fn length(&self, axis: Axis3) -> usize {
+        (self.max.get(axis) - self.min.get(axis) + 1) as usize
+    }
+
+    pub fn volume(&self) -> usize {
+        self.length(Axis3::X) * self.length(Axis3::Y) * self.length(Axis3::Z)
+    }
+
+    pub fn contains(&self, point: IntVector3) -> bool {
+        [Axis3::X, Axis3::Y, Axis3::Z]
+            .into_iter()
+            .all(|axis| self.range(axis).contains(&point.get(axis)))
+    }
+
+    pub fn intersection(&self, other: &Self) -> Option<Self> {
+        let min = self.min.component_max(other.min);
+        let max = self.max.component_min(other.max);
+        if min.x > max.x || min.y > max.y || min.z > max.z {
+            None
+        } else {
+            Some(Self { min, max })
+        }
+    }
+
+    pub fn intersects(&self, other: &Self) -> bool {
+        self.intersection(other).is_some()
+    }
+
+    pub fn translate(&self, offset: IntVector3) -> Self {
+        Self {
+            min: self.min + offset,
+            max: self.max + offset,
+        }
+    }
+
+    /// Iterates over every voxel in the box, in x, then y, then z order.
+    pub fn voxels(&self) -> impl Iterator<Item = IntVector3> + '_ {
+        self.range(Axis3::Z).flat_map(move |z| {
+            self.range(Axis3::Y)
+                .flat_map(move |y| self.range(Axis3::X).map(move |x| IntVector3::new(x, y, z)))
+        })
+    }
+}
+
+/// A plane spanned by two axes, used to flatten 3D space into a 2D grid.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct AxisPlane {
+    /// Becomes `x` on the 2D plane.
+    pub horizontal: Axis3,
+    /// Becomes `y` on the 2D plane.
+    pub vertical: Axis3,
+    /// If true, larger `vertical` coordinates render towards the top, like a side view.
+    pub vertical_up: bool,
+}
+
+impl AxisPlane {
+    /// A side view, with `horizontal` along the bottom and `z` pointing up.
+    pub fn side_view(horizontal: Axis3) -> Self {
+        AxisPlane {
+            horizontal,
+            vertical: Axis3::Z,
+            vertical_up: true,
+        }
+    }
+
+    pub fn project_point(&self, point: IntVector3) -> IntVector {
+        IntVector::new(point.get(self.horizontal), point.get(self.vertical))
+    }
+
+    /// The area that the box covers on the plane, in unflipped plane coordinates.
+    pub fn project_box(&self, b: &IntBox3) -> SignedGridShape {
+        SignedGridShape::new(self.project_point(b.min), self.project_point(b.max))
+    }
+
+    /// A shape covering everything from the origin to the far side of every box.
+    /// Boxes with negative coordinates on the plane are clipped.
+    pub fn shape_containing<'a>(&self, boxes: impl IntoIterator<Item = &'a IntBox3>) -> GridShape {
+        let (width, height) = boxes.into_iter().fold((0, 0), |(width, height), b| {
+            (
+                width.max(b.max.get(self.horizontal) + 1),
+                height.max(b.max.get(self.vertical) + 1),
+            )
+        });
+        GridShape {
+            width: width.max(0) as usize,
+            height: height.max(0) as usize,
+        }
+    }
+
+    /// Converts a grid coordinate within `shape` to its coordinate on the plane,
+    /// accounting for `vertical_up`.
+    pub fn plane_coord(&self, shape: &GridShape, grid_coord: IntVector) -> IntVector {
+        if self.vertical_up {
+            IntVector::new(grid_coord.x, shape.height as isize - grid_coord.y - 1)
+        } else {
+            grid_coord
+        }
+    }
+
+    /// Renders each labeled box on the plane. Empty cells are `.`, and cells covered by more
+    /// than one box are `?`.
+    pub fn format_char_grid(&self, boxes: &[(IntBox3, char)]) -> String {
+        let shape = self.shape_containing(boxes.iter().map(|(b, _)| b));
+        let footprints = boxes
+            .iter()
+            .map(|(b, label)| (self.project_box(b), *label))
+            .collect_vec();
+        shape.format_char_grid(shape.coord_iter().map(|grid_coord| {
+            let coord = self.plane_coord(&shape, grid_coord);
+            let mut covering = footprints
+                .iter()
+                .filter(|(footprint, _)| footprint.is_in_bounds(coord));
+            match (covering.next(), covering.next()) {
+                (None, _) => '.',
+                (Some((_, label)), None) => *label,
+                (Some(_), Some(_)) => '?',
+            }
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_arithmetic() {
+        let a: IntVector3 = "1,2,3".parse().unwrap();
+        let b = IntVector3::new(-1, 0, 5);
+        assert_eq!(a + b, IntVector3::new(0, 2, 8));
+        assert_eq!(a - b, IntVector3::new(2, 2, -2));
+        assert_eq!(a * 2, IntVector3::new(2, 4, 6));
+        assert_eq!(a.manhattan_distance(b), 6);
+        assert!("1,2".parse::<IntVector3>().is_err());
+    }
+
+    #[test]
+    fn test_box_intersection_and_volume() {
+        let a = IntBox3::from_corners(IntVector3::new(2, 0, 0), IntVector3::new(0, 2, 2));
+        let b = IntBox3::from_corners(IntVector3::new(1, 1, 1), IntVector3::new(5, 5, 5));
+        assert_eq!(a.volume(), 27);
+        assert_eq!(
+            a.intersection(&b),
+            Some(IntBox3::from_corners(
+                IntVector3::new(1, 1, 1),
+                IntVector3::new(2, 2, 2)
+            ))
+        );
+        assert_eq!(a.voxels().count(), a.volume());
+        assert!(a.voxels().all(|voxel| a.contains(voxel)));
+        let c = b.translate(IntVector3::new(10, 0, 0));
+        assert!(!a.intersects(&c));
+    }
+
+    #[test]
+    fn test_side_view() {
+        let boxes = [
+            (
+                IntBox3::from_corners(IntVector3::new(0, 0, 1), IntVector3::new(2, 0, 1)),
+                'A',
+            ),
+            (
+                IntBox3::from_corners(IntVector3::new(1, 0, 2), IntVector3::new(1, 0, 3)),
+                'B',
+            ),
+        ];
+        let rendered = AxisPlane::side_view(Axis3::X).format_char_grid(&boxes);
+        assert_eq!(rendered, ".B.\n.B.\nAAA\n...\n");
+    }
+}
@@ -0,0 +1,88 @@
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// Hands out dense ids (0, 1, 2, ...) to values in the order they're first seen, so that
+/// everything else about them can live in plain `Vec`s indexed by id instead of hash maps.
+#[derive(Debug, Clone)]
+pub struct Interner<T> {
+    ids: HashMap<T, usize>,
+    values: Vec<T>,
+}
+
+impl<T> Default for Interner<T> {
+    fn default() -> Self {
+        Interner {
+            ids: HashMap::new(),
+            values: Vec::new(),
+        }
+    }
+}
+
+impl<T: Hash + Eq + Clone> Interner<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The value's id, giving it the next one if it doesn't have one yet.
+    pub fn intern(&mut self, value: T) -> usize {
+        if let Some(&id) = self.ids.get(&value) {
+            return id;
+        }
+        let id = self.values.len();
+        self.ids.insert(value.clone(), id);
+        self.values.push(value);
+        id
+    }
+
+    pub fn get<Q>(&self, value: &Q) -> Option<usize>
+    where
+        T: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.ids.get(value).copied()
+    }
+
+    /// Panics if `id` didn't come from this interner.
+    pub fn resolve(&self, id: usize) -> &T {
+        &self.values[id]
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Every value with its id, in id order.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &T)> + '_ {
+        self.values.iter().enumerate()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::framework::prelude::*;
+
+    #[test]
+    fn test_intern() {
+        let mut interner = Interner::new();
+        assert_eq!(interner.intern("b".to_string()), 0);
+        assert_eq!(interner.intern("a".to_string()), 1);
+        assert_eq!(interner.intern("b".to_string()), 0);
+        assert_eq!(interner.len(), 2);
+        assert_eq!(interner.get("a"), Some(1));
+        assert_eq!(interner.get("c"), None);
+        assert_eq!(interner.resolve(0), "b");
+        assert_eq!(
+            interner
+                .iter()
+                .map(|(id, value)| (id, value.as_str()))
+                .collect_vec(),
+            vec![(0, "b"), (1, "a")]
+        );
+    }
+}
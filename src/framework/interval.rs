@@ -0,0 +1,356 @@
+use std::fmt::Display;
+use std::ops::{Range, RangeInclusive};
+
+use num::PrimInt;
+
+use crate::framework::prelude::*;
+
+/// A half-open span of integers, `start..end`. Unlike `RangeInclusive`, an empty interval is easy
+/// to represent, and lengths never need a `+ 1`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Interval<T> {
+    pub start: T,
+    pub end: T,
+}
+
+impl<T: PrimInt> Interval<T> {
+    /// An inverted interval (`end < start`) is treated as empty.
+    pub fn new(start: T, end: T) -> Self {
+        Self { start, end }
+    }
+
+    pub fn empty() -> Self {
+        Self::new(T::zero(), T::zero())
+    }
+
+    /// Fails if the interval would extend past `T::max_value()`.
+    pub fn from_start_length(start: T, length: T) -> Result<Self>
+    where
+        T: Display,
+    {
+        let end = start
+            .checked_add(&length)
+            .ok_or_else(|| anyhow!("Interval starting at {} overflows", start))?;
+        Ok(Self::new(start, end))
+    }
+
+    /// Fails if the range includes `T::max_value()`, since that can't be expressed as an
+    /// exclusive end.
+    pub fn from_inclusive(range: &RangeInclusive<T>) -> Result<Self> {
+        if range.is_empty() {
+            return Ok(Self::empty());
+        }
+        let end = range
+            .end()
+            .checked_add(&T::one())
+            .ok_or_else(|| anyhow!("Inclusive range ends at the maximum value"))?;
+        Ok(Self::new(*range.start(), end))
+    }
+
+    /// `None` if the interval is empty.
+    pub fn to_inclusive(self) -> Option<RangeInclusive<T>> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(self.start..=(self.end - T::one()))
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.end <= self.start
+    }
+
+    pub fn len(&self) -> T {
+        if self.is_empty() {
+            T::zero()
+        } else {
+            self.end - self.start
+        }
+    }
+
+    pub fn contains(&self, value: T) -> bool {
+        self.start <= value && value < self.end
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        Self::new(self.start.max(other.start), self.end.min(other.end))
+    }
+
+    pub fn intersects(&self, other: &Self) -> bool {
+        !self.intersection(other).is_empty()
+    }
+
+    /// Splits into everything below `point`, and everything at or above it. Either half may be
+    /// empty.
+    pub fn split_at(&self, point: T) -> (Self, Self) {
+        let point = point.max(self.start).min(self.end.max(self.start));
+        (
+            Self::new(self.start, point),
+            Self::new(point, self.end.max(self.start)),
+        )
+    }
+}
+
+impl<T: PrimInt> From<Range<T>> for Interval<T> {
+    fn from(range: Range<T>) -> Self {
+        Self::new(range.start, range.end)
+    }
+}
+
+/// A set of integers stored as sorted, disjoint intervals. Touching intervals are merged, so two
+/// sets with the same members always compare equal.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct IntervalSet<T> {
+    intervals: Vec<Interval<T>>,
+}
+
+impl<T: PrimInt> Default for IntervalSet<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: PrimInt> IntervalSet<T> {
+    pub fn new() -> Self {
+        Self { intervals: vec![] }
+    }
+
+    /// The normalized intervals, in ascending order.
+    pub fn intervals(&self) -> &[Interval<T>] {
+        &self.intervals
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.intervals.is_empty()
+    }
+
+    /// The number of integers in the set.
+    pub fn len(&self) -> T {
+        self.intervals
+            .iter()
+            .fold(T::zero(), |sum, interval| sum + interval.len())
+    }
+
+    pub fn min(&self) -> Option<T> {
+        self.intervals.first().map(|interval| interval.start)
+    }
+
+    pub fn contains(&self, value: T) -> bool {
+        let index = self
+            .intervals
+            .partition_point(|interval| interval.end <= value);
+        self.intervals
+            .get(index)
+            .is_some_and(|interval| interval.contains(value))
+    }
+
+    pub fn insert(&mut self, interval: Interval<T>) {
+        if interval.is_empty() {
+            return;
+        }
+        self.intervals.push(interval);
+        self.normalize();
+    }
+
+    /// Sorts the intervals and merges any that overlap or touch.
+    fn normalize(&mut self) {
+        self.intervals.retain(|interval| !interval.is_empty());
+        self.intervals
+            .sort_unstable_by_key(|interval| interval.start);
+        let mut merged: Vec<Interval<T>> = Vec::with_capacity(self.intervals.len());
+        for interval in self.intervals.drain(..) {
+            match merged.last_mut() {
+                Some(last) if interval.start <= last.end => last.end = last.end.max(interval.end),
+                _ => merged.push(interval),
+            }
+        }
+        self.intervals = merged;
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        self.intervals
+            .iter()
+            .chain(other.intervals.iter())
+            .copied()
+            .collect()
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        self.intervals
+            .iter()
+            .cartesian_product(other.intervals.iter())
+            .map(|(a, b)| a.intersection(b))
+            .collect()
+    }
+
+    /// Everything in `self` that isn't in `other`.
+    pub fn difference(&self, other: &Self) -> Self {
+        let mut remaining = self.intervals.clone();
+        for removed in other.intervals.iter() {
+            remaining = remaining
+                .into_iter()
+                .flat_map(|interval| {
+                    let (below, rest) = interval.split_at(removed.start);
+                    let (_, above) = rest.split_at(removed.end);
+                    [below, above]
+                })
+                .collect();
+        }
+        remaining.into_iter().collect()
+    }
+
+    /// Splits into everything below `point`, and everything at or above it.
+    pub fn split_at(&self, point: T) -> (Self, Self) {
+        let (below, above): (Vec<_>, Vec<_>) = self
+            .intervals
+            .iter()
+            .map(|interval| interval.split_at(point))
+            .unzip();
+        (below.into_iter().collect(), above.into_iter().collect())
+    }
+}
+
+impl<T: PrimInt> FromIterator<Interval<T>> for IntervalSet<T> {
+    fn from_iter<I: IntoIterator<Item = Interval<T>>>(iter: I) -> Self {
+        let mut set = Self {
+            intervals: iter.into_iter().collect(),
+        };
+        set.normalize();
+        set
+    }
+}
+
+/// An axis-aligned box with an interval along each of `N` dimensions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct IntervalBox<T, const N: usize> {
+    pub axes: [Interval<T>; N],
+}
+
+impl<T: PrimInt, const N: usize> IntervalBox<T, N> {
+    pub fn new(axes: [Interval<T>; N]) -> Self {
+        Self { axes }
+    }
+
+    /// A box with the same interval along every axis.
+    pub fn cube(interval: Interval<T>) -> Self {
+        Self::new([interval; N])
+    }
+
+    pub fn get(&self, axis: usize) -> Interval<T> {
+        self.axes[axis]
+    }
+
+    pub fn with(&self, axis: usize, interval: Interval<T>) -> Self {
+        let mut result = *self;
+        result.axes[axis] = interval;
+        result
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.axes.iter().any(|interval| interval.is_empty())
+    }
+
+    /// The number of points in the box. Widened to `u128` since this grows fast.
+    pub fn volume(&self) -> u128 {
+        self.axes
+            .iter()
+            .map(|interval| interval.len().to_u128().unwrap())
+            .product()
+    }
+
+    pub fn contains(&self, point: [T; N]) -> bool {
+        self.axes
+            .iter()
+            .zip(point)
+            .all(|(interval, value)| interval.contains(value))
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        let mut result = *self;
+        for (interval, other) in result.axes.iter_mut().zip(other.axes.iter()) {
+            *interval = interval.intersection(other);
+        }
+        result
+    }
+
+    /// Splits along one axis into everything below `point`, and everything at or above it.
+    pub fn split_at(&self, axis: usize, point: T) -> (Self, Self) {
+        let (below, above) = self.axes[axis].split_at(point);
+        (self.with(axis, below), self.with(axis, above))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn set(intervals: &[(u64, u64)]) -> IntervalSet<u64> {
+        intervals
+            .iter()
+            .map(|&(start, end)| Interval::new(start, end))
+            .collect()
+    }
+
+    #[test]
+    fn test_interval() {
+        let interval = Interval::from_start_length(10u64, 5).unwrap();
+        assert_eq!(interval.len(), 5);
+        assert_eq!(interval.to_inclusive(), Some(10..=14));
+        assert_eq!(Interval::from_inclusive(&(10..=14)).unwrap(), interval);
+        assert!(Interval::from_inclusive(&(0..=u8::MAX)).is_err());
+        assert!(Interval::from_start_length(u8::MAX, 1).is_err());
+        assert_eq!(
+            Interval::<i64>::from_start_length(-1, i64::MIN)
+                .unwrap_err()
+                .to_string(),
+            "Interval starting at -1 overflows"
+        );
+        assert_eq!(
+            interval.split_at(12),
+            (Interval::new(10, 12), Interval::new(12, 15))
+        );
+        assert!(interval.split_at(20).1.is_empty());
+        assert!(interval.split_at(0).0.is_empty());
+        assert_eq!(Interval::new(5u64, 3).len(), 0);
+    }
+
+    #[test]
+    fn test_normalize() {
+        let set = set(&[(10, 12), (0, 3), (3, 5), (11, 20), (30, 30)]);
+        assert_eq!(
+            set.intervals(),
+            &[Interval::new(0, 5), Interval::new(10, 20)]
+        );
+        assert_eq!(set.len(), 15);
+        assert_eq!(set.min(), Some(0));
+        assert!(set.contains(4));
+        assert!(!set.contains(5));
+        assert!(set.contains(19));
+    }
+
+    #[test]
+    fn test_set_operations() {
+        let a = set(&[(0, 10), (20, 30)]);
+        let b = set(&[(5, 25)]);
+        assert_eq!(a.union(&b), set(&[(0, 30)]));
+        assert_eq!(a.intersection(&b), set(&[(5, 10), (20, 25)]));
+        assert_eq!(a.difference(&b), set(&[(0, 5), (25, 30)]));
+        assert_eq!(b.difference(&a), set(&[(10, 20)]));
+        assert_eq!(
+            a.split_at(22),
+            (set(&[(0, 10), (20, 22)]), set(&[(22, 30)]))
+        );
+    }
+
+    #[test]
+    fn test_box() {
+        let full = IntervalBox::<u16, 4>::cube(Interval::new(1, 4001));
+        assert_eq!(full.volume(), 4000u128.pow(4));
+        let (below, above) = full.split_at(2, 1000);
+        assert_eq!(below.volume() + above.volume(), full.volume());
+        assert_eq!(below.get(2), Interval::new(1, 1000));
+        assert!(below.contains([4000, 4000, 999, 1]));
+        assert!(!below.contains([4000, 4000, 1000, 1]));
+        assert!(below.intersection(&above).is_empty());
+    }
+}
@@ -1,7 +1,34 @@
 use std::time::Duration;
 
+#[allow(dead_code)]
+pub mod aho_corasick;
+#[allow(dead_code)]
+pub mod bit_grid;
+#[allow(dead_code)]
+pub mod compression;
+pub mod cycle;
+#[cfg(test)]
+pub mod fuzz;
+#[allow(dead_code)]
+pub mod graph;
 pub mod grid;
+#[allow(dead_code)]
+pub mod grid3;
+#[allow(dead_code)]
+pub mod interner;
+#[allow(dead_code)]
+pub mod interval;
+#[allow(dead_code)]
+pub mod memo;
+pub mod parse;
+#[allow(dead_code)]
+pub mod polygon;
 pub mod prelude;
+#[cfg(test)]
+pub mod property;
+#[allow(dead_code)]
+pub mod sparse_grid;
+pub mod table;
 pub mod try_block;
 
 pub trait Day {
@@ -15,6 +42,17 @@ pub trait Day {
     fn run_script(&self, _name: &str) -> anyhow::Result<bool> {
         Ok(false)
     }
+
+    /// Like `run_script`, for scripts that take arguments. By default scripts don't.
+    fn run_script_with_args(&self, name: &str, args: &[String]) -> anyhow::Result<bool> {
+        if !args.is_empty() {
+            return Err(anyhow::anyhow!(
+                "\"{}\" script doesn't take arguments",
+                name
+            ));
+        }
+        self.run_script(name)
+    }
 }
 
 pub fn format_duration(input: &Duration) -> String {
@@ -0,0 +1,196 @@
+//! Small helpers for the shapes that keep showing up in puzzle inputs. Errors from `lines` and
+//! `Section` say which line of the input they came from.
+
+use std::str::FromStr;
+
+use anyhow::Context;
+
+use crate::framework::prelude::*;
+
+/// Parses whitespace-separated numbers, like `41 48 83 86 17`.
+pub fn numbers<T>(s: &str) -> Result<Vec<T>>
+where
+    T: FromStr,
+    T::Err: Into<Error>,
+{
+    s.split_whitespace()
+        .map(|number| {
+            number
+                .parse::<T>()
+                .map_err(Into::into)
+                .with_context(|| format!("Invalid number: {:?}", number))
+        })
+        .collect()
+}
+
+/// Parses every line with `parse_line`, adding the line number to any error. Line numbers start
+/// at 1.
+pub fn lines<T>(input: &str, parse_line: impl FnMut(&str) -> Result<T>) -> Result<Vec<T>> {
+    lines_from(input, 1, parse_line)
+}
+
+fn lines_from<T>(
+    input: &str,
+    first_line: usize,
+    mut parse_line: impl FnMut(&str) -> Result<T>,
+) -> Result<Vec<T>> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(i, line)| {
+            parse_line(line).with_context(|| format!("line {}: {:?}", first_line + i, line))
+        })
+        .collect()
+}
+
+/// A chunk of input between blank lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Section<'a> {
+    /// The line number of the first line in this section, starting at 1.
+    pub first_line: usize,
+    pub text: &'a str,
+}
+
+impl<'a> Section<'a> {
+    /// Like [`lines`], but with line numbers relative to the whole input.
+    pub fn lines<T>(&self, parse_line: impl FnMut(&str) -> Result<T>) -> Result<Vec<T>> {
+        lines_from(self.text, self.first_line, parse_line)
+    }
+
+    pub fn parse<T>(&self) -> Result<T>
+    where
+        T: FromStr,
+        T::Err: Into<Error>,
+    {
+        self.text
+            .parse::<T>()
+            .map_err(Into::into)
+            .with_context(|| format!("section starting at line {}", self.first_line))
+    }
+}
+
+/// Splits the input on blank lines. Runs of blank lines count as one separator, and `\r\n` line
+/// endings are handled.
+pub fn sections(input: &str) -> Vec<Section<'_>> {
+    let mut sections = vec![];
+    let mut current: Option<(usize, usize, usize)> = None;
+    let mut offset = 0;
+    for (i, line) in input.split_inclusive('\n').enumerate() {
+        let content_end = offset + line.trim_end_matches(['\r', '\n']).len();
+        if line.trim().is_empty() {
+            if let Some((first_line, start, end)) = current.take() {
+                sections.push(Section {
+                    first_line,
+                    text: &input[start..end],
+                });
+            }
+        } else {
+            match &mut current {
+                Some((_, _, end)) => *end = content_end,
+                None => current = Some((i + 1, offset, content_end)),
+            }
+        }
+        offset += line.len();
+    }
+    if let Some((first_line, start, end)) = current {
+        sections.push(Section {
+            first_line,
+            text: &input[start..end],
+        });
+    }
+    sections
+}
+
+/// Parses `name: value`, checking the name and returning the trimmed value.
+pub fn labeled<'a>(line: &'a str, name: &str) -> Result<&'a str> {
+    let (label, value) = label_value(line)?;
+    if label != name {
+        return Err(anyhow!("Expected {:?}, found {:?}", name, label));
+    }
+    Ok(value)
+}
+
+/// Splits `name: value` on the first colon, trimming both sides.
+pub fn label_value(line: &str) -> Result<(&str, &str)> {
+    let (label, value) = line
+        .split_once(':')
+        .ok_or_else(|| anyhow!("Expected \"name: value\", found {:?}", line))?;
+    Ok((label.trim(), value.trim()))
+}
+
+/// Parses an edge list like `label -> a, b, c`.
+pub fn edges(line: &str) -> Result<(&str, Vec<&str>)> {
+    let (label, targets) = line
+        .split_once("->")
+        .ok_or_else(|| anyhow!("Expected \"label -> a, b\", found {:?}", line))?;
+    let targets = targets
+        .split(',')
+        .map(str::trim)
+        .filter(|target| !target.is_empty())
+        .collect();
+    Ok((label.trim(), targets))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_numbers() {
+        assert_eq!(numbers::<i32>(" 0 -3  6 ").unwrap(), vec![0, -3, 6]);
+        assert!(numbers::<u8>("1 300").is_err());
+    }
+
+    #[test]
+    fn test_sections() {
+        let input = "a\r\nb\r\n\r\n\r\nc\nd\n\ne\n";
+        let sections = sections(input);
+        assert_eq!(
+            sections,
+            vec![
+                Section {
+                    first_line: 1,
+                    text: "a\r\nb"
+                },
+                Section {
+                    first_line: 5,
+                    text: "c\nd"
+                },
+                Section {
+                    first_line: 8,
+                    text: "e"
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_line_numbers_in_errors() {
+        let input = indoc! {"
+            1 2 3
+
+            4 5
+            6 x
+        "};
+        let error = sections(input)[1]
+            .lines(numbers::<u32>)
+            .unwrap_err()
+            .to_string();
+        assert_eq!(error, "line 4: \"6 x\"");
+
+        let error = lines(input, numbers::<u32>).unwrap_err();
+        assert_eq!(
+            format!("{:#}", error),
+            "line 4: \"6 x\": Invalid number: \"x\": invalid digit found in string"
+        );
+    }
+
+    #[test]
+    fn test_labels_and_edges() {
+        assert_eq!(labeled("Time:  7 15", "Time").unwrap(), "7 15");
+        assert!(labeled("Distance: 9", "Time").is_err());
+        assert_eq!(label_value("Game 1: 3 blue").unwrap(), ("Game 1", "3 blue"));
+        assert_eq!(edges("%a -> b, inv").unwrap(), ("%a", vec!["b", "inv"]));
+        assert!(edges("a, b").is_err());
+    }
+}
@@ -0,0 +1,305 @@
+use std::cmp::{Ordering, Reverse};
+
+use num::Integer;
+
+use crate::framework::grid::{Direction, IntVector};
+use crate::framework::prelude::*;
+
+/// A closed polygon on the integer lattice. The last vertex connects back to the first.
+///
+/// Every measurement is computed with checked `i128` arithmetic, so huge coordinates produce an
+/// error instead of silently wrapping.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Polygon {
+    vertices: Vec<IntVector>,
+}
+
+impl Polygon {
+    pub fn new(vertices: Vec<IntVector>) -> Self {
+        Polygon { vertices }
+    }
+
+    /// Traces a path from the origin, like a list of dig instructions. Zero-length steps are
+    /// skipped. Fails if the path doesn't end where it started.
+    pub fn from_steps(steps: impl IntoIterator<Item = (Direction, usize)>) -> Result<Self> {
+        let mut vertices = vec![];
+        let mut position = IntVector::new(0, 0);
+        for (direction, length) in steps {
+            if length == 0 {
+                continue;
+            }
+            vertices.push(position);
+            let length = isize::try_from(length)?;
+            let vector = direction.as_vector();
+            position = vector
+                .x
+                .checked_mul(length)
+                .and_then(|x| position.x.checked_add(x))
+                .zip(
+                    vector
+                        .y
+                        .checked_mul(length)
+                        .and_then(|y| position.y.checked_add(y)),
+                )
+                .map(|(x, y)| IntVector::new(x, y))
+                .ok_or_else(|| {
+                    anyhow!(
+                        "Overflow stepping {:?} {} from {:?}",
+                        direction,
+                        length,
+                        position
+                    )
+                })?;
+        }
+        if position != IntVector::new(0, 0) {
+            return Err(anyhow!("Path ends at {:?} instead of the origin", position));
+        }
+        Ok(Polygon { vertices })
+    }
+
+    pub fn vertices(&self) -> &[IntVector] {
+        &self.vertices
+    }
+
+    /// Each edge as `(start, end)`, including the closing edge.
+    pub fn edges(&self) -> impl Iterator<Item = (IntVector, IntVector)> + '_ {
+        self.vertices
+            .iter()
+            .copied()
+            .zip(self.vertices.iter().copied().cycle().skip(1))
+    }
+
+    /// Twice the signed area, via the shoelace formula. Doubling keeps the result an integer even
+    /// with diagonal edges. Positive when the vertices run clockwise as drawn, with y pointing
+    /// south.
+    pub fn twice_signed_area(&self) -> Result<i128> {
+        self.edges().try_fold(0i128, |sum, (a, b)| {
+            let (a, b) = (widen(a), widen(b));
+            (a.0 * b.1)
+                .checked_sub(b.0 * a.1)
+                .and_then(|cross| sum.checked_add(cross))
+                .ok_or_else(|| anyhow!("Overflow computing polygon area"))
+        })
+    }
+
+    pub fn twice_area(&self) -> Result<u128> {
+        Ok(self.twice_signed_area()?.unsigned_abs())
+    }
+
+    /// The number of lattice points on the boundary. When every edge follows a `Direction`, this
+    /// is also the number of steps it takes to walk the boundary.
+    pub fn boundary_length(&self) -> Result<u128> {
+        self.edges().try_fold(0u128, |sum, (a, b)| {
+            let (a, b) = (widen(a), widen(b));
+            let dx = (b.0 - a.0).unsigned_abs();
+            let dy = (b.1 - a.1).unsigned_abs();
+            sum.checked_add(dx.gcd(&dy))
+                .ok_or_else(|| anyhow!("Overflow computing polygon boundary"))
+        })
+    }
+
+    /// The number of lattice points strictly inside the polygon, via Pick's theorem. Only
+    /// meaningful for simple polygons; see [`Polygon::is_simple`].
+    pub fn interior_points(&self) -> Result<u128> {
+        // A = I + B/2 - 1, so 2I = 2A - B + 2
+        let twice_interior = (self.twice_area()? + 2)
+            .checked_sub(self.boundary_length()?)
+            .ok_or_else(|| anyhow!("Boundary is longer than the area allows; is it simple?"))?;
+        Ok(twice_interior / 2)
+    }
+
+    /// Interior and boundary points together, i.e. every tile the polygon covers when vertices
+    /// sit at tile centers.
+    pub fn enclosed_points(&self) -> Result<u128> {
+        self.interior_points()?
+            .checked_add(self.boundary_length()?)
+            .ok_or_else(|| anyhow!("Overflow counting enclosed points"))
+    }
+
+    /// True if no two edges touch, other than neighboring edges meeting at their shared vertex.
+    /// Degenerate polygons (fewer than 3 vertices or zero-length edges) aren't simple.
+    pub fn is_simple(&self) -> bool {
+        let edges = self
+            .edges()
+            .map(|(a, b)| (widen(a), widen(b)))
+            .collect_vec();
+        if edges.len() < 3 || edges.iter().any(|(a, b)| a == b) {
+            return false;
+        }
+        let last = edges.len() - 1;
+        for (i, j) in (0..edges.len()).tuple_combinations() {
+            let (a, b) = edges[i];
+            let (c, d) = edges[j];
+            let touching = if j == i + 1 {
+                // b == c; only a problem if the second edge doubles back over the first
+                on_segment(a, b, d) || on_segment(c, d, a)
+            } else if i == 0 && j == last {
+                // d == a
+                on_segment(a, b, c) || on_segment(c, d, b)
+            } else {
+                segments_intersect(a, b, c, d)
+            };
+            if touching {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+type Point = (i128, i128);
+
+fn widen(vector: IntVector) -> Point {
+    (vector.x as i128, vector.y as i128)
+}
+
+/// The sign of the cross product of `b - a` and `c - a`. Differences between `isize` coordinates
+/// can be close to 2^64, so the products are compared by magnitude rather than subtracted.
+fn orientation(a: Point, b: Point, c: Point) -> i128 {
+    let left = signed_product(b.0 - a.0, c.1 - a.1);
+    let right = signed_product(b.1 - a.1, c.0 - a.0);
+    match left.cmp(&right) {
+        Ordering::Less => -1,
+        Ordering::Equal => 0,
+        Ordering::Greater => 1,
+    }
+}
+
+/// A product that orders correctly, without the overflow of multiplying in `i128`.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum SignedProduct {
+    Negative(Reverse<u128>),
+    Zero,
+    Positive(u128),
+}
+
+fn signed_product(a: i128, b: i128) -> SignedProduct {
+    let magnitude = a.unsigned_abs() * b.unsigned_abs();
+    match (a.signum() * b.signum()).cmp(&0) {
+        Ordering::Less => SignedProduct::Negative(Reverse(magnitude)),
+        Ordering::Equal => SignedProduct::Zero,
+        Ordering::Greater => SignedProduct::Positive(magnitude),
+    }
+}
+
+fn on_segment(a: Point, b: Point, point: Point) -> bool {
+    orientation(a, b, point) == 0
+        && (a.0.min(b.0)..=a.0.max(b.0)).contains(&point.0)
+        && (a.1.min(b.1)..=a.1.max(b.1)).contains(&point.1)
+}
+
+fn segments_intersect(a: Point, b: Point, c: Point, d: Point) -> bool {
+    let abc = orientation(a, b, c);
+    let abd = orientation(a, b, d);
+    let cda = orientation(c, d, a);
+    let cdb = orientation(c, d, b);
+    if abc * abd < 0 && cda * cdb < 0 {
+        return true;
+    }
+    on_segment(a, b, c) || on_segment(a, b, d) || on_segment(c, d, a) || on_segment(c, d, b)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn square(size: isize) -> Polygon {
+        Polygon::new(vec![
+            IntVector::new(0, 0),
+            IntVector::new(size, 0),
+            IntVector::new(size, size),
+            IntVector::new(0, size),
+        ])
+    }
+
+    #[test]
+    fn test_square() {
+        let polygon = square(4);
+        assert_eq!(polygon.twice_signed_area().unwrap(), 32);
+        assert_eq!(polygon.boundary_length().unwrap(), 16);
+        assert_eq!(polygon.interior_points().unwrap(), 9);
+        assert_eq!(polygon.enclosed_points().unwrap(), 25);
+        assert!(polygon.is_simple());
+
+        let mut reversed = polygon.vertices().to_vec();
+        reversed.reverse();
+        assert_eq!(Polygon::new(reversed).twice_signed_area().unwrap(), -32);
+    }
+
+    #[test]
+    fn test_from_steps() {
+        use Direction::*;
+        let polygon =
+            Polygon::from_steps([(East, 2), (SouthWest, 2), (North, 0), (North, 2)]).unwrap();
+        assert_eq!(
+            polygon.vertices(),
+            &[
+                IntVector::new(0, 0),
+                IntVector::new(2, 0),
+                IntVector::new(0, 2)
+            ]
+        );
+        assert_eq!(polygon.twice_area().unwrap(), 4);
+        assert_eq!(polygon.boundary_length().unwrap(), 6);
+        assert_eq!(polygon.interior_points().unwrap(), 0);
+        assert!(Polygon::from_steps([(East, 2), (North, 2)]).is_err());
+    }
+
+    #[test]
+    fn test_is_simple() {
+        let bowtie = Polygon::new(vec![
+            IntVector::new(0, 0),
+            IntVector::new(2, 2),
+            IntVector::new(2, 0),
+            IntVector::new(0, 2),
+        ]);
+        assert!(!bowtie.is_simple());
+
+        let touching_vertex = Polygon::new(vec![
+            IntVector::new(0, 0),
+            IntVector::new(2, 0),
+            IntVector::new(1, 1),
+            IntVector::new(2, 2),
+            IntVector::new(0, 2),
+            IntVector::new(1, 1),
+        ]);
+        assert!(!touching_vertex.is_simple());
+
+        let doubles_back = Polygon::new(vec![
+            IntVector::new(0, 0),
+            IntVector::new(2, 0),
+            IntVector::new(1, 0),
+        ]);
+        assert!(!doubles_back.is_simple());
+
+        let notched = Polygon::new(vec![
+            IntVector::new(0, 0),
+            IntVector::new(4, 0),
+            IntVector::new(4, 4),
+            IntVector::new(2, 1),
+            IntVector::new(0, 4),
+        ]);
+        assert!(notched.is_simple());
+    }
+
+    #[test]
+    fn test_overflow() {
+        let huge = square(isize::MAX);
+        assert_eq!(
+            huge.twice_area().unwrap(),
+            2 * (isize::MAX as u128 * isize::MAX as u128)
+        );
+        let wide_bowtie = Polygon::new(vec![
+            IntVector::new(isize::MIN, isize::MIN),
+            IntVector::new(isize::MAX, isize::MAX),
+            IntVector::new(isize::MAX, isize::MIN),
+            IntVector::new(isize::MIN, isize::MAX),
+        ]);
+        assert!(!wide_bowtie.is_simple());
+        assert!(Polygon::from_steps([
+            (Direction::East, usize::MAX / 2),
+            (Direction::East, usize::MAX / 2),
+        ])
+        .is_err());
+    }
+}
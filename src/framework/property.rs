@@ -0,0 +1,141 @@
+//! A small property-testing harness. `check` generates a few hundred random inputs and fails with
+//! the seed and the offending input if a property doesn't hold for one of them. The runs are
+//! deterministic; set `PROPERTY_SEED` to try a different set of inputs, and `PROPERTY_CASES` to
+//! change how many are tried.
+
+use std::fmt::Debug;
+use std::ops::RangeInclusive;
+
+use num::PrimInt;
+
+use crate::framework::prelude::*;
+
+const DEFAULT_SEED: u64 = 0x2023_1225;
+const DEFAULT_CASES: usize = 200;
+/// Cases start small and grow up to this size over the run.
+pub const MAX_SIZE: usize = 10;
+
+/// SplitMix64. Not suitable for anything but tests, but tiny and good enough to shake out bugs.
+#[derive(Debug, Clone)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A uniformly random value in `range`. Panics if the range is empty.
+    pub fn gen_range<T: PrimInt>(&mut self, range: RangeInclusive<T>) -> T {
+        let (start, end) = range.into_inner();
+        assert!(start <= end, "can't pick from an empty range");
+        let start = start.to_i128().unwrap();
+        let span = (end.to_i128().unwrap() - start) as u128 + 1;
+        let offset = (((self.next_u64() as u128) << 64) | self.next_u64() as u128) % span;
+        T::from(start + offset as i128).unwrap()
+    }
+
+    /// True with probability `numerator / denominator`.
+    pub fn chance(&mut self, numerator: u64, denominator: u64) -> bool {
+        self.gen_range(1..=denominator) <= numerator
+    }
+
+    /// Panics if `items` is empty.
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.gen_range(0..=items.len() - 1)]
+    }
+}
+
+fn env_or<T: std::str::FromStr>(name: &str, default: T) -> T {
+    std::env::var(name)
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(default)
+}
+
+/// Each case gets its own generator, so cases don't depend on how much earlier ones used.
+fn case_seed(seed: u64, case: usize) -> u64 {
+    Rng::new(seed ^ (case as u64).rotate_left(32)).next_u64()
+}
+
+/// Checks `property` against inputs from `generate`, which gets a size between 1 and `MAX_SIZE`
+/// to scale the input by. Panics on the first input that fails.
+pub fn check<T: Debug>(
+    name: &str,
+    generate: impl Fn(&mut Rng, usize) -> T,
+    property: impl Fn(&T) -> Result<()>,
+) {
+    let seed = env_or("PROPERTY_SEED", DEFAULT_SEED);
+    let cases = env_or("PROPERTY_CASES", DEFAULT_CASES);
+    for case in 0..cases {
+        let size = 1 + case * MAX_SIZE / cases.max(1);
+        let input = generate(&mut Rng::new(case_seed(seed, case)), size.min(MAX_SIZE));
+        if let Err(error) = property(&input) {
+            panic!(
+                "property {:?} failed on case {} of seed {:#x}: {:#}\ninput: {:#?}",
+                name, case, seed, error, input
+            );
+        }
+    }
+}
+
+/// Fails with both values if they differ, for use inside properties.
+pub fn ensure_eq<T: PartialEq + Debug>(left: T, right: T, context: &str) -> Result<()> {
+    if left == right {
+        Ok(())
+    } else {
+        Err(anyhow!("{}: {:?} != {:?}", context, left, right))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_gen_range() {
+        let mut rng = Rng::new(1);
+        for _ in 0..1000 {
+            let value = rng.gen_range(-3i8..=3);
+            assert!((-3..=3).contains(&value));
+        }
+        assert_eq!(rng.gen_range(u64::MAX..=u64::MAX), u64::MAX);
+        let seen = (0..1000).map(|_| rng.gen_range(0u8..=4)).unique().count();
+        assert_eq!(seen, 5);
+    }
+
+    #[test]
+    fn test_check_passes() {
+        check(
+            "reversing twice",
+            |rng, size| (0..size).map(|_| rng.gen_range(0u32..=100)).collect_vec(),
+            |list| {
+                let twice = list.iter().rev().rev().copied().collect_vec();
+                ensure_eq(&twice, list, "reversed twice")
+            },
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "property \"sums are small\" failed")]
+    fn test_check_fails() {
+        check(
+            "sums are small",
+            |rng, size| (0..size).map(|_| rng.gen_range(0u32..=100)).collect_vec(),
+            |list| {
+                if list.iter().sum::<u32>() < 300 {
+                    Ok(())
+                } else {
+                    Err(anyhow!("too big"))
+                }
+            },
+        );
+    }
+}
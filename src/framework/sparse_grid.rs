@@ -0,0 +1,213 @@
+use std::collections::HashMap;
+
+use crate::framework::grid::{Grid, GridShape, IntVector, SignedGridShape};
+
+/// A grid with unbounded (and possibly negative) coordinates, where only occupied cells take up
+/// memory. The bounding shape grows automatically as cells are set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SparseGrid<T> {
+    cells: HashMap<IntVector, T>,
+    bounds: Option<SignedGridShape>,
+}
+
+impl<T> Default for SparseGrid<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> SparseGrid<T> {
+    pub fn new() -> Self {
+        SparseGrid {
+            cells: HashMap::new(),
+            bounds: None,
+        }
+    }
+
+    pub fn get(&self, coord: IntVector) -> Option<&T> {
+        self.cells.get(&coord)
+    }
+
+    pub fn get_mut(&mut self, coord: IntVector) -> Option<&mut T> {
+        self.cells.get_mut(&coord)
+    }
+
+    pub fn contains(&self, coord: IntVector) -> bool {
+        self.cells.contains_key(&coord)
+    }
+
+    /// Returns the previous value of the cell, if any.
+    pub fn set(&mut self, coord: IntVector, value: T) -> Option<T> {
+        match &mut self.bounds {
+            Some(bounds) => bounds.expand_to_include(coord),
+            None => self.bounds = Some(SignedGridShape::from_point(coord)),
+        }
+        self.cells.insert(coord, value)
+    }
+
+    /// Returns the removed value, if any. The bounds shrink if the cell was on the edge.
+    pub fn remove(&mut self, coord: IntVector) -> Option<T> {
+        let removed = self.cells.remove(&coord)?;
+        let on_edge = self.bounds.as_ref().is_some_and(|bounds| {
+            coord.x == bounds.top_left.x
+                || coord.y == bounds.top_left.y
+                || coord.x == bounds.bottom_right.x
+                || coord.y == bounds.bottom_right.y
+        });
+        if on_edge {
+            self.recalculate_bounds();
+        }
+        Some(removed)
+    }
+
+    fn recalculate_bounds(&mut self) {
+        self.bounds = self.cells.keys().fold(None, |bounds, coord| {
+            let mut bounds = bounds.unwrap_or_else(|| SignedGridShape::from_point(*coord));
+            bounds.expand_to_include(*coord);
+            Some(bounds)
+        });
+    }
+
+    /// The smallest shape containing every set cell, or `None` if the grid is empty.
+    pub fn bounds(&self) -> Option<&SignedGridShape> {
+        self.bounds.as_ref()
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    /// Iterates over set cells in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (IntVector, &T)> + '_ {
+        self.cells.iter().map(|(coord, cell)| (*coord, cell))
+    }
+
+    /// Renders every cell within the bounds, using `empty` for cells that aren't set.
+    pub fn format_char_grid(&self, empty: char, mut to_char: impl FnMut(&T) -> char) -> String {
+        let bounds = match &self.bounds {
+            Some(bounds) => bounds,
+            None => return String::new(),
+        };
+        bounds.format_char_grid(bounds.coord_iter().map(|coord| match self.get(coord) {
+            Some(cell) => to_char(cell),
+            None => empty,
+        }))
+    }
+
+    /// Converts to a dense grid covering `bounds()`, so the dense grid's origin is the top left
+    /// of the bounds.
+    pub fn to_dense(&self) -> Grid<Option<T>>
+    where
+        T: Clone,
+    {
+        let bounds = match &self.bounds {
+            Some(bounds) => bounds,
+            None => {
+                return Grid::filled(
+                    GridShape {
+                        width: 0,
+                        height: 0,
+                    },
+                    None,
+                )
+            }
+        };
+        Grid {
+            shape: bounds.as_grid_shape(),
+            cells: bounds
+                .coord_iter()
+                .map(|coord| self.get(coord).cloned())
+                .collect(),
+        }
+    }
+
+    /// Sets a cell for every `Some` in the dense grid, with the dense grid's origin at `offset`.
+    pub fn from_dense(grid: &Grid<Option<T>>, offset: IntVector) -> Self
+    where
+        T: Clone,
+    {
+        let mut sparse = SparseGrid::new();
+        for (coord, cell) in grid.iter() {
+            if let Some(cell) = cell {
+                sparse.set(coord + offset, cell.clone());
+            }
+        }
+        sparse
+    }
+}
+
+impl<T> FromIterator<(IntVector, T)> for SparseGrid<T> {
+    fn from_iter<I: IntoIterator<Item = (IntVector, T)>>(iter: I) -> Self {
+        let mut sparse = SparseGrid::new();
+        for (coord, cell) in iter {
+            sparse.set(coord, cell);
+        }
+        sparse
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use indoc::indoc;
+
+    use super::*;
+
+    #[test]
+    fn test_bounds_track_set_cells() {
+        let mut grid = SparseGrid::new();
+        assert_eq!(grid.bounds(), None);
+        grid.set(IntVector::new(2, 3), 'a');
+        grid.set(IntVector::new(-1, 5), 'b');
+        assert_eq!(
+            grid.bounds(),
+            Some(&SignedGridShape::new(
+                IntVector::new(-1, 3),
+                IntVector::new(2, 5)
+            ))
+        );
+        grid.remove(IntVector::new(-1, 5));
+        assert_eq!(
+            grid.bounds(),
+            Some(&SignedGridShape::from_point(IntVector::new(2, 3)))
+        );
+        grid.remove(IntVector::new(2, 3));
+        assert_eq!(grid.bounds(), None);
+    }
+
+    #[test]
+    fn test_format_char_grid() {
+        let grid: SparseGrid<char> = [(IntVector::new(-2, -1), '#'), (IntVector::new(1, 0), '#')]
+            .into_iter()
+            .collect();
+        let expected = indoc! {"
+            #...
+            ...#
+        "};
+        assert_eq!(grid.format_char_grid('.', |c| *c), expected);
+    }
+
+    #[test]
+    fn test_dense_round_trip() {
+        let dense = Grid::parse_char_grid(
+            indoc! {"
+                #..
+                .#.
+            "},
+            |c| Ok(if c == '#' { Some(true) } else { None }),
+        )
+        .unwrap();
+        let sparse = SparseGrid::from_dense(&dense, IntVector::new(-5, 10));
+        assert_eq!(sparse.len(), 2);
+        assert!(sparse.contains(IntVector::new(-4, 11)));
+        assert_eq!(
+            sparse
+                .to_dense()
+                .format_char_grid(|c| if c.is_some() { '#' } else { '.' }),
+            "#.\n.#\n"
+        );
+    }
+}
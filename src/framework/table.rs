@@ -0,0 +1,79 @@
+use crate::framework::prelude::*;
+
+/// Lays `rows` out under `headers` as plain text, padding each column to its widest cell.
+/// Columns flagged in `right_align` (usually the numeric ones) are right-aligned, the rest
+/// left-aligned, and trailing padding is trimmed from every line.
+///
+/// Panics if a row or `right_align` doesn't have one entry per header.
+pub fn format_table<R: AsRef<[String]>>(
+    headers: &[&str],
+    rows: &[R],
+    right_align: &[bool],
+) -> String {
+    assert_eq!(right_align.len(), headers.len(), "one alignment per column");
+    for row in rows {
+        assert_eq!(row.as_ref().len(), headers.len(), "one cell per column");
+    }
+    let widths = (0..headers.len())
+        .map(|column| {
+            rows.iter()
+                .map(|row| row.as_ref()[column].chars().count())
+                .chain([headers[column].chars().count()])
+                .max()
+                .unwrap_or(0)
+        })
+        .collect_vec();
+    let format_row = |row: &[&str]| {
+        row.iter()
+            .zip(&widths)
+            .zip(right_align)
+            .map(|((cell, width), right_align)| {
+                if *right_align {
+                    format!("{:>width$}", cell, width = width)
+                } else {
+                    format!("{:<width$}", cell, width = width)
+                }
+            })
+            .join("  ")
+            .trim_end()
+            .to_string()
+            + "\n"
+    };
+    let mut table = format_row(headers);
+    for row in rows {
+        table += &format_row(&row.as_ref().iter().map(String::as_str).collect_vec());
+    }
+    table
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use indoc::indoc;
+
+    #[test]
+    fn test_format_table() {
+        let rows = [
+            ["1".to_string(), "apple".to_string(), "x".to_string()],
+            ["100".to_string(), "fig".to_string(), "".to_string()],
+        ];
+        assert_eq!(
+            format_table(&["N", "Fruit", "Note"], &rows, &[true, false, false]),
+            indoc! {"
+                  N  Fruit  Note
+                  1  apple  x
+                100  fig
+            "}
+        );
+        assert_eq!(
+            format_table::<[String; 1]>(&["Ünïcödé"], &[], &[false]),
+            "Ünïcödé\n"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "one cell per column")]
+    fn test_mismatched_columns() {
+        format_table(&["A", "B"], &[vec!["1".to_string()]], &[true, true]);
+    }
+}
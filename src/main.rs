@@ -68,6 +68,9 @@ struct Args {
     /// Run a custom script instead of the day's code. `day` is required if this option is used.
     #[arg(short, long)]
     script: Option<String>,
+    /// Arguments for the script, after a `--`.
+    #[arg(last = true)]
+    script_args: Vec<String>,
 }
 
 fn main() {
@@ -80,7 +83,7 @@ fn main() {
             .iter()
             .find(|day| day.day_number() == day_number)
             .expect("Day not found")
-            .run_script(&script)
+            .run_script_with_args(&script, &args.script_args)
             .unwrap();
 
         if !result {